@@ -0,0 +1,38 @@
+use ark_ff::Field;
+use ark_ff_optimized::fp64::Fp;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use ministark::hash::ElementHashFn;
+use ministark::hash::HashFn;
+use ministark::hash::Sha256HashFn;
+use ministark::random::PublicCoin;
+use ministark::random::PublicCoinImpl;
+
+// compare runs with and without `--features parallel`
+const BENCHMARK_GRINDING_FACTORS: [u8; 3] = [16, 20, 24];
+
+fn grind_bench<F: Field, H: ElementHashFn<F>>(c: &mut Criterion, name: &str) {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+
+    for grinding_factor in BENCHMARK_GRINDING_FACTORS {
+        let public_coin = PublicCoinImpl::<F, H>::new(H::hash_chunks([b"grinding".as_slice()]));
+
+        group.bench_with_input(
+            BenchmarkId::new("grind_proof_of_work", grinding_factor),
+            &grinding_factor,
+            |b, &grinding_factor| {
+                b.iter(|| public_coin.grind_proof_of_work(grinding_factor).unwrap())
+            },
+        );
+    }
+}
+
+fn grind_benches(c: &mut Criterion) {
+    grind_bench::<Fp, Sha256HashFn>(c, "Sha256 grinding");
+}
+
+criterion_group!(benches, grind_benches);
+criterion_main!(benches);
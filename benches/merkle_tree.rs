@@ -10,12 +10,15 @@ use ministark::hash::ElementHashFn;
 use ministark::hash::Sha256HashFn;
 use ministark::merkle::MatrixMerkleTree;
 use ministark::merkle::MatrixMerkleTreeImpl;
+use ministark::merkle::MerkleTree;
 use ministark::utils::GpuAllocator;
 use ministark::Matrix;
 use ministark_gpu::GpuField;
 
 const BENCHMARK_TREE_DEPTH: [usize; 4] = [14, 15, 16, 17];
 
+const BENCHMARK_NUM_QUERIES: [usize; 4] = [1, 8, 32, 64];
+
 fn build_merkle_tree_bench<F: GpuField + Field, H: ElementHashFn<F>>(
     c: &mut Criterion,
     name: &str,
@@ -39,9 +42,49 @@ fn build_merkle_tree_bench<F: GpuField + Field, H: ElementHashFn<F>>(
     }
 }
 
+fn verify_merkle_proof_bench<F: GpuField + Field, H: ElementHashFn<F>>(
+    c: &mut Criterion,
+    name: &str,
+) {
+    let mut rng = ark_std::test_rng();
+    let mut group = c.benchmark_group(name);
+
+    let n = 1 << BENCHMARK_TREE_DEPTH[0];
+    let leaves: Vec<F> = (0..n).map(|_| F::rand(&mut rng)).collect();
+    let matrix = Matrix::new(vec![
+        leaves.to_vec_in(GpuAllocator),
+        leaves.to_vec_in(GpuAllocator),
+        leaves.to_vec_in(GpuAllocator),
+    ]);
+    let tree = MatrixMerkleTreeImpl::<H>::from_matrix(&matrix);
+    let root = tree.root();
+
+    for num_queries in BENCHMARK_NUM_QUERIES {
+        // spread queries across the tree
+        let row_ids = (0..num_queries).map(|i| i * 7919 % n).collect::<Vec<_>>();
+        let rows = row_ids.iter().map(|&i| [leaves[i]; 3]).collect::<Vec<_>>();
+        let proof = MatrixMerkleTree::<F>::prove_rows(&tree, &row_ids).unwrap();
+
+        group.bench_with_input(BenchmarkId::new("verify_rows", num_queries), &n, |b, _| {
+            b.iter(|| {
+                MatrixMerkleTreeImpl::<H>::verify_rows(&root, &row_ids, &rows, proof.clone())
+                    .unwrap();
+            })
+        });
+    }
+}
+
 fn build_merkle_tree_benches(c: &mut Criterion) {
     build_merkle_tree_bench::<Fp, Sha256HashFn>(c, "Sha256");
 }
 
-criterion_group!(benches, build_merkle_tree_benches);
+fn verify_merkle_proof_benches(c: &mut Criterion) {
+    verify_merkle_proof_bench::<Fp, Sha256HashFn>(c, "Sha256 verify");
+}
+
+criterion_group!(
+    benches,
+    build_merkle_tree_benches,
+    verify_merkle_proof_benches
+);
 criterion_main!(benches);
@@ -14,6 +14,7 @@ use ministark::constraints::AlgebraicItem;
 use ministark::constraints::Constraint;
 use ministark::constraints::Hint;
 use ministark::constraints::VerifierChallenge;
+use ministark::expression::Expr;
 use ministark::hints::Hints;
 use ministark::utils::FieldVariant;
 use ministark_gpu::fields::p18446744069414584321::ark::Fp;
@@ -58,22 +59,56 @@ impl AirConfig for BrainfuckAirConfig {
         ])
     }
 
-    fn constraints(trace_len: usize) -> Vec<Constraint<FieldVariant<Self::Fp, Self::Fq>>> {
+    fn constraints(
+        trace_domain: &Radix2EvaluationDomain<Fp>,
+    ) -> Vec<Constraint<FieldVariant<Self::Fp, Self::Fq>>> {
         use AlgebraicItem::*;
         let one = Constant(FieldVariant::<Fp, Fq3>::Fp(Fp::one()));
-        let trace_xs = Radix2EvaluationDomain::<Fp>::new(trace_len).unwrap();
-        let first_trace_x = Constant(FieldVariant::<Fp, Fq3>::Fp(trace_xs.element(0)));
-        let last_trace_x = Constant(FieldVariant::Fp(trace_xs.element(trace_len - 1)));
+        let trace_len = trace_domain.size();
+        let first_trace_x = Constant(FieldVariant::<Fp, Fq3>::Fp(trace_domain.element(0)));
+        let last_trace_x = Constant(FieldVariant::Fp(trace_domain.element(trace_len - 1)));
 
         let transition_constraints = [
-            tables::ProcessorBaseColumn::transition_constraints(),
-            tables::ProcessorExtensionColumn::transition_constraints(),
-            tables::MemoryBaseColumn::transition_constraints(),
-            tables::MemoryExtensionColumn::transition_constraints(),
-            tables::InstructionBaseColumn::transition_constraints(),
-            tables::InstructionExtensionColumn::transition_constraints(),
-            tables::InputExtensionColumn::transition_constraints(),
-            tables::OutputExtensionColumn::transition_constraints(),
+            checked_transition_constraints(
+                "processor",
+                tables::ProcessorBaseColumn::transition_constraints(),
+                tables::ProcessorBaseColumn::MAX_TRANSITION_DEGREE,
+            ),
+            checked_transition_constraints(
+                "processor extension",
+                tables::ProcessorExtensionColumn::transition_constraints(),
+                tables::ProcessorExtensionColumn::MAX_TRANSITION_DEGREE,
+            ),
+            checked_transition_constraints(
+                "memory",
+                tables::MemoryBaseColumn::transition_constraints(),
+                tables::MemoryBaseColumn::MAX_TRANSITION_DEGREE,
+            ),
+            checked_transition_constraints(
+                "memory extension",
+                tables::MemoryExtensionColumn::transition_constraints(),
+                tables::MemoryExtensionColumn::MAX_TRANSITION_DEGREE,
+            ),
+            checked_transition_constraints(
+                "instruction",
+                tables::InstructionBaseColumn::transition_constraints(),
+                tables::InstructionBaseColumn::MAX_TRANSITION_DEGREE,
+            ),
+            checked_transition_constraints(
+                "instruction extension",
+                tables::InstructionExtensionColumn::transition_constraints(),
+                tables::InstructionExtensionColumn::MAX_TRANSITION_DEGREE,
+            ),
+            checked_transition_constraints(
+                "input extension",
+                tables::InputExtensionColumn::transition_constraints(),
+                tables::InputExtensionColumn::MAX_TRANSITION_DEGREE,
+            ),
+            checked_transition_constraints(
+                "output extension",
+                tables::OutputExtensionColumn::transition_constraints(),
+                tables::OutputExtensionColumn::MAX_TRANSITION_DEGREE,
+            ),
         ]
         .into_iter()
         .flatten()
@@ -125,6 +160,25 @@ impl AirConfig for BrainfuckAirConfig {
     }
 }
 
+// Checks the transition constraints of a table don't exceed the degree the
+// table declares. A constraint of higher degree than expected still proves but
+// may need a larger blowup factor than the proof options provide.
+fn checked_transition_constraints(
+    table: &str,
+    constraints: Vec<Expr<AlgebraicItem<FieldVariant<Fp, Fq3>>>>,
+    max_degree: usize,
+) -> Vec<Expr<AlgebraicItem<FieldVariant<Fp, Fq3>>>> {
+    for (i, constraint) in constraints.iter().enumerate() {
+        let degree = Constraint::new(constraint.clone()).trace_degree();
+        debug_assert!(
+            degree <= max_degree,
+            "{table} transition constraint {i} has degree {degree} but at most {max_degree} is \
+             declared"
+        );
+    }
+    constraints
+}
+
 // Computes the evaluation terminal for the instruction table
 fn compute_instruction_evaluation_argument(source_code: &str, challenges: &Challenges<Fq3>) -> Fq3 {
     use Challenge::Eta;
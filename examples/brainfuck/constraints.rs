@@ -8,6 +8,7 @@ use crate::tables::MemoryBaseColumn;
 use crate::tables::MemoryExtensionColumn;
 use crate::tables::OutputBaseColumn;
 use crate::tables::OutputExtensionColumn;
+use crate::tables::PaddedSortedTable;
 use crate::tables::ProcessorBaseColumn;
 use crate::tables::ProcessorExtensionColumn;
 use crate::vm::OpCode;
@@ -23,6 +24,8 @@ use ministark_gpu::GpuFftField;
 use std::borrow::Borrow;
 
 impl ProcessorBaseColumn {
+    pub const MAX_TRANSITION_DEGREE: usize = 11;
+
     pub fn boundary_constraints<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
     ) -> Vec<Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>> {
         use ProcessorBaseColumn::*;
@@ -127,6 +130,8 @@ impl ProcessorBaseColumn {
 }
 
 impl ProcessorExtensionColumn {
+    pub const MAX_TRANSITION_DEGREE: usize = 9;
+
     pub fn boundary_constraints<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
     ) -> Vec<Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>> {
         use ProcessorExtensionColumn::*;
@@ -277,6 +282,8 @@ impl ProcessorExtensionColumn {
 }
 
 impl MemoryBaseColumn {
+    pub const MAX_TRANSITION_DEGREE: usize = 2;
+
     pub fn boundary_constraints<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
     ) -> Vec<Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>> {
         use MemoryBaseColumn::*;
@@ -297,19 +304,19 @@ impl MemoryBaseColumn {
             //
             // 3. if the memory pointer increases by one, then the memory value must be set to zero
             (Mp.next() - Mp.curr()) * MemVal.next(),
-            // 4. dummy has to be zero or one
-            (Dummy.next() - one) * Dummy.next(),
-            // 5. if dummy is set the memory pointer can not change
-            (Mp.next() - Mp.curr()) * Dummy.curr(),
-            // 6. if dummy is set the memory value can not change
-            (MemVal.next() - MemVal.curr()) * Dummy.curr(),
-            // 7. if the memory pointer remains the same, then the cycle has to increase by one
+            // 4. if the memory pointer remains the same, then the cycle has to increase by one
             (Mp.next() - Mp.curr() - one) * (Cycle.next() - Cycle.curr() - one),
         ]
+        .into_iter()
+        // 5. dummy has to be zero or one and if set the memory pointer and value can not change
+        .chain(Self::dummy_constraints())
+        .collect()
     }
 }
 
 impl MemoryExtensionColumn {
+    pub const MAX_TRANSITION_DEGREE: usize = 3;
+
     pub fn transition_constraints<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
     ) -> Vec<Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>> {
         use Challenge::Beta;
@@ -331,6 +338,8 @@ impl MemoryExtensionColumn {
 }
 
 impl InstructionBaseColumn {
+    pub const MAX_TRANSITION_DEGREE: usize = 2;
+
     pub fn boundary_constraints<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
     ) -> Vec<Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>> {
         use InstructionBaseColumn::*;
@@ -363,6 +372,8 @@ impl InstructionBaseColumn {
 }
 
 impl InstructionExtensionColumn {
+    pub const MAX_TRANSITION_DEGREE: usize = 9;
+
     pub fn boundary_constraints<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
     ) -> Vec<Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>> {
         use Challenge::A;
@@ -424,6 +435,8 @@ impl InstructionExtensionColumn {
 }
 
 impl InputExtensionColumn {
+    pub const MAX_TRANSITION_DEGREE: usize = 1;
+
     pub fn boundary_constraints<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
     ) -> Vec<Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>> {
         use InputBaseColumn::*;
@@ -450,6 +463,8 @@ impl InputExtensionColumn {
 }
 
 impl OutputExtensionColumn {
+    pub const MAX_TRANSITION_DEGREE: usize = 1;
+
     pub fn boundary_constraints<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
     ) -> Vec<Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>> {
         use OutputBaseColumn::*;
@@ -5,6 +5,7 @@ use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
 use ministark::hash::HashFn;
 use ministark::hash::Sha256HashFn;
+use ministark::merkle::public_input_root;
 use ministark::merkle::MatrixMerkleTreeImpl;
 use ministark::random::PublicCoin;
 use ministark::random::PublicCoinImpl;
@@ -70,8 +71,18 @@ impl Stark for BrainfuckClaim {
     type Trace = BrainfuckTrace;
 
     fn gen_public_coin(&self, air: &ministark::Air<Self::AirConfig>) -> Self::PublicCoin {
+        let BrainfuckClaim {
+            source_code,
+            input,
+            output,
+        } = air.public_inputs();
         let mut seed = Vec::new();
-        air.public_inputs().serialize_compressed(&mut seed).unwrap();
+        // programs can be large so only their root is absorbed
+        public_input_root::<Sha256HashFn>(source_code.as_bytes())
+            .serialize_compressed(&mut seed)
+            .unwrap();
+        input.serialize_compressed(&mut seed).unwrap();
+        output.serialize_compressed(&mut seed).unwrap();
         air.trace_len().serialize_compressed(&mut seed).unwrap();
         air.options().serialize_compressed(&mut seed).unwrap();
         PublicCoinImpl::new(Sha256HashFn::hash_chunks([&*seed]))
@@ -141,7 +152,13 @@ fn prove(source_code_path: PathBuf, input: String, output_path: PathBuf) {
     };
 
     let now = Instant::now();
-    let proof = pollster::block_on(claim.prove(OPTIONS, trace)).unwrap();
+    let proof = match pollster::block_on(claim.prove(OPTIONS, trace)) {
+        Ok(proof) => proof,
+        Err(err) => {
+            eprintln!("Failed to generate proof: {err:?}");
+            std::process::exit(1);
+        }
+    };
     println!("Proof generated in: {:.0?}", now.elapsed());
     let security_level = proof.security_level_bits();
     println!("Proof security (conjectured): {security_level}bit",);
@@ -160,14 +177,19 @@ fn prove(source_code_path: PathBuf, input: String, output_path: PathBuf) {
 fn verify(source_code_path: PathBuf, input: String, output: String, proof_path: PathBuf) {
     let source_code = fs::read_to_string(source_code_path).unwrap();
     let proof_bytes = fs::read(proof_path).unwrap();
-    let (execution_info, proof): (BrainfuckClaim, Proof<BrainfuckClaim>) =
+    // the claim bundled with the proof is ignored. The input and output
+    // streams are bound to the proof by the IO tables' evaluation arguments
+    // so verifying against a different output fails.
+    let (_, proof): (BrainfuckClaim, Proof<BrainfuckClaim>) =
         <_>::deserialize_compressed(proof_bytes.as_slice()).unwrap();
-    assert_eq!(input.as_bytes(), execution_info.input);
-    assert_eq!(output.as_bytes(), execution_info.output);
-    assert_eq!(source_code, execution_info.source_code);
+    let claim = BrainfuckClaim {
+        source_code,
+        input: input.into_bytes(),
+        output: output.into_bytes(),
+    };
 
     let now = Instant::now();
-    execution_info
+    claim
         .verify(proof, SECURITY_LEVEL)
         .expect("verification failed");
     println!("Proof verified in: {:?}", now.elapsed());
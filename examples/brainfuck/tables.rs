@@ -1,27 +1,83 @@
+use ark_ff::FftField;
+use ark_ff::Field;
+use ministark::constraints::AlgebraicItem;
+use ministark::constraints::ExecutionTraceColumn;
+use ministark::expression::Expr;
+use ministark::utils::FieldVariant;
+use ministark::StarkExtensionOf;
+use ministark_gpu::GpuFftField;
+
 pub trait BrainfuckColumn {
     const FIRST_TRACE_COL_INDEX: usize;
     const LAST_TRACE_COL_INDEX: usize;
     const NUM_TRACE_COLUMNS: usize = Self::LAST_TRACE_COL_INDEX - Self::FIRST_TRACE_COL_INDEX + 1;
+    /// Challenges the columns are computed from. Challenges aren't split into
+    /// disjoint ranges: both tables in a permutation or evaluation argument
+    /// must use the argument's challenges e.g. the processor and memory
+    /// tables share `D`, `E`, `F` and `Beta`.
+    const CHALLENGES: &'static [Challenge] = &[];
 }
 
-#[derive(Clone, Copy)]
-pub enum Challenge {
-    A,
-    B,
-    C,
-    D,
-    E,
-    F,
-    Alpha,
-    Beta,
-    Gamma,
-    Delta,
-    Eta,
-}
-
-impl ministark::constraints::VerifierChallenge for Challenge {
-    fn index(&self) -> usize {
-        *self as usize
+/// A table sorted for a consistency argument (e.g. memory sorted by address)
+/// where filler rows are marked with a boolean dummy column. Dummy rows are
+/// used both to smooth over gaps in the cycle count and to pad the table to a
+/// power of two. Dummy rows copy the frozen columns of the previous row and
+/// advance the cycle by one so the sorted order is maintained.
+pub trait PaddedSortedTable: BrainfuckColumn + ExecutionTraceColumn + Copy + 'static {
+    const CYCLE: Self;
+    const DUMMY: Self;
+    /// Columns that can not change on the row after a dummy row
+    const FROZEN: &'static [Self];
+
+    /// Returns the column's position within a row of this table
+    fn position(self) -> usize {
+        self.index() - Self::FIRST_TRACE_COL_INDEX
+    }
+
+    /// Returns a dummy row that follows `prev`
+    fn dummy_row<F: Field, const N: usize>(prev: &[F; N]) -> [F; N] {
+        let mut row = [F::zero(); N];
+        row[Self::CYCLE.position()] = prev[Self::CYCLE.position()] + F::one();
+        for column in Self::FROZEN {
+            row[column.position()] = prev[column.position()];
+        }
+        row[Self::DUMMY.position()] = F::one();
+        row
+    }
+
+    /// Appends dummy rows until there are `n` rows
+    fn pad_rows<F: Field, const N: usize>(rows: &mut Vec<[F; N]>, n: usize) {
+        while rows.len() < n {
+            let new_row = Self::dummy_row(rows.last().unwrap());
+            rows.push(new_row);
+        }
+    }
+
+    /// Returns the "dummy is boolean" and "dummy freezes changes" constraints
+    fn dummy_constraints<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+    ) -> Vec<Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>> {
+        let one = AlgebraicItem::Constant(FieldVariant::Fp(Fp::one()));
+        let mut constraints = vec![(Self::DUMMY.next() - one) * Self::DUMMY.next()];
+        for column in Self::FROZEN {
+            constraints.push((column.next() - column.curr()) * Self::DUMMY.curr());
+        }
+        constraints
+    }
+}
+
+ministark::air_challenges! {
+    pub enum Challenge {
+        A,
+        B,
+        C,
+        D,
+        E,
+        F,
+        Alpha,
+        Beta,
+        Gamma,
+        Delta,
+        Eta,
     }
 }
 
@@ -136,27 +192,50 @@ impl BrainfuckColumn for ProcessorExtensionColumn {
     const FIRST_TRACE_COL_INDEX: usize = OutputBaseColumn::LAST_TRACE_COL_INDEX + 1;
     const LAST_TRACE_COL_INDEX: usize =
         Self::FIRST_TRACE_COL_INDEX + Self::OutputEvaluation as usize;
+    const CHALLENGES: &'static [Challenge] = &[
+        Challenge::A,
+        Challenge::B,
+        Challenge::C,
+        Challenge::Alpha,
+        Challenge::D,
+        Challenge::E,
+        Challenge::F,
+        Challenge::Beta,
+        Challenge::Gamma,
+        Challenge::Delta,
+    ];
 }
 
 impl BrainfuckColumn for MemoryExtensionColumn {
     const FIRST_TRACE_COL_INDEX: usize = ProcessorExtensionColumn::LAST_TRACE_COL_INDEX + 1;
     const LAST_TRACE_COL_INDEX: usize = Self::FIRST_TRACE_COL_INDEX + Self::Permutation as usize;
+    const CHALLENGES: &'static [Challenge] =
+        &[Challenge::D, Challenge::E, Challenge::F, Challenge::Beta];
 }
 
 impl BrainfuckColumn for InstructionExtensionColumn {
     const FIRST_TRACE_COL_INDEX: usize = MemoryExtensionColumn::LAST_TRACE_COL_INDEX + 1;
     const LAST_TRACE_COL_INDEX: usize =
         Self::FIRST_TRACE_COL_INDEX + Self::ProgramEvaluation as usize;
+    const CHALLENGES: &'static [Challenge] = &[
+        Challenge::A,
+        Challenge::B,
+        Challenge::C,
+        Challenge::Alpha,
+        Challenge::Eta,
+    ];
 }
 
 impl BrainfuckColumn for InputExtensionColumn {
     const FIRST_TRACE_COL_INDEX: usize = InstructionExtensionColumn::LAST_TRACE_COL_INDEX + 1;
     const LAST_TRACE_COL_INDEX: usize = Self::FIRST_TRACE_COL_INDEX + Self::Evaluation as usize;
+    const CHALLENGES: &'static [Challenge] = &[Challenge::Gamma];
 }
 
 impl BrainfuckColumn for OutputExtensionColumn {
     const FIRST_TRACE_COL_INDEX: usize = InputExtensionColumn::LAST_TRACE_COL_INDEX + 1;
     const LAST_TRACE_COL_INDEX: usize = Self::FIRST_TRACE_COL_INDEX + Self::Evaluation as usize;
+    const CHALLENGES: &'static [Challenge] = &[Challenge::Delta];
 }
 
 macro_rules! impl_column {
@@ -175,6 +254,12 @@ impl_column!(ProcessorExtensionColumn);
 impl_column!(MemoryBaseColumn);
 impl_column!(MemoryExtensionColumn);
 
+impl PaddedSortedTable for MemoryBaseColumn {
+    const CYCLE: Self = Self::Cycle;
+    const DUMMY: Self = Self::Dummy;
+    const FROZEN: &'static [Self] = &[Self::Mp, Self::MemVal];
+}
+
 impl_column!(InstructionBaseColumn);
 impl_column!(InstructionExtensionColumn);
 
@@ -183,3 +268,107 @@ impl_column!(InputExtensionColumn);
 
 impl_column!(OutputBaseColumn);
 impl_column!(OutputExtensionColumn);
+
+#[cfg(test)]
+mod tests {
+    use super::BrainfuckColumn;
+    use super::Challenge;
+    use super::InputExtensionColumn;
+    use super::InstructionExtensionColumn;
+    use super::MemoryExtensionColumn;
+    use super::OutputExtensionColumn;
+    use super::ProcessorExtensionColumn;
+    use ministark::constraints::AlgebraicItem;
+    use ministark::constraints::VerifierChallenge;
+    use ministark::expression::Expr;
+    use ministark::utils::FieldVariant;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fq3;
+    use std::collections::BTreeSet;
+
+    type Constraints = Vec<Expr<AlgebraicItem<FieldVariant<Fp, Fq3>>>>;
+
+    /// Returns the indices of the challenges `constraints` read
+    fn challenges_read_by(constraints: &[Constraints]) -> BTreeSet<usize> {
+        let mut indices = BTreeSet::new();
+        for constraint in constraints.concat() {
+            constraint.traverse(&mut |node| {
+                if let Expr::Leaf(AlgebraicItem::Challenge(i)) = node {
+                    indices.insert(*i);
+                }
+            });
+        }
+        indices
+    }
+
+    fn indices(challenges: &[Challenge]) -> BTreeSet<usize> {
+        challenges.iter().map(VerifierChallenge::index).collect()
+    }
+
+    #[test]
+    fn every_challenge_is_consumed_by_a_table() {
+        let tables = [
+            ProcessorExtensionColumn::CHALLENGES,
+            MemoryExtensionColumn::CHALLENGES,
+            InstructionExtensionColumn::CHALLENGES,
+            InputExtensionColumn::CHALLENGES,
+            OutputExtensionColumn::CHALLENGES,
+        ];
+        let consumed = tables.concat().into_iter().collect::<BTreeSet<_>>();
+
+        assert_eq!(Challenge::NUM_CHALLENGES, consumed.len());
+    }
+
+    #[test]
+    fn permutation_arguments_share_their_challenges() {
+        let processor = ProcessorExtensionColumn::CHALLENGES;
+
+        assert!(MemoryExtensionColumn::CHALLENGES
+            .iter()
+            .all(|c| processor.contains(c)));
+        assert!(InstructionExtensionColumn::CHALLENGES
+            .iter()
+            .filter(|&&c| c != Challenge::Eta)
+            .all(|c| processor.contains(c)));
+    }
+
+    #[test]
+    fn table_challenges_match_their_constraints() {
+        assert_eq!(
+            indices(ProcessorExtensionColumn::CHALLENGES),
+            challenges_read_by(&[
+                ProcessorExtensionColumn::boundary_constraints(),
+                ProcessorExtensionColumn::transition_constraints(),
+                ProcessorExtensionColumn::terminal_constraints(),
+            ])
+        );
+        assert_eq!(
+            indices(MemoryExtensionColumn::CHALLENGES),
+            challenges_read_by(&[MemoryExtensionColumn::transition_constraints()])
+        );
+        assert_eq!(
+            indices(InstructionExtensionColumn::CHALLENGES),
+            challenges_read_by(&[
+                InstructionExtensionColumn::boundary_constraints(),
+                InstructionExtensionColumn::transition_constraints(),
+                InstructionExtensionColumn::terminal_constraints(),
+            ])
+        );
+        assert_eq!(
+            indices(InputExtensionColumn::CHALLENGES),
+            challenges_read_by(&[
+                InputExtensionColumn::boundary_constraints(),
+                InputExtensionColumn::transition_constraints(),
+                InputExtensionColumn::terminal_constraints(),
+            ])
+        );
+        assert_eq!(
+            indices(OutputExtensionColumn::CHALLENGES),
+            challenges_read_by(&[
+                OutputExtensionColumn::boundary_constraints(),
+                OutputExtensionColumn::transition_constraints(),
+                OutputExtensionColumn::terminal_constraints(),
+            ])
+        );
+    }
+}
@@ -3,6 +3,7 @@ use crate::tables::InputBaseColumn;
 use crate::tables::InstructionBaseColumn;
 use crate::tables::MemoryBaseColumn;
 use crate::tables::OutputBaseColumn;
+use crate::tables::PaddedSortedTable;
 use crate::tables::ProcessorBaseColumn;
 use crate::trace::into_columns;
 use crate::BrainfuckTrace;
@@ -157,6 +158,12 @@ pub fn simulate(
             row
         });
 
+        // Each executed instruction gets its own instruction table row so an
+        // instruction executed many times appears that many times. This keeps
+        // the processor to instruction table argument an exact permutation
+        // rather than a lookup with multiplicities. The program rows above are
+        // matched against the program with an evaluation argument that only
+        // counts the first row at each address.
         instruction_rows.push({
             use InstructionBaseColumn::*;
             let mut row = [Fp::zero(); InstructionBaseColumn::NUM_TRACE_COLUMNS];
@@ -259,7 +266,7 @@ pub fn simulate(
     };
 
     pad_processor_rows(&mut processor_rows, padding_len);
-    pad_memory_rows(&mut memory_rows, padding_len);
+    MemoryBaseColumn::pad_rows(&mut memory_rows, padding_len);
     pad_instruction_rows(&mut instruction_rows, padding_len);
     pad_input_rows(&mut input_rows, padding_len);
     pad_output_rows(&mut output_rows, padding_len);
@@ -296,19 +303,6 @@ fn pad_processor_rows(rows: &mut Vec<[Fp; ProcessorBaseColumn::NUM_TRACE_COLUMNS
     }
 }
 
-fn pad_memory_rows(rows: &mut Vec<[Fp; MemoryBaseColumn::NUM_TRACE_COLUMNS]>, n: usize) {
-    use MemoryBaseColumn::*;
-    while rows.len() < n {
-        let last_row = rows.last().unwrap();
-        let mut new_row = [Fp::zero(); MemoryBaseColumn::NUM_TRACE_COLUMNS];
-        new_row[Cycle as usize] = last_row[Cycle as usize] + Fp::one();
-        new_row[Mp as usize] = last_row[Mp as usize];
-        new_row[MemVal as usize] = last_row[MemVal as usize];
-        new_row[Dummy as usize] = Fp::one();
-        rows.push(new_row);
-    }
-}
-
 fn pad_instruction_rows(rows: &mut Vec<[Fp; InstructionBaseColumn::NUM_TRACE_COLUMNS]>, n: usize) {
     use InstructionBaseColumn::*;
     let last_ip = rows.last().unwrap()[Ip as usize];
@@ -366,11 +360,7 @@ fn derive_memory_rows(
         if curr[Mp as usize] == next[Mp as usize]
             && curr[Cycle as usize] + Fp::one() != next[Cycle as usize]
         {
-            let mut dummy_row = [Fp::zero(); MemoryBaseColumn::NUM_TRACE_COLUMNS];
-            dummy_row[Cycle as usize] = curr[Cycle as usize] + Fp::one();
-            dummy_row[Mp as usize] = curr[Mp as usize];
-            dummy_row[MemVal as usize] = curr[MemVal as usize];
-            dummy_row[Dummy as usize] = Fp::one();
+            let dummy_row = MemoryBaseColumn::dummy_row(curr);
             memory_rows.insert(i + 1, dummy_row)
         }
 
@@ -15,14 +15,13 @@ use ministark::merkle::MatrixMerkleTreeImpl;
 use ministark::random::PublicCoin;
 use ministark::random::PublicCoinImpl;
 use ministark::stark::Stark;
+use ministark::trace::TraceBuilder;
 use ministark::utils::FieldVariant;
-use ministark::utils::GpuAllocator;
 use ministark::utils::SerdeOutput;
 use ministark::Matrix;
 use ministark::ProofOptions;
 use ministark::Trace;
 use ministark_gpu::fields::p18446744069414584321::ark::Fp;
-use num_traits::Pow;
 use sha2::Sha256;
 use std::time::Instant;
 
@@ -70,43 +69,35 @@ impl AirConfig for FibAirConfig {
         )])
     }
 
-    fn constraints(trace_len: usize) -> Vec<Constraint<FieldVariant<Self::Fp, Self::Fq>>> {
+    fn constraints(
+        trace_domain: &Radix2EvaluationDomain<Fp>,
+    ) -> Vec<Constraint<FieldVariant<Self::Fp, Self::Fq>>> {
         use AlgebraicItem::*;
-        let trace_xs = Radix2EvaluationDomain::<Fp>::new(trace_len).unwrap();
+        let trace_len = trace_domain.size();
         // NOTE: =1
-        let first_trace_x = Constant(FieldVariant::Fp(trace_xs.element(0)));
-        // NOTE: =trace_xs.group_gen_inv()
-        let last_trace_x = Constant(FieldVariant::Fp(trace_xs.element(trace_len - 1)));
-        let one = Constant(FieldVariant::Fp(Fp::one()));
-
-        let boundary_constraints = {
-            let v0 = AlgebraicItem::Constant(FieldVariant::Fp(Fp::one()));
-            let v1 = v0 + v0;
-            let v2 = &v1 * v0;
-            let v3 = &v1 * &v2;
-            let v4 = &v2 * &v3;
-            let v5 = &v3 * &v4;
-            let v6 = &v4 * &v5;
-            let v7 = &v5 * &v6;
-
-            vec![
-                0.curr() - v0,
-                1.curr() - v1,
-                2.curr() - v2,
-                3.curr() - v3,
-                4.curr() - v4,
-                5.curr() - v5,
-                6.curr() - v6,
-                7.curr() - v7,
-            ]
-        }
+        let first_trace_x = Constant(FieldVariant::Fp(trace_domain.element(0)));
+        // NOTE: =trace_domain.group_gen_inv()
+        let last_trace_x = Constant(FieldVariant::Fp(trace_domain.element(trace_len - 1)));
+
+        let boundary_constraints = vec![
+            0.curr() - AlgebraicItem::from(1),
+            1.curr() - AlgebraicItem::from(2),
+            2.curr() - AlgebraicItem::from(2),
+            3.curr() - AlgebraicItem::from(4),
+            4.curr() - AlgebraicItem::from(8),
+            5.curr() - AlgebraicItem::from(32),
+            6.curr() - AlgebraicItem::from(256),
+            7.curr() - AlgebraicItem::from(8192),
+        ]
         .into_iter()
         .map(|constraint| {
             // ensure constraint holds in the first row
             // symbolically divide `(x - t_0)`
             constraint / (X - first_trace_x)
-        });
+        })
+        .map(Constraint::new);
 
+        // ensure constraints hold in all rows except the last
         let transition_constraints = vec![
             0.next() - 6.curr() * 7.curr(),
             1.next() - 7.curr() * 0.next(),
@@ -118,13 +109,7 @@ impl AirConfig for FibAirConfig {
             7.next() - 5.next() * 6.next(),
         ]
         .into_iter()
-        .map(|constraint| {
-            // ensure constraints hold in all rows except the last
-            // multiply by `(x - t_(n-1))` to remove the last term
-            // NOTE: `x^trace_len - 1 = (x - t_0)(x - t_1)...(x - t_(n-1))`
-            // NOTE: `t^(n-1) = t^(-1)`
-            constraint * ((X - last_trace_x) / (X.pow(trace_len) - one))
-        });
+        .map(|constraint| Constraint::transition(constraint, trace_domain));
 
         let terminal_constraints =
             vec![7.curr() - AlgebraicItem::Hint(FibHint::ClaimedNthFibNum as usize)]
@@ -133,12 +118,12 @@ impl AirConfig for FibAirConfig {
                     // ensure constraint holds in the last row
                     // symbolically divide `(x - t_0)`
                     constraint / (X - last_trace_x)
-                });
+                })
+                .map(Constraint::new);
 
         boundary_constraints
             .chain(terminal_constraints)
             .chain(transition_constraints)
-            .map(Constraint::new)
             .collect()
     }
 }
@@ -174,51 +159,29 @@ impl Stark for FibClaim {
 
 fn gen_trace(n: usize) -> FibTrace {
     assert!(n.is_power_of_two());
-    assert!(n > 8);
-
-    let num_rows = n / 8;
-
-    let mut col0 = Vec::with_capacity_in(num_rows, GpuAllocator);
-    let mut col1 = Vec::with_capacity_in(num_rows, GpuAllocator);
-    let mut col2 = Vec::with_capacity_in(num_rows, GpuAllocator);
-    let mut col3 = Vec::with_capacity_in(num_rows, GpuAllocator);
-    let mut col4 = Vec::with_capacity_in(num_rows, GpuAllocator);
-    let mut col5 = Vec::with_capacity_in(num_rows, GpuAllocator);
-    let mut col6 = Vec::with_capacity_in(num_rows, GpuAllocator);
-    let mut col7 = Vec::with_capacity_in(num_rows, GpuAllocator);
-
-    let mut v0 = Fp::one();
-    let mut v1 = v0 + v0;
-    let mut v2 = v0 * v1;
-    let mut v3 = v1 * v2;
-    let mut v4 = v2 * v3;
-    let mut v5 = v3 * v4;
-    let mut v6 = v4 * v5;
-    let mut v7 = v5 * v6;
-
-    for _ in 0..num_rows {
-        col0.push(v0);
-        col1.push(v1);
-        col2.push(v2);
-        col3.push(v3);
-        col4.push(v4);
-        col5.push(v5);
-        col6.push(v6);
-        col7.push(v7);
-
-        v0 = v6 * v7;
-        v1 = v7 * v0;
-        v2 = v0 * v1;
-        v3 = v1 * v2;
-        v4 = v2 * v3;
-        v5 = v3 * v4;
-        v6 = v4 * v5;
-        v7 = v5 * v6;
-    }
-
-    FibTrace(Matrix::new(vec![
-        col0, col1, col2, col3, col4, col5, col6, col7,
-    ]))
+    assert!(n / 8 >= ministark::Air::<FibAirConfig>::min_trace_len(OPTIONS));
+
+    let v0 = Fp::one();
+    let v1 = v0 + v0;
+    let v2 = v0 * v1;
+    let v3 = v1 * v2;
+    let v4 = v2 * v3;
+    let v5 = v3 * v4;
+    let v6 = v4 * v5;
+    let v7 = v5 * v6;
+
+    let matrix = TraceBuilder::new([v0, v1, v2, v3, v4, v5, v6, v7]).build(n / 8, |row| {
+        let v0 = row[6] * row[7];
+        let v1 = row[7] * v0;
+        let v2 = v0 * v1;
+        let v3 = v1 * v2;
+        let v4 = v2 * v3;
+        let v5 = v3 * v4;
+        let v6 = v4 * v5;
+        let v7 = v5 * v6;
+        [v0, v1, v2, v3, v4, v5, v6, v7]
+    });
+    FibTrace(matrix)
 }
 
 const SECURITY_LEVEL: u32 = 30;
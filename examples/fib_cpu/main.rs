@@ -0,0 +1,148 @@
+//! Minimal example that proves and verifies a small Fibonacci trace on the
+//! CPU. Unlike the other examples it doesn't enable any unstable features or
+//! touch the GPU allocator. miniSTARK itself still needs a nightly toolchain:
+//!
+//! ```bash
+//! cargo +nightly run --example fib_cpu --no-default-features
+//! ```
+
+use ark_ff::One;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use ark_serialize::CanonicalSerialize;
+use ministark::air::AirConfig;
+use ministark::challenges::Challenges;
+use ministark::constraints::row_divisor;
+use ministark::constraints::AlgebraicItem;
+use ministark::constraints::Constraint;
+use ministark::constraints::ExecutionTraceColumn;
+use ministark::hash::HashFn;
+use ministark::hash::Sha256HashFn;
+use ministark::hints::Hints;
+use ministark::merkle::MatrixMerkleTreeImpl;
+use ministark::random::PublicCoin;
+use ministark::random::PublicCoinImpl;
+use ministark::stark::Stark;
+use ministark::trace::TraceBuilder;
+use ministark::utils::FieldVariant;
+use ministark::utils::SerdeOutput;
+use ministark::Air;
+use ministark::Matrix;
+use ministark::ProofOptions;
+use ministark::Trace;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use num_traits::Pow;
+use sha2::Sha256;
+
+ministark::air_columns! {
+    enum FibColumn {
+        base: [A, B],
+        extension: [],
+    }
+}
+
+struct FibTrace(Matrix<Fp>);
+
+impl Trace for FibTrace {
+    type Fp = Fp;
+    type Fq = Fp;
+
+    fn base_columns(&self) -> &Matrix<Self::Fp> {
+        &self.0
+    }
+}
+
+struct FibAirConfig;
+
+impl AirConfig for FibAirConfig {
+    const NUM_BASE_COLUMNS: usize = FibColumn::NUM_BASE_COLUMNS;
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = Fp;
+
+    fn gen_hints(_trace_len: usize, result: &Fp, _: &Challenges<Fp>) -> Hints<Fp> {
+        Hints::new(vec![(0, *result)])
+    }
+
+    fn constraints(
+        trace_domain: &Radix2EvaluationDomain<Fp>,
+    ) -> Vec<Constraint<FieldVariant<Fp, Fp>>> {
+        use AlgebraicItem::*;
+        use FibColumn::*;
+        let last_row = trace_domain.size() - 1;
+
+        // both columns start at `1`
+        let boundary_constraints = [
+            A.curr() - AlgebraicItem::from(1),
+            B.curr() - AlgebraicItem::from(1),
+        ]
+        .into_iter()
+        .map(|constraint| Constraint::new(constraint / row_divisor(trace_domain, 0)));
+
+        // each row holds the next two Fibonacci numbers
+        let transition_constraints = [
+            A.next() - (A.curr() + B.curr()),
+            B.next() - (A.next() + B.curr()),
+        ]
+        .into_iter()
+        .map(|constraint| Constraint::transition(constraint, trace_domain));
+
+        // the last value is the claimed result
+        let terminal_constraint =
+            Constraint::new((B.curr() - Hint(0)) / row_divisor(trace_domain, last_row));
+
+        boundary_constraints
+            .chain(transition_constraints)
+            .chain([terminal_constraint])
+            .collect()
+    }
+}
+
+struct FibClaim(Fp);
+
+impl Stark for FibClaim {
+    type Fp = Fp;
+    type Fq = Fp;
+    type AirConfig = FibAirConfig;
+    type Digest = SerdeOutput<Sha256>;
+    type PublicCoin = PublicCoinImpl<Fp, Sha256HashFn>;
+    type MerkleTree = MatrixMerkleTreeImpl<Sha256HashFn>;
+    type Witness = FibTrace;
+    type Trace = FibTrace;
+
+    fn get_public_inputs(&self) -> Fp {
+        self.0
+    }
+
+    fn generate_trace(&self, witness: FibTrace) -> FibTrace {
+        witness
+    }
+
+    fn gen_public_coin(&self, air: &Air<FibAirConfig>) -> Self::PublicCoin {
+        let mut seed = Vec::new();
+        air.public_inputs().serialize_compressed(&mut seed).unwrap();
+        air.trace_len().serialize_compressed(&mut seed).unwrap();
+        air.options().serialize_compressed(&mut seed).unwrap();
+        PublicCoinImpl::new(Sha256HashFn::hash_chunks([&*seed]))
+    }
+}
+
+const SECURITY_LEVEL: u32 = 30;
+const OPTIONS: ProofOptions = ProofOptions::new(32, 4, 8, 2, 4);
+
+fn main() {
+    let matrix = TraceBuilder::new([Fp::one(), Fp::one()]).build(1024, |&[a, b]| {
+        let a = a + b;
+        [a, a + b]
+    });
+    let result = *matrix.0[FibColumn::B.index()].last().unwrap();
+    let claim = FibClaim(result);
+
+    let proof = pollster::block_on(claim.prove(OPTIONS, FibTrace(matrix))).expect("prover failed");
+    println!("Proof size: {} bytes", proof.compressed_size());
+
+    claim
+        .verify(proof, SECURITY_LEVEL)
+        .expect("verification failed");
+    println!("Proof verified");
+}
@@ -4,6 +4,7 @@ use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
 use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
 use ark_ff::One;
+use ark_poly::Radix2EvaluationDomain;
 use ministark::air::AirConfig;
 use ministark::constraints::Constraint;
 use ministark::utils::FieldVariant;
@@ -25,7 +26,9 @@ impl AirConfig for RescueAirConfig {
     type Fq = Fp;
     type PublicInputs = ();
 
-    fn constraints(_trace_len: usize) -> Vec<Constraint<FieldVariant<Self::Fp, Self::Fq>>> {
+    fn constraints(
+        _trace_domain: &Radix2EvaluationDomain<Fp>,
+    ) -> Vec<Constraint<FieldVariant<Self::Fp, Self::Fq>>> {
         todo!()
     }
 }
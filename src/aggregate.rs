@@ -0,0 +1,244 @@
+use crate::air::AirConfig;
+use crate::challenges::Challenges;
+use crate::constraints::AlgebraicItem;
+use crate::constraints::Constraint;
+use crate::hints::Hints;
+use crate::random::PublicCoin;
+use crate::stark::Stark;
+use crate::trace::TraceInfo;
+use crate::trace::TraceInfoMismatch;
+use crate::utils::FieldVariant;
+use crate::Air;
+use crate::Matrix;
+use crate::Trace;
+use alloc::vec::Vec;
+use ark_ff::Zero;
+use ark_poly::Radix2EvaluationDomain;
+use ark_serialize::CanonicalSerialize;
+use core::marker::PhantomData;
+
+/// AIR for `N` instances of `A` proven together in a single proof
+///
+/// The instance traces are placed side by side so instance `i` owns base
+/// columns `i * A::NUM_BASE_COLUMNS..(i + 1) * A::NUM_BASE_COLUMNS` and the
+/// same range of extension columns. Instance `i` also owns a copy of the inner
+/// fixed columns. Hints are interleaved so hint `j` of instance `i` is hint
+/// `j * N + i`. Each instance's constraints are copied with their column,
+/// fixed column and hint indices shifted. Verifier challenges are shared
+/// between instances. Inner AIRs with a runtime dependent number of base
+/// columns aren't supported.
+pub struct AggregateAirConfig<A: AirConfig, const N: usize>(PhantomData<A>);
+
+impl<A: AirConfig, const N: usize> AirConfig for AggregateAirConfig<A, N> {
+    const NUM_BASE_COLUMNS: usize = N * A::NUM_BASE_COLUMNS;
+    const NUM_EXTENSION_COLUMNS: usize = N * A::NUM_EXTENSION_COLUMNS;
+    const NUM_RANDOMIZER_ROWS: usize = A::NUM_RANDOMIZER_ROWS;
+    type Fp = A::Fp;
+    type Fq = A::Fq;
+    type PublicInputs = [A::PublicInputs; N];
+
+    fn constraints(
+        trace_domain: &Radix2EvaluationDomain<Self::Fp>,
+    ) -> Vec<Constraint<FieldVariant<Self::Fp, Self::Fq>>> {
+        let constraints = A::constraints(trace_domain);
+        let num_fixed_columns = A::fixed_columns().len();
+        (0..N)
+            .flat_map(|instance| {
+                constraints.iter().map(move |constraint| {
+                    Constraint::new(constraint.map_leaves(&mut |&item| {
+                        shift_item::<A, N>(item, instance, num_fixed_columns)
+                    }))
+                })
+            })
+            .collect()
+    }
+
+    /// Checks the aggregate shape then each instance against the inner AIR
+    fn check_trace_info(
+        trace_info: &TraceInfo,
+        public_inputs: &[A::PublicInputs; N],
+    ) -> Result<(), TraceInfoMismatch> {
+        let &TraceInfo {
+            trace_len,
+            num_base_columns,
+            num_extension_columns,
+        } = trace_info;
+        if num_base_columns != Self::NUM_BASE_COLUMNS {
+            return Err(TraceInfoMismatch::NumBaseColumns {
+                expected: Self::NUM_BASE_COLUMNS,
+                actual: num_base_columns,
+            });
+        }
+        if num_extension_columns != Self::NUM_EXTENSION_COLUMNS {
+            return Err(TraceInfoMismatch::NumExtensionColumns {
+                expected: Self::NUM_EXTENSION_COLUMNS,
+                actual: num_extension_columns,
+            });
+        }
+        let instance_trace_info = TraceInfo {
+            trace_len,
+            num_base_columns: A::NUM_BASE_COLUMNS,
+            num_extension_columns: A::NUM_EXTENSION_COLUMNS,
+        };
+        public_inputs
+            .iter()
+            .try_for_each(|public_inputs| A::check_trace_info(&instance_trace_info, public_inputs))
+    }
+
+    fn fixed_columns() -> Vec<Vec<Self::Fp>> {
+        let fixed_columns = A::fixed_columns();
+        (0..N).flat_map(|_| fixed_columns.clone()).collect()
+    }
+
+    fn gen_hints(
+        trace_len: usize,
+        public_inputs: &[A::PublicInputs; N],
+        challenges: &Challenges<Self::Fq>,
+    ) -> Hints<Self::Fq> {
+        let instance_hints = public_inputs
+            .iter()
+            .map(|public_inputs| {
+                assert_eq!(A::num_base_columns(public_inputs), A::NUM_BASE_COLUMNS);
+                A::gen_hints(trace_len, public_inputs, challenges)
+            })
+            .collect::<Vec<_>>();
+        let num_hints = instance_hints.iter().map(|hints| hints.len()).max();
+        let mut public = Vec::new();
+        let mut private = Vec::new();
+        for index in 0..num_hints.unwrap_or(0) {
+            for (instance, hints) in instance_hints.iter().enumerate() {
+                let shifted = index * N + instance;
+                // instances with fewer hints are padded so indices stay dense
+                match hints.get(index) {
+                    Some(&value) if hints.is_private(index) => private.push((shifted, value)),
+                    Some(&value) => public.push((shifted, value)),
+                    None => public.push((shifted, Self::Fq::zero())),
+                }
+            }
+        }
+        Hints::new_with_private(public, private)
+    }
+
+    fn domain_offset() -> Self::Fp {
+        A::domain_offset()
+    }
+}
+
+/// Moves an item of an inner AIR constraint to the columns and hints of
+/// `instance`. Extension columns are indexed after all base columns.
+fn shift_item<A: AirConfig, const N: usize>(
+    item: AlgebraicItem<FieldVariant<A::Fp, A::Fq>>,
+    instance: usize,
+    num_fixed_columns: usize,
+) -> AlgebraicItem<FieldVariant<A::Fp, A::Fq>> {
+    match item {
+        AlgebraicItem::Trace(col, offset) if col < A::NUM_BASE_COLUMNS => {
+            AlgebraicItem::Trace(instance * A::NUM_BASE_COLUMNS + col, offset)
+        }
+        AlgebraicItem::Trace(col, offset) => {
+            let extension_col = instance * A::NUM_EXTENSION_COLUMNS + col - A::NUM_BASE_COLUMNS;
+            AlgebraicItem::Trace(N * A::NUM_BASE_COLUMNS + extension_col, offset)
+        }
+        AlgebraicItem::Hint(index) => AlgebraicItem::Hint(index * N + instance),
+        AlgebraicItem::Fixed(index) => AlgebraicItem::Fixed(instance * num_fixed_columns + index),
+        AlgebraicItem::X
+        | AlgebraicItem::Constant(_)
+        | AlgebraicItem::Challenge(_)
+        | AlgebraicItem::Periodic(_) => item,
+    }
+}
+
+/// Execution traces of `N` instances combined column wise
+pub struct AggregateTrace<T: Trace> {
+    traces: Vec<T>,
+    base_columns: Matrix<T::Fp>,
+}
+
+impl<T: Trace> AggregateTrace<T> {
+    pub fn new(traces: Vec<T>) -> Self {
+        let len = traces.first().map_or(0, |trace| trace.len());
+        assert!(
+            traces.iter().all(|trace| trace.len() == len),
+            "aggregated traces must have the same length"
+        );
+        let base_columns = Matrix::join(
+            traces
+                .iter()
+                .map(|trace| Matrix::new(trace.base_columns().0.clone()))
+                .collect(),
+        );
+        Self {
+            traces,
+            base_columns,
+        }
+    }
+}
+
+impl<T: Trace> Trace for AggregateTrace<T> {
+    type Fp = T::Fp;
+    type Fq = T::Fq;
+
+    fn base_columns(&self) -> &Matrix<Self::Fp> {
+        &self.base_columns
+    }
+
+    fn build_extension_columns(
+        &self,
+        challenges: &Challenges<Self::Fq>,
+    ) -> Option<Matrix<Self::Fq>> {
+        let extension_columns = self
+            .traces
+            .iter()
+            .map(|trace| trace.build_extension_columns(challenges))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Matrix::join(extension_columns))
+    }
+}
+
+/// Proves `N` claims of the same STARK with a single proof
+///
+/// The claims share one FRI instance which amortizes its cost. The public
+/// inputs of every claim are bound into the transcript so tampering with any
+/// claim fails verification of the whole proof.
+pub struct Aggregate<S: Stark, const N: usize>(pub [S; N]);
+
+impl<S: Stark, const N: usize> Stark for Aggregate<S, N> {
+    type Fp = S::Fp;
+    type Fq = S::Fq;
+    type AirConfig = AggregateAirConfig<S::AirConfig, N>;
+    type PublicCoin = S::PublicCoin;
+    type MerkleTree = S::MerkleTree;
+    type Trace = AggregateTrace<S::Trace>;
+    type Digest = S::Digest;
+    type Witness = [S::Witness; N];
+
+    fn get_public_inputs(&self) -> [<S::AirConfig as AirConfig>::PublicInputs; N] {
+        core::array::from_fn(|i| self.0[i].get_public_inputs())
+    }
+
+    /// Seeds the public coin with the trace length, options and the public
+    /// inputs of every claim
+    fn gen_public_coin(&self, air: &Air<Self::AirConfig>) -> Self::PublicCoin {
+        let mut seed = Vec::new();
+        air.trace_len().serialize_compressed(&mut seed).unwrap();
+        air.options().serialize_compressed(&mut seed).unwrap();
+        air.public_inputs().serialize_compressed(&mut seed).unwrap();
+        let mut public_coin = S::PublicCoin::new(S::Digest::default());
+        public_coin.reseed_with_bytes(&seed);
+        public_coin
+    }
+
+    fn aux_data(&self) -> Vec<Self::Fp> {
+        self.0.iter().flat_map(S::aux_data).collect()
+    }
+
+    fn generate_trace(&self, witnesses: [S::Witness; N]) -> AggregateTrace<S::Trace> {
+        let traces = self
+            .0
+            .iter()
+            .zip(witnesses)
+            .map(|(claim, witness)| claim.generate_trace(witness))
+            .collect();
+        AggregateTrace::new(traces)
+    }
+}
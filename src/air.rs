@@ -3,33 +3,127 @@ use crate::constraints::AlgebraicItem;
 use crate::constraints::CompositionConstraint;
 use crate::constraints::CompositionItem;
 use crate::constraints::Constraint;
+use crate::constraints::PeriodicColumn;
 use crate::expression::Expr;
 use crate::hints::Hints;
+use crate::prover::Instant;
+use crate::random::draw_multiple;
+use crate::random::PublicCoin;
+use crate::schema::ConstraintSchema;
+use crate::trace::TraceInfo;
+use crate::trace::TraceInfoMismatch;
+use crate::trace::MIN_TRACE_LEN;
+use crate::utils::horner_evaluate;
 use crate::utils::FieldVariant;
+use crate::utils::GpuAllocator;
 use crate::utils::GpuVec;
 use crate::Matrix;
 use crate::ProofOptions;
 use crate::StarkExtensionOf;
+use alloc::collections::BTreeMap;
 use alloc::collections::BTreeSet;
 use alloc::vec::Vec;
 use ark_ff::FftField;
+use ark_ff::Field;
 use ark_poly::EvaluationDomain;
 use ark_poly::Radix2EvaluationDomain;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
+use ark_serialize::Compress;
+use ark_serialize::Read;
+use ark_serialize::SerializationError;
+use ark_serialize::Valid;
+use ark_serialize::Validate;
+use ark_serialize::Write;
 use ministark_gpu::GpuFftField;
 use num_traits::Pow;
-use crate::prover::Instant;
 
 pub trait AirConfig: Send + Sync + Sized + 'static {
     const NUM_BASE_COLUMNS: usize;
     const NUM_EXTENSION_COLUMNS: usize = 0;
+    /// Number of trailing trace rows that no constraint applies to. The prover
+    /// can replace up to this many rows with random values for zero-knowledge
+    /// (see [`ProofOptions::with_num_randomizers`]). Transition constraints
+    /// exempt these rows with
+    /// [`Constraint::transition_with_exempt_rows`] and boundary constraints
+    /// sit on earlier rows. Defaults to none.
+    const NUM_RANDOMIZER_ROWS: usize = 0;
 
     type Fp: GpuFftField<FftField = Self::Fp> + FftField;
     type Fq: StarkExtensionOf<Self::Fp>;
     type PublicInputs: CanonicalSerialize + CanonicalDeserialize + Clone;
 
-    fn constraints(trace_len: usize) -> Vec<Constraint<FieldVariant<Self::Fp, Self::Fq>>>;
+    /// Returns the number of base columns for the given public inputs. Override
+    /// this if the trace width is only known at runtime e.g. a VM with a
+    /// program dependent number of registers. Defaults to
+    /// [`Self::NUM_BASE_COLUMNS`].
+    fn num_base_columns(_public_inputs: &Self::PublicInputs) -> usize {
+        Self::NUM_BASE_COLUMNS
+    }
+
+    /// Checks the shape of the trace a proof claims against this AIR. The
+    /// verifier calls this before doing any work on the proof. Override to
+    /// add checks for AIRs with a runtime dependent shape.
+    fn check_trace_info(
+        trace_info: &TraceInfo,
+        public_inputs: &Self::PublicInputs,
+    ) -> Result<(), TraceInfoMismatch> {
+        let &TraceInfo {
+            trace_len,
+            num_base_columns,
+            num_extension_columns,
+        } = trace_info;
+        if !trace_len.is_power_of_two() {
+            return Err(TraceInfoMismatch::LengthNotPowerOfTwo { len: trace_len });
+        }
+        if trace_len < MIN_TRACE_LEN {
+            return Err(TraceInfoMismatch::TooShort { len: trace_len });
+        }
+        let expected = Self::num_base_columns(public_inputs);
+        if num_base_columns != expected {
+            return Err(TraceInfoMismatch::NumBaseColumns {
+                expected,
+                actual: num_base_columns,
+            });
+        }
+        if num_extension_columns != Self::NUM_EXTENSION_COLUMNS {
+            return Err(TraceInfoMismatch::NumExtensionColumns {
+                expected: Self::NUM_EXTENSION_COLUMNS,
+                actual: num_extension_columns,
+            });
+        }
+        Ok(())
+    }
+
+    /// Public columns the verifier knows in full e.g. a lookup table or
+    /// selector flags. Constraints reference them with
+    /// [`AlgebraicItem::Fixed`]. Unlike trace columns they aren't committed
+    /// to: both sides evaluate them like periodic columns which keeps them out
+    /// of the proof. Each column must have a power of two length no longer
+    /// than the trace and columns shorter than the trace repeat.
+    fn fixed_columns() -> Vec<Vec<Self::Fp>> {
+        Vec::new()
+    }
+
+    /// Returns the AIR constraints. The trace domain is provided so boundary
+    /// and transition constraints can refer to its elements (e.g. the first
+    /// and last trace x values) without rebuilding the domain.
+    fn constraints(
+        trace_domain: &Radix2EvaluationDomain<Self::Fp>,
+    ) -> Vec<Constraint<FieldVariant<Self::Fp, Self::Fq>>>;
+
+    /// Builds the constraints and composition constraint for a trace with
+    /// `trace_len` rows. The result can be serialized and loaded by processes
+    /// that build the same AIR often. See [`CompiledAir`]
+    fn compile(trace_len: usize) -> CompiledAir<Self> {
+        CompiledAir::new(trace_len)
+    }
+
+    /// Returns the constraints for a trace with `trace_len` rows as
+    /// serializable data for external tooling. See [`ConstraintSchema`]
+    fn export_constraints(trace_len: usize) -> ConstraintSchema<FieldVariant<Self::Fp, Self::Fq>> {
+        ConstraintSchema::new(&Self::constraints(&trace_domain::<Self>(trace_len)))
+    }
 
     fn gen_hints(
         _trace_len: usize,
@@ -51,12 +145,14 @@ pub trait AirConfig: Send + Sync + Sized + 'static {
         trace_len: usize,
         constraints: &[Constraint<FieldVariant<Self::Fp, Self::Fq>>],
     ) -> CompositionConstraint<FieldVariant<Self::Fp, Self::Fq>> {
-        let ce_blowup_factor = constraints
+        // constraints are only raised to the smallest degree that fits in whole
+        // columns rather than the power-of-two constraint evaluation blowup
+        let num_composition_columns = constraints
             .iter()
-            .map(|c| c.blowup_factor(trace_len))
+            .map(|c| c.num_columns(trace_len))
             .max()
             .unwrap();
-        let composition_degree = trace_len * ce_blowup_factor - 1;
+        let composition_degree = trace_len * num_composition_columns - 1;
         let trace_degree = trace_len - 1;
         let x = Expr::Leaf(CompositionItem::Item(AlgebraicItem::X));
         let mut composition_coeff = (0..).map(|i| Expr::Leaf(CompositionItem::CompositionCoeff(i)));
@@ -87,9 +183,11 @@ pub trait AirConfig: Send + Sync + Sized + 'static {
         composition_constraint: &CompositionConstraint<FieldVariant<Self::Fp, Self::Fq>>,
         challenges: &[Self::Fq],
         hints: &[Self::Fq],
+        fixed_columns: &[PeriodicColumn<'_, FieldVariant<Self::Fp, Self::Fq>>],
         composition_constraint_coeffs: &[Self::Fq],
         lde_step: usize,
-        x_lde: GpuVec<Self::Fp>,
+        domain_offset: Self::Fp,
+        x_lde: &GpuVec<Self::Fp>,
         base_trace_lde_cols: &[&[Self::Fp]],
         extension_trace_lde_cols: Option<&[&[Self::Fq]]>,
     ) -> Matrix<Self::Fq> {
@@ -119,23 +217,286 @@ pub trait AirConfig: Send + Sync + Sized + 'static {
             &eval_expr,
             challenges,
             hints,
+            fixed_columns,
             lde_step,
-            Self::domain_offset(),
-            &x_lde,
+            domain_offset,
+            x_lde,
             base_trace_lde_cols,
             extension_trace_lde_cols,
         )
     }
+
+    /// Evaluates the composition constraint at a single point. This is the
+    /// single point analogue of [`Self::eval_constraint`] used by the verifier
+    /// at the out-of-domain point `z`. `trace_evals` maps each `(column,
+    /// offset)` trace argument to the evaluation of that column at `z·gᵒᶠᶠˢᵉᵗ`.
+    #[allow(clippy::too_many_arguments)]
+    fn eval_constraint_at_point(
+        composition_constraint: &CompositionConstraint<FieldVariant<Self::Fp, Self::Fq>>,
+        trace_len: usize,
+        challenges: &[Self::Fq],
+        hints: &[Self::Fq],
+        fixed_columns: &[PeriodicColumn<'_, FieldVariant<Self::Fp, Self::Fq>>],
+        composition_constraint_coeffs: &[Self::Fq],
+        trace_evals: &BTreeMap<(usize, isize), Self::Fq>,
+        z: Self::Fq,
+    ) -> Self::Fq {
+        use AlgebraicItem::*;
+        use CompositionItem::*;
+        let eval_periodic = |col: PeriodicColumn<'_, FieldVariant<Self::Fp, Self::Fq>>| {
+            let point = z.pow([(trace_len / col.interval_size()) as u64]);
+            let coeffs = col
+                .coeffs()
+                .iter()
+                .map(FieldVariant::as_fq)
+                .collect::<Vec<_>>();
+            FieldVariant::Fq(horner_evaluate(&coeffs, &point))
+        };
+        composition_constraint
+            .graph_eval(&mut |leaf| match leaf {
+                Item(X) => FieldVariant::Fq(z),
+                &Item(Constant(v)) => v,
+                &Item(Challenge(i)) => FieldVariant::Fq(challenges[i]),
+                &Item(Hint(i)) => FieldVariant::Fq(hints[i]),
+                &Item(Periodic(col)) => eval_periodic(col),
+                &Item(Trace(i, j)) => FieldVariant::Fq(trace_evals[&(i, j)]),
+                &Item(Fixed(i)) => eval_periodic(fixed_columns[i]),
+                &CompositionCoeff(i) => FieldVariant::Fq(composition_constraint_coeffs[i]),
+            })
+            .as_fq()
+    }
+}
+
+/// Returns the size of the LDE domain or `None` if it overflows or exceeds the
+/// largest power-of-two subgroup of the base field.
+///
+/// Domains always live in the base field. Trace values aren't lifted into
+/// [`AirConfig::Fq`] to borrow a larger subgroup: the NTTs, the GPU kernels
+/// and the base trace commitment all work over `Fp`, so the base field's
+/// two-adicity caps the LDE size.
+pub fn lde_domain_size<A: AirConfig>(trace_len: usize, options: ProofOptions) -> Option<usize> {
+    let size = trace_len.checked_mul(options.lde_blowup_factor.into())?;
+    (size.is_power_of_two() && size.ilog2() <= A::Fp::TWO_ADICITY).then_some(size)
 }
 
+/// Returns the domain the trace is interpolated over
+///
+/// Only radix-2 domains are supported so traces must have a power of two
+/// length. The GPU NTTs, the bit-reversed row order of every commitment and
+/// FRI folding (by a power of two) all depend on it. A trace of length `3·2ᵏ`
+/// has to be padded to the next power of two e.g. by repeating its last row
+/// under a transition constraint that allows it.
 pub fn trace_domain<A: AirConfig>(trace_len: usize) -> Radix2EvaluationDomain<A::Fp> {
     Radix2EvaluationDomain::new(trace_len).unwrap()
 }
 
+/// Returns the coefficients of each of [`AirConfig::fixed_columns`]. A column
+/// of `n` values is interpolated over the subgroup of size `n` so it's
+/// evaluated like a periodic column that repeats every `n` rows.
+fn fixed_column_coeffs<C: AirConfig>() -> Vec<Vec<FieldVariant<C::Fp, C::Fq>>> {
+    C::fixed_columns()
+        .into_iter()
+        .map(|values| {
+            let domain = Radix2EvaluationDomain::new(values.len()).unwrap();
+            assert_eq!(domain.size(), values.len(), "length must be a power of two");
+            let coeffs = domain.ifft(&values).into_iter().map(FieldVariant::Fp);
+            coeffs.collect()
+        })
+        .collect()
+}
+
+/// Returns the fixed columns read by any of the constraints
+fn fixed_column_reads<T>(constraints: &[Constraint<T>]) -> BTreeSet<usize> {
+    let mut reads = BTreeSet::new();
+    for constraint in constraints {
+        constraint.traverse(&mut |node| {
+            if let &Expr::Leaf(AlgebraicItem::Fixed(i)) = node {
+                reads.insert(i);
+            }
+        });
+    }
+    reads
+}
+
+/// Returns true if `trace_len` is a trace length the AIR's field supports
+fn is_valid_trace_len<C: AirConfig>(trace_len: usize) -> bool {
+    trace_len.is_power_of_two()
+        && trace_len >= MIN_TRACE_LEN
+        && Radix2EvaluationDomain::<C::Fp>::new(trace_len).is_some()
+}
+
+/// Constraints, composition constraint and composition layout of an AIR for
+/// one trace length
+///
+/// Provers can serialize the output of [`AirConfig::compile`] once and build
+/// each [`Air`] from the loaded result with [`Air::from_compiled`] rather than
+/// rebuilding the composition constraint. Proofs are identical to those of an
+/// AIR built from scratch. Validation checks the composition layout matches
+/// the loaded constraints but not that they're the constraints of `C`: the
+/// serialized data is meant to be written and read by the same prover rather
+/// than received from another party.
+///
+/// Constraints keep their [`AlgebraicItem::Fixed`] leaves. The coefficients of
+/// the fixed columns are owned by the compiled AIR (and the [`Air`] built from
+/// it) and aren't serialized: loading recomputes them from
+/// [`AirConfig::fixed_columns`]. Periodic columns are matched against the
+/// columns of [`AirConfig::constraints`] so loading doesn't leak their
+/// coefficients.
+pub struct CompiledAir<C: AirConfig> {
+    trace_len: usize,
+    fixed_columns: Vec<Vec<FieldVariant<C::Fp, C::Fq>>>,
+    constraints: Vec<Constraint<FieldVariant<C::Fp, C::Fq>>>,
+    composition_constraint: CompositionConstraint<FieldVariant<C::Fp, C::Fq>>,
+    ce_blowup_factor: usize,
+    num_composition_columns: usize,
+}
+
+impl<C: AirConfig> CompiledAir<C> {
+    /// # Panics
+    ///
+    /// Panics if a fixed column is longer than the trace or a constraint reads
+    /// a fixed column that doesn't exist
+    pub fn new(trace_len: usize) -> Self {
+        let fixed_columns = fixed_column_coeffs::<C>();
+        for (i, coeffs) in fixed_columns.iter().enumerate() {
+            assert!(
+                coeffs.len() <= trace_len,
+                "fixed column {i} has {} rows but the trace only has {trace_len}",
+                coeffs.len()
+            );
+        }
+        let constraints = C::constraints(&trace_domain::<C>(trace_len));
+        if let Some(&i) = fixed_column_reads(&constraints).last() {
+            let num_fixed_columns = fixed_columns.len();
+            assert!(
+                i < num_fixed_columns,
+                "constraint reads fixed column {i} but there are only {num_fixed_columns} \
+                 fixed columns"
+            );
+        }
+        let composition_constraint = C::composition_constraint(trace_len, &constraints);
+        let ce_blowup_factor = composition_constraint.blowup_factor(trace_len);
+        let num_composition_columns = composition_constraint.num_columns(trace_len);
+        assert!(
+            num_composition_columns <= ce_blowup_factor,
+            "composition polynomial needs {num_composition_columns} columns but the constraint \
+             evaluation blowup factor is only {ce_blowup_factor}"
+        );
+        Self {
+            trace_len,
+            fixed_columns,
+            constraints,
+            composition_constraint,
+            ce_blowup_factor,
+            num_composition_columns,
+        }
+    }
+
+    pub const fn trace_len(&self) -> usize {
+        self.trace_len
+    }
+}
+
+impl<C: AirConfig> CanonicalSerialize for CompiledAir<C> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.trace_len.serialize_with_mode(&mut writer, compress)?;
+        ConstraintSchema::new(&self.constraints).serialize_with_mode(&mut writer, compress)?;
+        ConstraintSchema::from_composition(&self.composition_constraint)
+            .serialize_with_mode(&mut writer, compress)?;
+        self.ce_blowup_factor
+            .serialize_with_mode(&mut writer, compress)?;
+        self.num_composition_columns
+            .serialize_with_mode(writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.trace_len.serialized_size(compress)
+            + ConstraintSchema::new(&self.constraints).serialized_size(compress)
+            + ConstraintSchema::from_composition(&self.composition_constraint)
+                .serialized_size(compress)
+            + self.ce_blowup_factor.serialized_size(compress)
+            + self.num_composition_columns.serialized_size(compress)
+    }
+}
+
+impl<C: AirConfig> Valid for CompiledAir<C> {
+    fn check(&self) -> Result<(), SerializationError> {
+        let trace_len = self.trace_len;
+        if !is_valid_trace_len::<C>(trace_len) {
+            return Err(SerializationError::InvalidData);
+        }
+        let num_fixed_columns = self.fixed_columns.len();
+        if self
+            .fixed_columns
+            .iter()
+            .any(|coeffs| coeffs.len() > trace_len)
+            || fixed_column_reads(&self.constraints)
+                .last()
+                .is_some_and(|&i| i >= num_fixed_columns)
+        {
+            return Err(SerializationError::InvalidData);
+        }
+        let composition_constraint = &self.composition_constraint;
+        if self.ce_blowup_factor != composition_constraint.blowup_factor(trace_len)
+            || self.num_composition_columns != composition_constraint.num_columns(trace_len)
+            || self.num_composition_columns > self.ce_blowup_factor
+        {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(())
+    }
+}
+
+impl<C: AirConfig> CanonicalDeserialize for CompiledAir<C> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let trace_len = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        if !is_valid_trace_len::<C>(trace_len) {
+            return Err(SerializationError::InvalidData);
+        }
+        // periodic nodes borrow the coefficients of the AIR's own columns
+        let periodic_columns = C::constraints(&trace_domain::<C>(trace_len))
+            .iter()
+            .flat_map(Constraint::periodic_columns)
+            .collect::<Vec<_>>();
+        let constraints: ConstraintSchema<FieldVariant<C::Fp, C::Fq>> =
+            <_>::deserialize_with_mode(&mut reader, compress, validate)?;
+        let composition_constraint: ConstraintSchema<FieldVariant<C::Fp, C::Fq>> =
+            <_>::deserialize_with_mode(&mut reader, compress, validate)?;
+        let composition_constraint = composition_constraint
+            .to_composition(&periodic_columns)
+            .map_err(|_| SerializationError::InvalidData)?;
+        let constraints = constraints
+            .to_constraints(&periodic_columns)
+            .map_err(|_| SerializationError::InvalidData)?;
+        let compiled = Self {
+            trace_len,
+            fixed_columns: fixed_column_coeffs::<C>(),
+            constraints,
+            composition_constraint,
+            ce_blowup_factor: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+            num_composition_columns: <_>::deserialize_with_mode(reader, compress, validate)?,
+        };
+        if validate == Validate::Yes {
+            compiled.check()?;
+        }
+        Ok(compiled)
+    }
+}
+
 pub struct Air<AC: AirConfig> {
+    fixed_columns: Vec<Vec<FieldVariant<AC::Fp, AC::Fq>>>,
     constraints: Vec<Constraint<FieldVariant<AC::Fp, AC::Fq>>>,
     composition_constraint: CompositionConstraint<FieldVariant<AC::Fp, AC::Fq>>,
     ce_blowup_factor: usize,
+    num_composition_columns: usize,
     trace_len: usize,
     options: ProofOptions,
     public_inputs: AC::PublicInputs,
@@ -143,21 +504,67 @@ pub struct Air<AC: AirConfig> {
 
 impl<C: AirConfig> Air<C> {
     pub fn new(trace_len: usize, public_inputs: C::PublicInputs, options: ProofOptions) -> Self {
-        let constraints = C::constraints(trace_len);
-        let composition_constraint = C::composition_constraint(trace_len, &constraints);
-        let ce_blowup_factor = composition_constraint.blowup_factor(trace_len);
-        assert!(ce_blowup_factor <= options.lde_blowup_factor.into());
+        Self::from_compiled(C::compile(trace_len), public_inputs, options)
+    }
 
+    /// Builds an AIR from a compiled AIR e.g. one loaded from disk
+    pub fn from_compiled(
+        compiled: CompiledAir<C>,
+        public_inputs: C::PublicInputs,
+        options: ProofOptions,
+    ) -> Self {
+        let CompiledAir {
+            trace_len,
+            fixed_columns,
+            constraints,
+            composition_constraint,
+            ce_blowup_factor,
+            num_composition_columns,
+        } = compiled;
+        assert!(
+            lde_domain_size::<C>(trace_len, options).is_some(),
+            "LDE domain of trace length {trace_len} and blowup {} exceeds 2^{}",
+            options.lde_blowup_factor,
+            C::Fp::TWO_ADICITY
+        );
+        assert!(ce_blowup_factor <= options.lde_blowup_factor.into());
         Self {
+            fixed_columns,
             constraints,
             composition_constraint,
             ce_blowup_factor,
+            num_composition_columns,
             trace_len,
             options,
             public_inputs,
         }
     }
 
+    /// Returns the smallest power of two trace length that can be proven with
+    /// `options`. Shorter traces leave FRI without a valid remainder, the LDE
+    /// domain with fewer positions than queries, too few rows for the
+    /// randomizers or fixed columns longer than the trace.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no trace length fits in the field's two-adic subgroup
+    pub fn min_trace_len(options: ProofOptions) -> usize {
+        let fri_options = options.into_fri_options();
+        let max_fixed_len = C::fixed_columns().iter().map(Vec::len).max();
+        let mut trace_len = MIN_TRACE_LEN.max(max_fixed_len.unwrap_or(0));
+        loop {
+            let lde_domain_size =
+                lde_domain_size::<C>(trace_len, options).expect("LDE domain is too large");
+            if fri_options.is_valid_domain_size(lde_domain_size)
+                && usize::from(options.num_queries) <= lde_domain_size
+                && usize::from(options.num_randomizers) < trace_len
+            {
+                return trace_len;
+            }
+            trace_len *= 2;
+        }
+    }
+
     pub const fn trace_len(&self) -> usize {
         self.trace_len
     }
@@ -170,14 +577,32 @@ impl<C: AirConfig> Air<C> {
         &self.public_inputs
     }
 
+    /// Replaces the public inputs. Constraints only depend on the trace length
+    /// so they don't need to be rebuilt.
+    pub fn set_public_inputs(&mut self, public_inputs: C::PublicInputs) {
+        self.public_inputs = public_inputs;
+    }
+
+    pub fn num_base_columns(&self) -> usize {
+        C::num_base_columns(&self.public_inputs)
+    }
+
     pub const fn ce_blowup_factor(&self) -> usize {
         self.ce_blowup_factor
     }
 
+    /// Number of columns the composition polynomial is split into. This can be
+    /// less than the constraint evaluation blowup factor since it isn't
+    /// restricted to a power of two. AIRs whose composition polynomial has
+    /// degree less than the trace length get a single column which is
+    /// committed and evaluated out-of-domain without any splitting.
+    pub const fn num_composition_columns(&self) -> usize {
+        self.num_composition_columns
+    }
+
     /// Returns a degree that all constraint polynomials must be normalized to.
     pub const fn composition_degree(&self) -> usize {
-        let ce_domain_size = self.trace_len * self.ce_blowup_factor();
-        ce_domain_size - 1
+        self.trace_len * self.num_composition_columns() - 1
     }
 
     pub fn num_challenges(&self) -> usize {
@@ -196,6 +621,23 @@ impl<C: AirConfig> Air<C> {
         C::gen_hints(self.trace_len(), self.public_inputs(), challenges)
     }
 
+    /// Returns the index of a private hint referenced by the constraints if any.
+    /// Constraints may only reference public hints since private hints aren't
+    /// reproduced by the verifier.
+    pub fn find_private_hint(&self, hints: &Hints<C::Fq>) -> Option<usize> {
+        let mut private_hint = None;
+        for constraint in &self.constraints {
+            constraint.traverse(&mut |node| {
+                if let Expr::Leaf(AlgebraicItem::Hint(i)) = node
+                    && hints.is_private(*i)
+                {
+                    private_hint.get_or_insert(*i);
+                }
+            });
+        }
+        private_hint
+    }
+
     pub fn num_composition_constraint_coeffs(&self) -> usize {
         let mut num_coeffs = 0;
         self.composition_constraint.traverse(&mut |node| {
@@ -206,42 +648,188 @@ impl<C: AirConfig> Air<C> {
         num_coeffs
     }
 
+    /// Draws the composition constraint coefficients from a public coin
+    /// seeded with `seed`. Gives the same number of coefficients as the prover
+    /// and verifier draw from the channel which makes it possible to test
+    /// constraint evaluation without running the full protocol.
+    pub fn composition_coeffs_from_seed<P: PublicCoin<Field = C::Fq>>(
+        &self,
+        seed: P::Digest,
+    ) -> Vec<C::Fq> {
+        let mut public_coin = P::new(seed);
+        draw_multiple(&mut public_coin, self.num_composition_constraint_coeffs())
+    }
+
     pub fn trace_domain(&self) -> Radix2EvaluationDomain<C::Fp> {
         trace_domain::<C>(self.trace_len)
     }
 
+    /// Offset of the LDE and constraint evaluation cosets. Uses the offset set
+    /// in the proof options if there is one otherwise [`AirConfig::domain_offset`]
+    pub fn domain_offset(&self) -> C::Fp {
+        self.options
+            .coset_offset_exponent
+            .map_or_else(C::domain_offset, |exponent| {
+                C::Fp::GENERATOR.pow([u64::from(exponent)])
+            })
+    }
+
     /// Low degree extension domain
     pub fn lde_domain(&self) -> Radix2EvaluationDomain<C::Fp> {
-        let offset = C::domain_offset();
+        let offset = self.domain_offset();
         let trace_len = self.trace_len();
         let lde_blowup_factor = self.lde_blowup_factor();
         Radix2EvaluationDomain::new_coset(trace_len * lde_blowup_factor, offset).unwrap()
     }
 
+    /// Draws the out-of-domain point from `public_coin`. Points on the trace
+    /// domain or the LDE domain are rejected and redrawn: constraint divisors
+    /// vanish on the trace domain and DEEP quotients divide by zero on the
+    /// LDE domain. The constraint evaluation domain is a subset of the LDE
+    /// domain. A collision is unlikely for large fields but not impossible.
+    pub fn draw_ood_point<P: PublicCoin<Field = C::Fq>>(&self, public_coin: &mut P) -> C::Fq {
+        let trace_len = self.trace_len() as u64;
+        let lde_len = (self.trace_len() * self.lde_blowup_factor()) as u64;
+        // `z` is in the coset `offset * <g>` of size `n` iff `z^n = offset^n`
+        let lde_offset_pow = C::Fq::from(self.domain_offset().pow([lde_len]));
+        loop {
+            let z = public_coin.draw();
+            if z.pow([trace_len]) != C::Fq::ONE && z.pow([lde_len]) != lde_offset_pow {
+                return z;
+            }
+        }
+    }
+
     /// Constraint evaluation domain
     pub fn ce_domain(&self) -> Radix2EvaluationDomain<C::Fp> {
-        let offset = C::domain_offset();
+        let offset = self.domain_offset();
         let trace_len = self.trace_len();
         let blowup_factor = self.ce_blowup_factor();
         Radix2EvaluationDomain::new_coset(trace_len * blowup_factor, offset).unwrap()
     }
 
+    /// Evaluates the vanishing polynomial `X^n - c` over the LDE domain. Over
+    /// the coset `x^n` repeats with a period of `lde_len / gcd(n, lde_len)` so
+    /// only one period is computed. Constraint evaluation already evaluates
+    /// each distinct divisor once e.g. `X - t_0` shared by all boundary
+    /// constraints.
+    pub fn vanishing_evals(&self, n: usize, c: C::Fp) -> GpuVec<C::Fp> {
+        let lde_domain = self.lde_domain();
+        let lde_len = lde_domain.size();
+        let period = lde_len >> n.trailing_zeros().min(lde_len.trailing_zeros());
+        let period_evals = lde_domain
+            .elements()
+            .take(period)
+            .map(|x| x.pow([n as u64]) - c)
+            .collect::<Vec<_>>();
+        let mut evals = Vec::with_capacity_in(lde_len, GpuAllocator);
+        evals.extend(period_evals.iter().cycle().take(lde_len));
+        evals
+    }
+
     /// Low degree extension domain
     #[inline]
     pub const fn lde_blowup_factor(&self) -> usize {
         self.options.lde_blowup_factor as usize
     }
 
+    /// Returns [`AirConfig::fixed_columns`] as periodic columns.
+    /// [`AlgebraicItem::Fixed`] leaves of the constraints evaluate to these.
+    pub fn fixed_columns(&self) -> Vec<PeriodicColumn<'_, FieldVariant<C::Fp, C::Fq>>> {
+        self.fixed_columns
+            .iter()
+            .map(|coeffs| PeriodicColumn::new(coeffs, coeffs.len()))
+            .collect()
+    }
+
+    pub fn constraints(&self) -> &[Constraint<FieldVariant<C::Fp, C::Fq>>] {
+        &self.constraints
+    }
+
     pub const fn composition_constraint(
         &self,
     ) -> &CompositionConstraint<FieldVariant<C::Fp, C::Fq>> {
         &self.composition_constraint
     }
 
+    /// Returns the index of a constraint, and the `(column, offset)` it reads,
+    /// if it reads a row offset at least as large as the trace or one that
+    /// wraps around to the same row as another offset (e.g. `-1` and `15` on a
+    /// 16 row trace). The prover sends an out-of-domain evaluation at
+    /// `z⋅gᵒᶠᶠˢᵉᵗ` for every distinct offset so the DEEP composition would
+    /// divide by the same point twice.
+    pub fn find_out_of_range_offset(&self) -> Option<(usize, (usize, isize))> {
+        let trace_len = self.trace_len as isize;
+        let offsets = self.trace_arguments().into_iter().map(|(_, offset)| offset);
+        // each row is read at its smallest offset and other offsets wrap onto it
+        let mut row_offsets = BTreeMap::new();
+        for offset in offsets.collect::<BTreeSet<isize>>() {
+            row_offsets
+                .entry(offset.rem_euclid(trace_len))
+                .or_insert(offset);
+        }
+        let wraps = |offset: isize| {
+            offset.unsigned_abs() >= self.trace_len
+                || row_offsets[&offset.rem_euclid(trace_len)] != offset
+        };
+        for (i, constraint) in self.constraints.iter().enumerate() {
+            for (column, offset) in constraint.trace_arguments() {
+                if wraps(offset) {
+                    return Some((i, (column, offset)));
+                }
+            }
+        }
+        None
+    }
+
     pub fn trace_arguments(&self) -> BTreeSet<(usize, isize)> {
         self.constraints
             .iter()
             .map(Constraint::trace_arguments)
             .fold(BTreeSet::new(), |a, b| &a | &b)
     }
+
+    /// Returns the indices of declared extension columns that no constraint
+    /// references. Declaring more extension columns than the constraints use
+    /// is usually a mistake and still costs a full LDE and commitment.
+    pub fn unused_extension_columns(&self) -> Vec<usize> {
+        let referenced_columns = self
+            .trace_arguments()
+            .into_iter()
+            .map(|(column, _)| column)
+            .collect::<BTreeSet<usize>>();
+        let num_base_columns = self.num_base_columns();
+        (num_base_columns..num_base_columns + C::NUM_EXTENSION_COLUMNS)
+            .filter(|column| !referenced_columns.contains(column))
+            .collect()
+    }
+
+    /// Estimates the bytes of GPU shared memory the prover allocates for the
+    /// execution trace, its polynomials and their LDE, the constraint
+    /// evaluations, the composition polynomial and its LDE and the DEEP
+    /// composition LDE. Buffers freed before later ones are allocated are still
+    /// counted so this is an upper bound on the peak. Merkle trees are built in
+    /// host memory and aren't counted.
+    pub fn estimated_gpu_bytes(&self) -> usize {
+        let lde_size = self.trace_len * usize::from(self.options.lde_blowup_factor);
+        let ce_size = self.trace_len * self.ce_blowup_factor;
+        let fp_size = core::mem::size_of::<C::Fp>();
+        let fq_size = core::mem::size_of::<C::Fq>();
+        let base_column_bytes = self.num_base_columns() * fp_size;
+        let extension_column_bytes = C::NUM_EXTENSION_COLUMNS * fq_size;
+        // the trace and its polynomials
+        let trace = 2 * self.trace_len * (base_column_bytes + extension_column_bytes);
+        let trace_lde = lde_size * (base_column_bytes + extension_column_bytes);
+        let constraint_evals = ce_size * fq_size;
+        // the composition polynomial and the columns it's split into
+        let composition_polys = (ce_size + self.composition_degree() + 1) * fq_size;
+        let composition_trace_lde = self.num_composition_columns * lde_size * fq_size;
+        let deep_composition_lde = lde_size * fq_size;
+        trace
+            + trace_lde
+            + constraint_evals
+            + composition_polys
+            + composition_trace_lde
+            + deep_composition_lde
+    }
 }
@@ -2,6 +2,8 @@ use crate::challenges::Challenges;
 use crate::fri;
 use crate::fri::FriProof;
 use crate::hints::Hints;
+use crate::proof::CompositionCommitment;
+use crate::prover::ProvingError;
 use crate::random::PublicCoin;
 use crate::stark::Stark;
 use crate::trace::Queries;
@@ -13,12 +15,118 @@ use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
 use std::collections::BTreeSet;
 
+/// Number of nonces tried between grinding progress updates
+const GRINDING_BATCH_SIZE: u64 = 1 << 20;
+
+/// Step of the Fiat-Shamir transcript. Commitments are absorbed and
+/// randomness is drawn from the public coin in the order of
+/// [`TRANSCRIPT_SCHEDULE`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptStep {
+    /// Absorb the domain separator
+    DomainSeparator,
+    /// Absorb the auxiliary data
+    AuxData,
+    /// Absorb the base trace commitment
+    BaseTrace,
+    /// Absorb the randomness beacon
+    RandomnessBeacon,
+    /// Draw the AIR challenges
+    Challenges,
+    /// Absorb the extension trace commitment
+    ExtensionTrace,
+    /// Draw the composition constraint coefficients
+    CompositionCoeffs,
+    /// Absorb the composition trace commitment or coefficients
+    CompositionTrace,
+    /// Draw the out-of-domain point
+    OodPoint,
+    /// Absorb the out-of-domain evaluations
+    OodEvals,
+    /// Draw the DEEP composition coefficients
+    DeepCoeffs,
+    /// Absorb each FRI layer commitment then draw its folding challenge and
+    /// absorb the remainder
+    Fri,
+    /// Absorb the proof of work nonce then draw the query positions
+    QueryPositions,
+}
+
+/// Order the prover and verifier take [`TranscriptStep`]s in. Every draw must
+/// come after the commitments it's meant to bind so reordering this breaks
+/// soundness as well as compatibility with existing proofs.
+pub const TRANSCRIPT_SCHEDULE: [TranscriptStep; 13] = [
+    TranscriptStep::DomainSeparator,
+    TranscriptStep::AuxData,
+    TranscriptStep::BaseTrace,
+    TranscriptStep::RandomnessBeacon,
+    TranscriptStep::Challenges,
+    TranscriptStep::ExtensionTrace,
+    TranscriptStep::CompositionCoeffs,
+    TranscriptStep::CompositionTrace,
+    TranscriptStep::OodPoint,
+    TranscriptStep::OodEvals,
+    TranscriptStep::DeepCoeffs,
+    TranscriptStep::Fri,
+    TranscriptStep::QueryPositions,
+];
+
+/// Value absorbed into or drawn from the public coin at a [`TranscriptStep`].
+/// The step determines how values are absorbed or drawn e.g. composition
+/// coefficients and the FRI remainder are absorbed as a vector. See
+/// [`Proof::transcript_log`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptEvent<F, D> {
+    AbsorbDigest {
+        step: TranscriptStep,
+        digest: D,
+    },
+    AbsorbElements {
+        step: TranscriptStep,
+        values: Vec<F>,
+    },
+    Draw {
+        step: TranscriptStep,
+        values: Vec<F>,
+    },
+    /// Absorbs the proof of work nonce (if there's grinding) then draws the
+    /// query positions
+    DrawQueries {
+        nonce: u64,
+        positions: Vec<usize>,
+    },
+}
+
+/// Position in [`TRANSCRIPT_SCHEDULE`]. Optional steps (e.g. the extension
+/// trace of an AIR without extension columns) can be skipped but a step
+/// can't be taken twice or before a step that precedes it in the schedule.
+#[derive(Debug, Clone, Default)]
+pub struct TranscriptCursor {
+    next: usize,
+}
+
+impl TranscriptCursor {
+    /// # Panics
+    ///
+    /// Panics if `step` is out of order with respect to the previous step
+    pub fn advance(&mut self, step: TranscriptStep) {
+        let position = TRANSCRIPT_SCHEDULE.iter().position(|s| *s == step).unwrap();
+        assert!(
+            position >= self.next,
+            "transcript step {step:?} is out of order"
+        );
+        self.next = position + 1;
+    }
+}
+
 pub struct ProverChannel<'a, S: Stark> {
     air: &'a Air<S::AirConfig>,
     pub public_coin: S::PublicCoin,
+    pub transcript: TranscriptCursor,
+    aux_data: Vec<S::Fp>,
     base_trace_commitment: S::Digest,
     extension_trace_commitment: Option<S::Digest>,
-    composition_trace_commitment: S::Digest,
+    composition_trace_commitment: CompositionCommitment<S::Digest, S::Fq>,
     fri_layer_commitments: Vec<S::Digest>,
     fri_remainder_coeffs: Vec<S::Fq>,
     execution_trace_ood_evals: Vec<S::Fq>,
@@ -28,13 +136,26 @@ pub struct ProverChannel<'a, S: Stark> {
 
 // impl<'a, A: Air, D: Digest> ProverChannel<'a, A, D> {
 impl<'a, S: Stark> ProverChannel<'a, S> {
-    pub fn new(air: &'a Air<S::AirConfig>, public_coin: S::PublicCoin) -> Self {
+    /// Creates a new channel. An optional domain separator is absorbed into the
+    /// transcript before any commitments are made.
+    pub fn new(
+        air: &'a Air<S::AirConfig>,
+        mut public_coin: S::PublicCoin,
+        domain_separator: Option<&[u8]>,
+    ) -> Self {
+        let mut transcript = TranscriptCursor::default();
+        if let Some(domain_separator) = domain_separator {
+            transcript.advance(TranscriptStep::DomainSeparator);
+            public_coin.reseed_with_bytes(domain_separator);
+        }
         ProverChannel {
             air,
             public_coin,
+            transcript,
+            aux_data: Vec::new(),
             extension_trace_commitment: None,
             base_trace_commitment: S::Digest::default(),
-            composition_trace_commitment: S::Digest::default(),
+            composition_trace_commitment: CompositionCommitment::Root(S::Digest::default()),
             execution_trace_ood_evals: Vec::new(),
             composition_trace_ood_evals: Vec::new(),
             fri_layer_commitments: Vec::new(),
@@ -43,23 +164,49 @@ impl<'a, S: Stark> ProverChannel<'a, S> {
         }
     }
 
+    /// Binds auxiliary data into the transcript. Must be called before any
+    /// trace commitments.
+    pub fn commit_aux_data(&mut self, aux_data: Vec<S::Fp>) {
+        if !aux_data.is_empty() {
+            self.transcript.advance(TranscriptStep::AuxData);
+            let aux_elements = aux_data
+                .iter()
+                .copied()
+                .map(S::Fq::from)
+                .collect::<Vec<_>>();
+            self.public_coin.reseed_with_field_elements(&aux_elements);
+        }
+        self.aux_data = aux_data;
+    }
+
     pub fn commit_base_trace(&mut self, commitment: S::Digest) {
+        self.transcript.advance(TranscriptStep::BaseTrace);
         self.public_coin.reseed_with_digest(&commitment);
         self.base_trace_commitment = commitment;
     }
 
+    pub fn commit_randomness_beacon(&mut self, beacon: S::Fq) {
+        self.transcript.advance(TranscriptStep::RandomnessBeacon);
+        self.public_coin.reseed_with_field_elements(&[beacon]);
+    }
+
     pub fn commit_extension_trace(&mut self, commitment: S::Digest) {
+        self.transcript.advance(TranscriptStep::ExtensionTrace);
         self.public_coin.reseed_with_digest(&commitment);
         self.extension_trace_commitment = Some(commitment);
     }
 
     pub fn commit_composition_trace(&mut self, commitment: S::Digest) {
+        self.transcript.advance(TranscriptStep::CompositionTrace);
         self.public_coin.reseed_with_digest(&commitment);
-        self.composition_trace_commitment = commitment;
+        self.composition_trace_commitment = CompositionCommitment::Root(commitment);
     }
 
-    pub fn get_ood_point(&mut self) -> S::Fq {
-        self.public_coin.draw()
+    /// Sends the composition polynomial coefficients in place of a commitment
+    pub fn commit_composition_coeffs(&mut self, coeffs: Vec<S::Fq>) {
+        self.transcript.advance(TranscriptStep::CompositionTrace);
+        self.public_coin.reseed_with_field_element_vector(&coeffs);
+        self.composition_trace_commitment = CompositionCommitment::Coeffs(coeffs);
     }
 
     pub fn send_ood_evals(
@@ -67,36 +214,74 @@ impl<'a, S: Stark> ProverChannel<'a, S> {
         execution_trace_oods: Vec<S::Fq>,
         composition_trace_oods: Vec<S::Fq>,
     ) {
+        self.transcript.advance(TranscriptStep::OodEvals);
         let ood_evals = [execution_trace_oods.clone(), composition_trace_oods.clone()].concat();
         self.public_coin.reseed_with_field_elements(&ood_evals);
         self.execution_trace_ood_evals = execution_trace_oods;
         self.composition_trace_ood_evals = composition_trace_oods;
     }
 
-    pub fn grind_fri_commitments(&mut self) {
+    /// Searches for a proof of work nonce trying at most `max_attempts` nonces.
+    /// Nonces are tried in batches and `on_progress` is called with the number
+    /// of nonces tried after each batch. With the `parallel` feature threads
+    /// search disjoint ranges of each batch and the first nonce found wins so
+    /// only the nonce's validity is deterministic, not its value.
+    pub fn grind_fri_commitments(
+        &mut self,
+        max_attempts: Option<u64>,
+        mut on_progress: impl FnMut(u64),
+    ) -> Result<(), ProvingError> {
         let grinding_factor = self.air.options().grinding_factor;
         if grinding_factor == 0 {
             // skip if there is no grinding required
-            return;
+            return Ok(());
+        }
+
+        // nonces start at 1 and the last one leaves room for an exclusive end
+        let last_nonce = max_attempts.map_or(u64::MAX - 1, |max| max.min(u64::MAX - 1));
+        let mut nonce = None;
+        let mut batch_start = 1;
+        while nonce.is_none() && batch_start <= last_nonce {
+            let batch_end = batch_start
+                .saturating_add(GRINDING_BATCH_SIZE)
+                .min(last_nonce + 1);
+            nonce = self
+                .public_coin
+                .grind_proof_of_work_in_range(grinding_factor, batch_start..batch_end);
+            on_progress(batch_end - 1);
+            batch_start = batch_end;
         }
 
-        let nonce = self
-            .public_coin
-            .grind_proof_of_work(grinding_factor)
-            .expect("nonce not found");
-        assert!(self
-            .public_coin
-            .verify_proof_of_work(grinding_factor, nonce));
+        let nonce = nonce
+            .filter(|&nonce| {
+                self.public_coin
+                    .verify_proof_of_work(grinding_factor, nonce)
+            })
+            .ok_or(match max_attempts {
+                Some(max_attempts) => ProvingError::GrindingExceededBudget {
+                    grinding_factor,
+                    max_attempts,
+                },
+                None => ProvingError::ProofOfWorkNotFound { grinding_factor },
+            })?;
 
         self.pow_nonce = nonce;
-        self.public_coin.reseed_with_int(self.pow_nonce);
+        Ok(())
     }
 
+    /// Draws query positions after absorbing the nonce found by
+    /// [`Self::grind_fri_commitments`]
     pub fn get_fri_query_positions(&mut self) -> BTreeSet<usize> {
         // TODO: voulnerability if multiple positions are the same
+        self.transcript.advance(TranscriptStep::QueryPositions);
         let lde_domain_size = self.air.trace_len() * self.air.lde_blowup_factor();
-        let num_queries = self.air.options().num_queries as usize;
-        self.public_coin.draw_queries(num_queries, lde_domain_size)
+        let options = self.air.options();
+        self.public_coin.draw_queries_after_grinding(
+            options.grinding_factor,
+            self.pow_nonce,
+            options.num_queries.into(),
+            lde_domain_size,
+        )
     }
 
     pub fn build_proof(
@@ -107,6 +292,7 @@ impl<'a, S: Stark> ProverChannel<'a, S> {
         Proof {
             options: self.air.options(),
             trace_len: self.air.trace_len(),
+            aux_data: self.aux_data,
             base_trace_commitment: self.base_trace_commitment,
             extension_trace_commitment: self.extension_trace_commitment,
             composition_trace_commitment: self.composition_trace_commitment,
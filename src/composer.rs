@@ -1,9 +1,10 @@
 use crate::air::AirConfig;
+#[cfg(debug_assertions)]
+use crate::prover::ProvingError;
 use crate::utils::divide_out_point_into;
 use crate::utils::divide_out_points_into;
 use crate::utils::horner_evaluate;
 use crate::utils::GpuAllocator;
-use crate::utils::GpuVec;
 use crate::Air;
 use crate::Matrix;
 use alloc::vec::Vec;
@@ -53,9 +54,10 @@ impl<'a, A: AirConfig> DeepPolyComposer<'a, A> {
         let g = trace_domain.group_gen();
         let g_inv = trace_domain.group_gen_inv();
 
-        let num_columns = A::NUM_BASE_COLUMNS + A::NUM_EXTENSION_COLUMNS;
-        let base_column_range = 0..A::NUM_BASE_COLUMNS;
-        let extension_column_range = A::NUM_BASE_COLUMNS..num_columns;
+        let num_base_columns = air.num_base_columns();
+        let num_columns = num_base_columns + A::NUM_EXTENSION_COLUMNS;
+        let base_column_range = 0..num_base_columns;
+        let extension_column_range = num_base_columns..num_columns;
 
         // generate ood evaluations for the execution trace polynomials
         let execution_trace_evals = ark_std::cfg_into_iter!(air.trace_arguments())
@@ -68,7 +70,7 @@ impl<'a, A: AirConfig> DeepPolyComposer<'a, A> {
                     horner_evaluate(coeffs, &x)
                 } else if extension_column_range.contains(&col_idx) {
                     let coeffs =
-                        &extension_trace_polys.as_deref().unwrap()[col_idx - A::NUM_BASE_COLUMNS];
+                        &extension_trace_polys.as_deref().unwrap()[col_idx - num_base_columns];
                     horner_evaluate(coeffs, &x)
                 } else {
                     panic!("column is {col_idx} but there are only {num_columns} columns")
@@ -85,7 +87,31 @@ impl<'a, A: AirConfig> DeepPolyComposer<'a, A> {
         (execution_trace_evals, composition_trace_evals)
     }
 
+    /// Like [`Self::into_deep_poly`] but checks the DEEP composition polynomial
+    /// has degree less than the trace length before it's handed to FRI. A
+    /// higher degree points at the constraints or their composition rather
+    /// than at FRI which would otherwise just fail to verify.
+    #[cfg(debug_assertions)]
+    pub fn into_deep_poly_with_degree_check(
+        self,
+        composition_coeffs: DeepCompositionCoeffs<A::Fq>,
+    ) -> Result<Matrix<A::Fq>, ProvingError> {
+        let trace_len = self.air.trace_len();
+        let deep_poly = self.into_deep_poly(composition_coeffs);
+        let degree = deep_poly.column_degrees().into_iter().max().unwrap_or(0);
+        if degree >= trace_len {
+            return Err(ProvingError::DeepPolyDegreeTooHigh {
+                degree,
+                max_degree: trace_len - 1,
+            });
+        }
+        Ok(deep_poly)
+    }
+
     // <https://medium.com/starkware/starkdex-deep-dive-the-stark-core-engine-497942d0f0ab>
+    // Quotients are computed in coefficient form with synthetic division so the
+    // prover does no field inversions here. The verifier batch inverts its
+    // denominators in `deep_composition_evaluations`.
     pub fn into_deep_poly(self, composition_coeffs: DeepCompositionCoeffs<A::Fq>) -> Matrix<A::Fq> {
         let Self {
             z,
@@ -114,9 +140,10 @@ impl<'a, A: AirConfig> DeepPolyComposer<'a, A> {
                 coeffs
             });
 
-        let num_columns = A::NUM_BASE_COLUMNS + A::NUM_EXTENSION_COLUMNS;
-        let base_column_range = 0..A::NUM_BASE_COLUMNS;
-        let extension_column_range = A::NUM_BASE_COLUMNS..num_columns;
+        let num_base_columns = air.num_base_columns();
+        let num_columns = num_base_columns + A::NUM_EXTENSION_COLUMNS;
+        let base_column_range = 0..num_base_columns;
+        let extension_column_range = num_base_columns..num_columns;
         let trace_arguments = air.trace_arguments();
         let execution_trace_xs_and_alphas = |col_idx| {
             let mut xs = Vec::new();
@@ -162,7 +189,13 @@ impl<'a, A: AirConfig> DeepPolyComposer<'a, A> {
                 .chain(extension_trace_quotients)
                 .collect(),
         );
-        let mut combined_coeffs = GpuVec::try_from(quotients.sum_columns()).unwrap();
+        // summing the columns leaves a single column
+        let mut combined_coeffs = quotients
+            .sum_columns()
+            .0
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| Vec::new_in(GpuAllocator));
 
         let chunk_size = 1 << 16;
         if degree_beta.is_zero() {
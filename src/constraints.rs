@@ -1,8 +1,14 @@
 use crate::expression::Expr;
 use crate::utils;
+use crate::utils::FieldVariant;
+use alloc::collections::BTreeMap;
 use alloc::collections::BTreeSet;
+use ark_ff::FftField;
+use ark_ff::Field;
 use ark_ff::One;
 use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
 use core::iter::Product;
 use core::iter::Sum;
 use core::ops::Add;
@@ -23,7 +29,17 @@ pub enum AlgebraicItem<T: 'static> {
     Constant(T),
     Challenge(usize),
     Periodic(PeriodicColumn<'static, T>),
+    /// Value of a fixed column (see [`crate::air::AirConfig::fixed_columns`])
+    /// at the current row. Fixed columns are evaluated like the periodic
+    /// columns returned by [`crate::Air::fixed_columns`] by the prover and
+    /// verifier rather than committed to.
+    Fixed(usize),
     Hint(usize),
+    /// Value of a trace column at the current row plus an offset. The trace
+    /// isn't split into segments: base columns come first then extension
+    /// columns and every table shares this index space. A constraint between
+    /// tables, like the processor's memory value matching the memory table's
+    /// at the same row, references both columns directly.
     Trace(/* =column */ usize, /* =offset */ isize),
 }
 
@@ -34,13 +50,20 @@ impl<T> AlgebraicItem<T> {
         match &self {
             // TODO: handle implications of a zero?
             Constant(_) | Challenge(_) | Hint(_) => Degree(0, 0),
-            Trace(_, _) => Degree(trace_degree, 0),
+            Trace(_, _) | Fixed(_) => Degree(trace_degree, 0),
             Periodic(col) => col.degree(trace_degree),
             X => Degree(1, 0),
         }
     }
 }
 
+/// Wraps an integer in a constant e.g. `A.curr() - AlgebraicItem::from(1)`
+impl<T: From<u64>> From<u64> for AlgebraicItem<T> {
+    fn from(v: u64) -> Self {
+        Self::Constant(T::from(v))
+    }
+}
+
 impl<T: Zero> Sum<Self> for Expr<AlgebraicItem<T>> {
     fn sum<I: Iterator<Item = Self>>(mut iter: I) -> Self {
         let zero = AlgebraicItem::Constant(T::zero()).into();
@@ -157,6 +180,17 @@ impl<T> Constraint<T> {
         (numerator_degree, denominator_degree)
     }
 
+    /// Returns the degree of the constraint as a polynomial in the trace cells
+    /// it reads. `X`, periodic and fixed columns, challenges and hints count
+    /// as constants.
+    pub fn trace_degree(&self) -> usize {
+        let Degree(numerator_degree, _) = self.0.eval(&mut |leaf| match leaf {
+            AlgebraicItem::Trace(_, _) => Degree(1, 0),
+            _ => Degree(0, 0),
+        });
+        numerator_degree
+    }
+
     /// Returns the power-of-2 degree blowup observed by evaluating constraints
     /// over the trace polynomials.
     pub fn blowup_factor(&self, trace_len: usize) -> usize {
@@ -165,6 +199,15 @@ impl<T> Constraint<T> {
         blowup_factor(numerator_degree, denominator_degree, trace_degree)
     }
 
+    /// Returns the number of trace length sized columns needed to hold the
+    /// coefficients of the constraint's evaluation polynomial. Unlike
+    /// [`Self::blowup_factor`] this isn't rounded up to a power of two.
+    pub fn num_columns(&self, trace_len: usize) -> usize {
+        let trace_degree = trace_len - 1;
+        let (numerator_degree, denominator_degree) = self.degree(trace_degree);
+        num_columns(numerator_degree, denominator_degree, trace_len)
+    }
+
     /// Returns the evaluation result if the numerator is 0 when the denominator
     /// is 0 otherwise returns None. This can be used as a heuristic check by
     /// the prover to ensure they have a valid execution trace.
@@ -248,6 +291,87 @@ impl<T> Constraint<T> {
         self.0.eval(&mut |leaf| CheckedEval(Some(f(leaf)))).0
     }
 
+    /// Evaluates the constraint as a rational function without dividing.
+    /// Output is of the form `(numerator, denominator)`. Their degrees in X are
+    /// bounded by [`Self::degree`] so evaluating them over a large enough
+    /// domain lets the prover check the division is exact.
+    pub fn eval_rational(&self, f: &mut impl FnMut(&AlgebraicItem<T>) -> T) -> (T, T)
+    where
+        T: Clone
+            + One
+            + Neg<Output = T>
+            + Add<Output = T>
+            + Mul<Output = T>
+            + Pow<usize, Output = T>,
+    {
+        /// Fraction of the form `(numerator, denominator)`
+        pub struct Rational<T>(T, T);
+
+        impl<T: Neg<Output = T>> Neg for Rational<T> {
+            type Output = Self;
+
+            fn neg(self) -> Self::Output {
+                Self(-self.0, self.1)
+            }
+        }
+
+        impl<T: Clone + Add<Output = T> + Mul<Output = T>> Add for Rational<T> {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self::Output {
+                let Self(an, ad) = self;
+                let Self(bn, bd) = rhs;
+                Self(an * bd.clone() + bn * ad.clone(), ad * bd)
+            }
+        }
+
+        impl<T: Mul<Output = T>> Div for Rational<T> {
+            type Output = Self;
+
+            fn div(self, rhs: Self) -> Self::Output {
+                let Self(an, ad) = self;
+                let Self(bn, bd) = rhs;
+                Self(an * bd, ad * bn)
+            }
+        }
+
+        impl<T: Mul<Output = T>> Mul for Rational<T> {
+            type Output = Self;
+
+            fn mul(self, rhs: Self) -> Self::Output {
+                let Self(an, ad) = self;
+                let Self(bn, bd) = rhs;
+                Self(an * bn, ad * bd)
+            }
+        }
+
+        impl<T: Pow<usize, Output = T>> Pow<usize> for Rational<T> {
+            type Output = Self;
+
+            fn pow(self, rhs: usize) -> Self::Output {
+                let Self(n, d) = self;
+                Self(n.pow(rhs), d.pow(rhs))
+            }
+        }
+
+        let Rational(numerator, denominator) = self.0.eval(&mut |leaf| Rational(f(leaf), T::one()));
+        (numerator, denominator)
+    }
+
+    /// Returns the periodic columns the constraint reads
+    pub fn periodic_columns(&self) -> BTreeSet<PeriodicColumn<'static, T>>
+    where
+        T: Clone + Ord,
+    {
+        let mut columns = BTreeSet::new();
+        self.traverse(&mut |node| {
+            if let Expr::Leaf(AlgebraicItem::Periodic(column)) = node {
+                columns.insert(column.clone());
+            }
+        });
+        columns
+    }
+
     // Adapted from https://github.com/0xProject/OpenZKP
     pub fn trace_arguments(&self) -> BTreeSet<(usize, isize)> {
         let mut arguments = BTreeSet::new();
@@ -258,6 +382,63 @@ impl<T> Constraint<T> {
         });
         arguments
     }
+
+    /// Returns the sorted and deduplicated `(column, offset)` pairs the
+    /// constraint reads. Useful for planning a trace layout e.g. finding the
+    /// largest row offset a constraint looks ahead.
+    pub fn referenced_columns(&self) -> Vec<(usize, isize)> {
+        self.trace_arguments().into_iter().collect()
+    }
+
+    /// Returns the constraint with each trace column replaced by the column it
+    /// maps to. Columns missing from `mapping` are kept. Lets a constraint be
+    /// written once as a template and instantiated for different columns.
+    pub fn remap_columns(&self, mapping: &BTreeMap<usize, usize>) -> Self
+    where
+        T: Clone + Ord,
+    {
+        Self::new(self.map_leaves(&mut |leaf| match leaf {
+            &AlgebraicItem::Trace(column, offset) => {
+                AlgebraicItem::Trace(*mapping.get(&column).unwrap_or(&column), offset)
+            }
+            leaf => leaf.clone(),
+        }))
+    }
+}
+
+impl<Fp: FftField, Fq: Field> Constraint<FieldVariant<Fp, Fq>> {
+    /// Returns a constraint that enforces `expression` at every row of the
+    /// trace except the last i.e. `expression ⋅ (X - ωⁿ⁻¹) / (Xⁿ - 1)`
+    pub fn transition(
+        expression: Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>,
+        trace_domain: &Radix2EvaluationDomain<Fp>,
+    ) -> Self {
+        let last_row = trace_domain.size() - 1;
+        Self::transition_with_exempt_rows(expression, trace_domain, &[last_row])
+    }
+
+    /// Returns a constraint that enforces `expression` at every row of the
+    /// trace except `exempt_rows` e.g. when the last few rows are constrained
+    /// by boundary constraints instead
+    ///
+    /// # Panics
+    /// Panics if an exempt row is outside the trace domain
+    pub fn transition_with_exempt_rows(
+        expression: Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>,
+        trace_domain: &Radix2EvaluationDomain<Fp>,
+        exempt_rows: &[usize],
+    ) -> Self {
+        let one = AlgebraicItem::Constant(FieldVariant::Fp(Fp::one()));
+        let vanishing_poly = AlgebraicItem::X.pow(trace_domain.size()) - one;
+        let exemptions = exempt_rows
+            .iter()
+            .map(|&row| row_divisor(trace_domain, row))
+            .reduce(|a, b| a * b);
+        Self::new(match exemptions {
+            Some(exemptions) => expression * (exemptions / vanishing_poly),
+            None => expression / vanishing_poly,
+        })
+    }
 }
 
 impl<T> From<Expr<AlgebraicItem<T>>> for Constraint<T> {
@@ -325,6 +506,14 @@ impl<T: Clone + Copy + Zero + Ord + Hash> CompositionConstraint<T> {
         let (numerator_degree, denominator_degree) = self.degree(trace_degree);
         blowup_factor(numerator_degree, denominator_degree, trace_degree)
     }
+
+    /// Returns the number of trace length sized columns the composition
+    /// polynomial is split into.
+    pub fn num_columns(&self, trace_len: usize) -> usize {
+        let trace_degree = trace_len - 1;
+        let (numerator_degree, denominator_degree) = self.degree(trace_degree);
+        num_columns(numerator_degree, denominator_degree, trace_len)
+    }
 }
 
 impl<T> Deref for CompositionConstraint<T> {
@@ -335,6 +524,86 @@ impl<T> Deref for CompositionConstraint<T> {
     }
 }
 
+/// Returns the divisor `X - ωʳᵒʷ` of a boundary constraint that asserts a value
+/// at `row` of the trace. Unlike the usual first and last row divisors `row`
+/// can be any row of the trace e.g. `(3.curr() - value) / row_divisor(d, 100)`
+/// asserts column 3 equals `value` at row 100.
+///
+/// # Panics
+/// Panics if `row` is outside the trace domain
+pub fn row_divisor<Fp: FftField, Fq: Field>(
+    trace_domain: &Radix2EvaluationDomain<Fp>,
+    row: usize,
+) -> Expr<AlgebraicItem<FieldVariant<Fp, Fq>>> {
+    let trace_len = trace_domain.size();
+    assert!(
+        row < trace_len,
+        "row {row} is outside the trace of length {trace_len}"
+    );
+    let row_x = AlgebraicItem::Constant(FieldVariant::Fp(trace_domain.element(row)));
+    AlgebraicItem::X - row_x
+}
+
+/// Returns a selector that is `1` at rows `i` with `i ≡ r (mod k)` and `0` at
+/// every other row e.g. `row_selector(d, 2, 0)` selects the even rows.
+///
+/// There is no leaf for the row index itself. The out-of-domain point `z`
+/// isn't in the trace domain so it has no row and the polynomial that
+/// interpolates the row index has degree `trace_len - 1`. A selector is a
+/// polynomial in `X` of degree `trace_len - trace_len / k` so like any other
+/// expression the verifier evaluates it at `z` directly.
+///
+/// # Panics
+/// Panics if `k` isn't a power of two dividing the trace length
+pub fn row_selector<Fp: FftField, Fq: Field>(
+    trace_domain: &Radix2EvaluationDomain<Fp>,
+    k: usize,
+    r: usize,
+) -> Expr<AlgebraicItem<FieldVariant<Fp, Fq>>> {
+    let trace_len = trace_domain.size();
+    assert!(
+        k.is_power_of_two() && k <= trace_len,
+        "{k} doesn't divide {trace_len}"
+    );
+    // `y = X^(n/k)` is `ωₖⁱ` at row `i` so the selector is `1/k ⋅ Σⱼ (ωₖ⁻ʳ⋅y)ʲ`
+    let root = Fp::get_root_of_unity(k as u64).unwrap();
+    let shift = root.inverse().unwrap().pow([r as u64]);
+    let k_inv = Fp::from(k as u64).inverse().unwrap();
+    let coeff = |j: usize| {
+        let coeff = k_inv * shift.pow([j as u64]);
+        AlgebraicItem::Constant(FieldVariant::Fp(coeff))
+    };
+    (1..k).fold(coeff(0).into(), |selector: Expr<_>, j| {
+        selector + AlgebraicItem::X.pow(j * trace_len / k) * coeff(j)
+    })
+}
+
+/// Returns the value of an extension field column stored in the base trace as
+/// `[Fq:Fp]` coordinate columns starting at `first_column` (see
+/// [`Matrix::append_extension_column`](crate::Matrix::append_extension_column)).
+/// The value is `Σᵢ cᵢ⋅eᵢ` where `cᵢ` is column `first_column + i` and `eᵢ` is
+/// the `i`th basis element of `Fq` over `Fp`.
+///
+/// This lets an AIR hold a few extension field columns under the base trace
+/// commitment rather than committing to a separate extension trace. Since the
+/// base trace is committed first these columns can't depend on challenges.
+pub fn extension_column<Fp: Field, Fq: Field<BasePrimeField = Fp>>(
+    first_column: usize,
+    offset: isize,
+) -> Expr<AlgebraicItem<FieldVariant<Fp, Fq>>> {
+    let degree = usize::try_from(Fq::extension_degree()).unwrap();
+    let basis_element = |i: usize| {
+        let mut coords = vec![Fp::zero(); degree];
+        coords[i] = Fp::one();
+        Fq::from_base_prime_field_elems(&coords).unwrap()
+    };
+    let term = |i: usize| {
+        let coord = AlgebraicItem::Trace(first_column + i, offset);
+        coord * AlgebraicItem::Constant(FieldVariant::Fq(basis_element(i)))
+    };
+    (1..degree).fold(term(0), |value, i| value + term(i))
+}
+
 /// Returns the power-of-2 degree blowup observed by evaluating constraints
 /// over the trace polynomials.
 const fn blowup_factor(
@@ -346,6 +615,17 @@ const fn blowup_factor(
     utils::ceil_power_of_two(degree) / trace_degree
 }
 
+/// Returns the number of columns of length `trace_len` needed to store the
+/// coefficients of a polynomial with the given degree.
+const fn num_columns(
+    numerator_degree: usize,
+    denominator_degree: usize,
+    trace_len: usize,
+) -> usize {
+    let degree = numerator_degree.saturating_sub(denominator_degree);
+    (degree + trace_len) / trace_len
+}
+
 pub trait Hint {
     fn index(&self) -> usize;
 
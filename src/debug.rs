@@ -1,128 +1,176 @@
 //! Tools for debugging issues that may arrive with AIR or STARK
-//! TODO:
 
 use crate::challenges::Challenges;
+use crate::constraints::AlgebraicItem;
+use crate::constraints::PeriodicColumn;
 use crate::hints::Hints;
+use crate::prover::ProvingError;
 use crate::stark::Stark;
+use crate::utils::horner_evaluate;
+use crate::utils::FieldVariant;
+use crate::Air;
 use crate::Matrix;
+use alloc::vec::Vec;
+use ark_ff::Field;
+#[cfg(debug_assertions)]
+use ark_ff::Zero;
+#[cfg(debug_assertions)]
+use ark_poly::univariate::DenseOrSparsePolynomial;
+#[cfg(debug_assertions)]
+use ark_poly::univariate::DensePolynomial;
+#[cfg(debug_assertions)]
+use ark_poly::DenseUVPolynomial;
+use ark_poly::EvaluationDomain;
+#[cfg(debug_assertions)]
+use ark_poly::Radix2EvaluationDomain;
 
-/// Checks AIR constraints are valid
-pub const fn default_validate_constraints<S: Stark>(
+/// Checks every AIR constraint holds at every row of the trace
+///
+/// Only the trace domain is checked, not the LDE or constraint evaluation
+/// domains. A constraint holds at a row if it evaluates to a value i.e. its
+/// numerator is zero wherever its denominator vanishes. Returns the first
+/// constraint and row that fails as [`ProvingError::UnsatisfiedConstraint`].
+/// The offending point is the `row`th element of the trace domain.
+///
+/// Debug builds also check every constraint divides exactly (see
+/// [`ProvingError::ConstraintNotDivisible`]). Interpolating every constraint
+/// is much slower than checking the rows so release builds skip it.
+pub fn default_validate_constraints<S: Stark>(
     _this: &S,
-    _challenges: &Challenges<S::Fq>,
-    _hints: &Hints<S::Fq>,
-    _base_trace: &Matrix<S::Fp>,
-    _extension_trace: Option<&Matrix<S::Fq>>,
-) {
-    // ```text
-    // TODO: move constraint checking from air.rs into here
-    // #[cfg(all(feature = "std", debug_assertions))]
-    // fn validate_constraints(
-    //     &self,
-    //     challenges: &Challenges<C::Fq>,
-    //     hints: &Hints<C::Fq>,
-    //     base_trace: &crate::Matrix<C::Fp>,
-    //     extension_trace: Option<&crate::Matrix<C::Fq>>,
-    // ) {
-    //     use AlgebraicItem::*;
-    //     use Expr::*;
+    air: &Air<S::AirConfig>,
+    challenges: &Challenges<S::Fq>,
+    hints: &Hints<S::Fq>,
+    base_trace: &Matrix<S::Fp>,
+    extension_trace: Option<&Matrix<S::Fq>>,
+) -> Result<(), ProvingError> {
+    use AlgebraicItem::*;
+    let trace_domain = air.trace_domain();
+    let trace_len = trace_domain.size();
+    let num_base_columns = base_trace.num_cols();
+    let fixed_columns = air.fixed_columns();
+    let trace_value = |row: usize, col: usize, offset: isize| {
+        #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+        let pos = (row as isize + offset).rem_euclid(trace_len as isize) as usize;
+        if col < num_base_columns {
+            FieldVariant::Fp(base_trace.0[col][pos])
+        } else {
+            FieldVariant::Fq(extension_trace.unwrap().0[col - num_base_columns][pos])
+        }
+    };
 
-    //     let num_execution_trace_columns = C::NUM_BASE_COLUMNS +
-    // C::NUM_EXTENSION_COLUMNS;     let mut col_indicies = vec![false;
-    // num_execution_trace_columns];     let mut challenge_indicies =
-    // vec![false; challenges.len()];     let mut hint_indicies =
-    // vec![false; hints.len()];
-
-    //     for constraint in &self.constraints {
-    //         constraint.traverse(&mut |node| match node {
-    //             Leaf(Challenge(i)) => challenge_indicies[*i] = true,
-    //             Leaf(Trace(i, _)) => col_indicies[*i] = true,
-    //             Leaf(Hint(i)) => hint_indicies[*i] = true,
-    //             _ => {}
-    //         })
-    //     }
-
-    //     for (index, exists) in col_indicies.into_iter().enumerate() {
-    //         if !exists {
-    //             // TODO: make assertion
-    //             println!("WARN: no constraints for execution trace column
-    // {index}");         }
-    //     }
-
-    //     for (index, exists) in challenge_indicies.into_iter().enumerate()
-    // {         if !exists {
-    //             // TODO: make assertion
-    //             println!("WARN: challenge at index {index} never used");
-    //         }
-    //     }
-
-    //     for (index, exists) in hint_indicies.into_iter().enumerate() {
-    //         if !exists {
-    //             // TODO: make assertion
-    //             println!("WARN: hint at index {index} never used");
-    //         }
-    //     }
-
-    //     let trace_domain = self.trace_domain();
-    //     let base_column_range = Self::base_column_range();
-    //     let extension_column_range = Self::extension_column_range();
-
-    //     // helper function to get a value from the execution trace
-    //     let get_trace_value = |row: usize, col: usize, offset: isize| {
-    //         let pos = (row as isize +
-    // offset).rem_euclid(trace_domain.size() as isize) as usize;
-    // if base_column_range.contains(&col) {
-    // FieldVariant::Fp(base_trace.0[col][pos])         } else if
-    // extension_column_range.contains(&col) {             let col =
-    // col - C::NUM_BASE_COLUMNS;
-    // FieldVariant::Fq(extension_trace.unwrap().0[col][pos])
-    //         } else {
-    //             unreachable!("requested column {col} does not exist")
-    //         }
-    //     };
-
-    //     for (c_idx, constraint) in
-    // self.constraints().into_iter().enumerate() {         for
-    // (row, x) in trace_domain.elements().enumerate() {
-    // let is_valid = constraint                 .check(&mut |leaf|
-    // match leaf {                     X => FieldVariant::Fp(x),
-    //                     &Hint(i) => FieldVariant::Fq(hints[i]),
-    //                     &Challenge(i) => FieldVariant::Fq(challenges[i]),
-    //                     &Trace(col, offset) => get_trace_value(row, col,
-    // offset),                     &Constant(c) => c,
-    //                 })
-    //                 .is_some();
+    for (constraint_idx, constraint) in air.constraints().iter().enumerate() {
+        for (row, x) in trace_domain.elements().enumerate() {
+            let evaluation = constraint.check(&mut |leaf| match leaf {
+                X => FieldVariant::Fp(x),
+                &Constant(c) => c,
+                &Challenge(i) => FieldVariant::Fq(challenges[i]),
+                &Hint(i) => FieldVariant::Fq(hints[i]),
+                &Periodic(col) => periodic_value::<S>(col, trace_len, x),
+                &Trace(col, offset) => trace_value(row, col, offset),
+                &Fixed(i) => periodic_value::<S>(fixed_columns[i], trace_len, x),
+            });
+            if evaluation.is_none() {
+                return Err(ProvingError::UnsatisfiedConstraint {
+                    constraint: constraint_idx,
+                    row,
+                });
+            }
+        }
+    }
+    #[cfg(debug_assertions)]
+    validate_divisibility::<S>(air, challenges, hints, base_trace, extension_trace)?;
+    Ok(())
+}
 
-    //             if !is_valid {
-    //                 let mut vals = vec![format!("x = {x}")];
-    //                 constraint.traverse(&mut |node| match *node {
-    //                     // get a description of each leaf node
-    //                     Leaf(Trace(col, offset)) => vals.push(format!(
-    //                         "Trace(col={col:0>3}, offset={offset:0>3}) =
-    // {}",                         get_trace_value(row, col,
-    // offset)                     )),
-    //                     Leaf(Challenge(i)) => {
-    //                         vals.push(format!("Challenge({i}) = {}",
-    // challenges[i]))                     }
-    //                     Leaf(Hint(i)) => vals.push(format!("Hint({i}) =
-    // {}", hints[i])),                     // skip tree nodes
-    //                     _ => (),
-    //                 });
+/// Checks the denominator of every constraint divides its numerator
+///
+/// Rows of the trace domain only catch denominators that vanish on the trace
+/// domain. A constraint divided by `X - t` for a `t` that isn't a root of the
+/// numerator holds at every row but isn't a polynomial so the proof wouldn't
+/// verify. Numerators and denominators are evaluated separately over a coset
+/// large enough to interpolate them and the polynomial division must leave no
+/// remainder. Returns the first such constraint as
+/// [`ProvingError::ConstraintNotDivisible`].
+#[cfg(debug_assertions)]
+fn validate_divisibility<S: Stark>(
+    air: &Air<S::AirConfig>,
+    challenges: &Challenges<S::Fq>,
+    hints: &Hints<S::Fq>,
+    base_trace: &Matrix<S::Fp>,
+    extension_trace: Option<&Matrix<S::Fq>>,
+) -> Result<(), ProvingError> {
+    use AlgebraicItem::*;
+    let trace_domain = air.trace_domain();
+    let trace_len = trace_domain.size();
+    let max_degree = air
+        .constraints()
+        .iter()
+        .map(|constraint| {
+            let (numerator_degree, denominator_degree) = constraint.degree(trace_len - 1);
+            numerator_degree.max(denominator_degree)
+        })
+        .max()
+        .unwrap_or(0);
+    let domain_size = (max_degree + 1).next_power_of_two().max(trace_len);
+    let domain = Radix2EvaluationDomain::new_coset(domain_size, air.domain_offset()).unwrap();
+    let base_evals = base_trace
+        .interpolate(trace_domain)
+        .into_evaluations(domain);
+    let extension_evals =
+        extension_trace.map(|t| t.interpolate(trace_domain).into_evaluations(domain));
+    let num_base_columns = base_evals.num_cols();
+    let fixed_columns = air.fixed_columns();
+    // a row offset on the trace domain is this many steps on the coset
+    let step = domain_size / trace_len;
+    let trace_value = |position: usize, col: usize, offset: isize| {
+        #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+        let pos =
+            (position as isize + offset * step as isize).rem_euclid(domain_size as isize) as usize;
+        if col < num_base_columns {
+            FieldVariant::Fp(base_evals.0[col][pos])
+        } else {
+            FieldVariant::Fq(extension_evals.as_ref().unwrap().0[col - num_base_columns][pos])
+        }
+    };
 
-    //                 vals.sort();
-    //                 vals.dedup();
+    for (constraint_idx, constraint) in air.constraints().iter().enumerate() {
+        let (numerator_evals, denominator_evals): (Vec<S::Fq>, Vec<S::Fq>) = domain
+            .elements()
+            .enumerate()
+            .map(|(position, x)| {
+                let (numerator, denominator) = constraint.eval_rational(&mut |leaf| match leaf {
+                    X => FieldVariant::Fp(x),
+                    &Constant(c) => c,
+                    &Challenge(i) => FieldVariant::Fq(challenges[i]),
+                    &Hint(i) => FieldVariant::Fq(hints[i]),
+                    &Periodic(col) => periodic_value::<S>(col, trace_len, x),
+                    &Trace(col, offset) => trace_value(position, col, offset),
+                    &Fixed(i) => periodic_value::<S>(fixed_columns[i], trace_len, x),
+                });
+                (numerator.as_fq(), denominator.as_fq())
+            })
+            .unzip();
+        let numerator = DensePolynomial::from_coefficients_vec(domain.ifft(&numerator_evals));
+        let denominator = DensePolynomial::from_coefficients_vec(domain.ifft(&denominator_evals));
+        let is_divisible = DenseOrSparsePolynomial::from(numerator)
+            .divide_with_q_and_r(&denominator.into())
+            .is_some_and(|(_, remainder)| remainder.is_zero());
+        if !is_divisible {
+            return Err(ProvingError::ConstraintNotDivisible {
+                constraint: constraint_idx,
+            });
+        }
+    }
+    Ok(())
+}
 
-    //                 // TODO: display constraint? eprintln!("Constraint
-    // is:\n{constraint}\n");                 #[cfg(feature = "std")]
-    //                 eprint!("Constraint {c_idx} does not evaluate to a
-    // low degree polynomial. ");                 #[cfg(feature =
-    // "std")]                 eprintln!("Divide by zero occurs at
-    // row {row}.\n");                 #[cfg(feature = "std")]
-    //                 eprintln!("Expression values:\n{}", vals.join("\n"));
-    //                 panic!();
-    //             }
-    //         }
-    //     }
-    // }
-    // ```
+/// Evaluates a periodic column of a trace with `trace_len` rows at `x`
+fn periodic_value<S: Stark>(
+    col: PeriodicColumn<'_, FieldVariant<S::Fp, S::Fq>>,
+    trace_len: usize,
+    x: S::Fp,
+) -> FieldVariant<S::Fp, S::Fq> {
+    let point = S::Fq::from(x.pow([(trace_len / col.interval_size()) as u64]));
+    let coeffs = col.coeffs().iter().map(FieldVariant::as_fq);
+    FieldVariant::Fq(horner_evaluate(&coeffs.collect::<Vec<_>>(), &point))
 }
@@ -0,0 +1,246 @@
+use crate::hash::Digest;
+use crate::hash::ElementHashFn;
+use crate::hash::HashFn;
+use crate::merkle::Error;
+use crate::merkle::HashedLeafConfig;
+use crate::merkle::MerkleTree;
+use crate::merkle::MerkleTreeImpl;
+use crate::merkle::MerkleView;
+use crate::Matrix;
+use alloc::vec::Vec;
+use ark_ff::Field;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use core::iter::zip;
+use core::marker::PhantomData;
+use rand::Rng;
+
+/// Commitment to the rows of a matrix where some columns are public and the
+/// rest are hidden behind salted commitments
+///
+/// Each private column gets its own random salt and every cell in the column
+/// is committed to as `merge(merge_with_int(salt, row), hash(value))`. Public
+/// cells are committed to as `hash(value)`. A row's leaf is the hash of its
+/// cell commitments so cells can be opened without revealing the rest of the
+/// row. Disclosing a column's salt later lets anyone check its values against
+/// the same root.
+pub struct DisclosureTree<F: Field, H: ElementHashFn<F>> {
+    merkle_tree: MerkleTreeImpl<HashedLeafConfig<H>>,
+    cell_commitments: Vec<Vec<H::Digest>>,
+    salts: Vec<Option<H::Digest>>,
+    _phantom: PhantomData<F>,
+}
+
+/// Opening of a set of rows of a [`DisclosureTree`]
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct DisclosureProof<F: Field, D: Digest> {
+    /// Salts of the private columns that are disclosed
+    pub salts: Vec<Option<D>>,
+    /// Values of the opened rows. Cells that stay hidden are `None`
+    pub rows: Vec<Vec<Option<F>>>,
+    /// Commitments of the hidden cells in row major order
+    pub hidden_cells: Vec<D>,
+    pub merkle_proof: MerkleView<D, D>,
+}
+
+impl<F: Field, H: ElementHashFn<F>> DisclosureTree<F, H> {
+    /// Commits to the rows of `matrix`. Columns not in `public_columns` are
+    /// salted with fresh randomness.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the number of rows is not a power of two.
+    pub fn new(
+        matrix: &Matrix<F>,
+        public_columns: &[usize],
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        let salts = (0..matrix.num_cols())
+            .map(|col| {
+                let is_public = public_columns.contains(&col);
+                (!is_public).then(|| H::hash(rng.gen::<[u8; 32]>()))
+            })
+            .collect::<Vec<_>>();
+        let cell_commitments = (0..matrix.num_rows())
+            .map(|row| {
+                let values = matrix.get_row(row).unwrap();
+                zip(&salts, values)
+                    .map(|(salt, value)| commit_to_cell::<F, H>(salt.as_ref(), row, value))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let leaves = cell_commitments
+            .iter()
+            .map(|row| hash_row::<H>(row))
+            .collect();
+        Ok(Self {
+            merkle_tree: MerkleTreeImpl::new(leaves)?,
+            cell_commitments,
+            salts,
+            _phantom: PhantomData,
+        })
+    }
+
+    pub fn root(&self) -> H::Digest {
+        self.merkle_tree.root()
+    }
+
+    /// Returns the salt of a private column. Sharing it discloses the column.
+    pub fn column_salt(&self, col: usize) -> Option<&H::Digest> {
+        self.salts.get(col)?.as_ref()
+    }
+
+    /// Opens the public columns and the private columns in `disclosed_columns`
+    /// at the given rows. Rows must be sorted and distinct.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a row is out of bounds.
+    pub fn open(
+        &self,
+        matrix: &Matrix<F>,
+        row_ids: &[usize],
+        disclosed_columns: &[usize],
+    ) -> Result<DisclosureProof<F, H::Digest>, Error> {
+        let salts = self
+            .salts
+            .iter()
+            .enumerate()
+            .map(|(col, salt)| salt.clone().filter(|_| disclosed_columns.contains(&col)))
+            .collect::<Vec<_>>();
+        let mut rows = Vec::new();
+        let mut hidden_cells = Vec::new();
+        for &row in row_ids {
+            let values = matrix.get_row(row).ok_or(Error::LeafIndexOutOfBounds {
+                i: row,
+                n: matrix.num_rows(),
+            })?;
+            let row_values = zip(&self.salts, &salts)
+                .zip(values)
+                .enumerate()
+                .map(|(col, ((salt, disclosed_salt), value))| {
+                    if salt.is_none() || disclosed_salt.is_some() {
+                        Some(value)
+                    } else {
+                        hidden_cells.push(self.cell_commitments[row][col].clone());
+                        None
+                    }
+                })
+                .collect();
+            rows.push(row_values);
+        }
+        Ok(DisclosureProof {
+            salts,
+            rows,
+            hidden_cells,
+            merkle_proof: self.merkle_tree.prove(row_ids)?,
+        })
+    }
+
+    /// Verifies an opening against a root. Every column in `public_columns`
+    /// must be opened and private columns can only be opened with their salt.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the opening doesn't resolve to the root.
+    pub fn verify(
+        root: &H::Digest,
+        public_columns: &[usize],
+        row_ids: &[usize],
+        proof: DisclosureProof<F, H::Digest>,
+    ) -> Result<(), Error> {
+        let DisclosureProof {
+            salts,
+            rows,
+            hidden_cells,
+            merkle_proof,
+        } = proof;
+        if rows.len() != row_ids.len() || merkle_proof.initial_leaves.len() != row_ids.len() {
+            return Err(Error::InvalidProof);
+        }
+        let mut hidden_cells = hidden_cells.into_iter();
+        for ((&row, values), leaf) in zip(row_ids, rows).zip(&merkle_proof.initial_leaves) {
+            if values.len() != salts.len() {
+                return Err(Error::InvalidProof);
+            }
+            let mut cell_commitments = Vec::new();
+            for (col, (salt, value)) in zip(&salts, values).enumerate() {
+                let is_public = public_columns.contains(&col);
+                let cell_commitment = match (value, salt) {
+                    (Some(value), None) if is_public => commit_to_cell::<F, H>(None, row, value),
+                    (Some(value), Some(salt)) if !is_public => {
+                        commit_to_cell::<F, H>(Some(salt), row, value)
+                    }
+                    (None, _) if !is_public => hidden_cells.next().ok_or(Error::InvalidProof)?,
+                    _ => return Err(Error::InvalidProof),
+                };
+                cell_commitments.push(cell_commitment);
+            }
+            if hash_row::<H>(&cell_commitments) != *leaf {
+                return Err(Error::LeafMismatch { i: row });
+            }
+        }
+        if hidden_cells.next().is_some() {
+            return Err(Error::InvalidProof);
+        }
+        MerkleTreeImpl::<HashedLeafConfig<H>>::verify(root, merkle_proof, row_ids)
+    }
+}
+
+fn commit_to_cell<F: Field, H: ElementHashFn<F>>(
+    salt: Option<&H::Digest>,
+    row: usize,
+    value: F,
+) -> H::Digest {
+    let value_hash = H::hash_elements([value]);
+    match salt {
+        Some(salt) => H::merge(&H::merge_with_int(salt, row as u64), &value_hash),
+        None => value_hash,
+    }
+}
+
+fn hash_row<H: HashFn>(cell_commitments: &[H::Digest]) -> H::Digest {
+    let bytes = cell_commitments
+        .iter()
+        .map(Digest::as_bytes)
+        .collect::<Vec<_>>();
+    H::hash_chunks(bytes.iter().map(|bytes| bytes.as_slice()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DisclosureTree;
+    use crate::hash::Sha256HashFn;
+    use crate::merkle::Error;
+    use crate::utils::GpuAllocator;
+    use crate::Matrix;
+    use ark_ff::UniformRand;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+
+    type Tree = DisclosureTree<Fp, Sha256HashFn>;
+
+    #[test]
+    fn private_column_can_be_disclosed_later() -> Result<(), Error> {
+        let mut rng = ark_std::test_rng();
+        let columns = (0..8)
+            .map(|_| (0..8).map(|_| Fp::rand(&mut rng)).collect::<Vec<_>>())
+            .map(|column| column.to_vec_in(GpuAllocator))
+            .collect();
+        let matrix = Matrix::new(columns);
+        let public_columns = [0, 1, 2, 3];
+        let tree = Tree::new(&matrix, &public_columns, &mut rng)?;
+        let root = tree.root();
+        let row_ids = [1, 5];
+
+        let public_proof = tree.open(&matrix, &row_ids, &[])?;
+        assert!(public_proof
+            .rows
+            .iter()
+            .all(|row| row[4..].iter().all(Option::is_none)));
+        Tree::verify(&root, &public_columns, &row_ids, public_proof)?;
+
+        let disclosed_proof = tree.open(&matrix, &row_ids, &[6])?;
+        assert_eq!(Some(matrix.0[6][5]), disclosed_proof.rows[1][6]);
+        Tree::verify(&root, &public_columns, &row_ids, disclosed_proof)
+    }
+}
@@ -1,6 +1,7 @@
 use crate::constraints::AlgebraicItem;
 use crate::constraints::PeriodicColumn;
 use crate::expression::Expr;
+use crate::expression::P;
 use crate::utils::FieldVariant;
 use crate::utils::GpuAllocator;
 use crate::Matrix;
@@ -28,12 +29,22 @@ use num_traits::Pow;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::sync::RwLock;
 
+/// Evaluates `expr` over the LDE in chunks of 512 points. With the `parallel`
+/// feature chunks are evaluated on the rayon thread pool (sized with
+/// `RAYON_NUM_THREADS` or a custom global pool). Each chunk writes to its own
+/// slice of the result so the output is identical to the serial path.
+/// [`AlgebraicItem::Fixed`] leaves are evaluated like the periodic column of
+/// `fixed_columns` at their index.
 #[allow(clippy::too_many_arguments)]
 pub fn eval<Fp: GpuFftField<FftField = Fp> + FftField, Fq: StarkExtensionOf<Fp>>(
     expr: &Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>,
     challenges: &[Fq],
     hints: &[Fq],
+    fixed_columns: &[PeriodicColumn<'_, FieldVariant<Fp, Fq>>],
     lde_step: usize,
     domain_offset: Fp,
     x_lde: &[Fp],
@@ -48,6 +59,7 @@ pub fn eval<Fp: GpuFftField<FftField = Fp> + FftField, Fq: StarkExtensionOf<Fp>>
             expr,
             challenges,
             hints,
+            fixed_columns,
             lde_step,
             domain_offset,
             x_lde,
@@ -59,6 +71,7 @@ pub fn eval<Fp: GpuFftField<FftField = Fp> + FftField, Fq: StarkExtensionOf<Fp>>
             expr,
             challenges,
             hints,
+            fixed_columns,
             lde_step,
             domain_offset,
             x_lde,
@@ -81,6 +94,7 @@ fn eval_impl<
     expr: &Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>,
     challenges: &[Fq],
     hints: &[Fq],
+    fixed_columns: &[PeriodicColumn<'_, FieldVariant<Fp, Fq>>],
     lde_step: usize,
     domain_offset: Fp,
     x_lde: &[Fp],
@@ -97,22 +111,33 @@ fn eval_impl<
     let num_extension_columns = extension_trace_lde_cols.map_or(0, <[_]>::len);
     let base_column_range = 0..num_base_columns;
     let extension_column_range = num_base_columns..num_base_columns + num_extension_columns;
-    let periodic_column_evals_map =
-        build_periodic_column_evals_map(expr, domain_offset, trace_len, lde_step, CHUNK_SIZE);
+    let periodic_column_evals_map = build_periodic_column_evals_map(
+        expr,
+        fixed_columns,
+        domain_offset,
+        trace_len,
+        lde_step,
+        CHUNK_SIZE,
+    );
+    let (expr, vanishing_inverses) = substitute_vanishing_inverses(expr, x_lde, CHUNK_SIZE);
     cfg_chunks_mut!(result, CHUNK_SIZE)
         .enumerate()
         .for_each(|(i, chunk)| {
             let chunk_offset = CHUNK_SIZE * i;
             let chunk_res: [Fq; CHUNK_SIZE] = expr
                 .graph_eval(&mut |leaf| match *leaf {
-                    X => EvalItem::Evals(Box::new(FieldVariant::Fp(extract_lde_chunk(
-                        x_lde,
-                        chunk_offset,
-                    )))),
-                    Constant(v) => EvalItem::Constant(v),
-                    Challenge(i) => EvalItem::Constant(FieldVariant::Fq(challenges[i])),
-                    Hint(i) => EvalItem::Constant(FieldVariant::Fq(hints[i])),
-                    Trace(col_idx, row_offset) => {
+                    EvalLeaf::VanishingInverse(i) => EvalItem::Evals(Box::new(FieldVariant::Fp(
+                        extract_lde_chunk(&vanishing_inverses[i], chunk_offset),
+                    ))),
+                    EvalLeaf::Item(X) => EvalItem::Evals(Box::new(FieldVariant::Fp(
+                        extract_lde_chunk(x_lde, chunk_offset),
+                    ))),
+                    EvalLeaf::Item(Constant(v)) => EvalItem::Constant(v),
+                    EvalLeaf::Item(Challenge(i)) => {
+                        EvalItem::Constant(FieldVariant::Fq(challenges[i]))
+                    }
+                    EvalLeaf::Item(Hint(i)) => EvalItem::Constant(FieldVariant::Fq(hints[i])),
+                    EvalLeaf::Item(Trace(col_idx, row_offset)) => {
                         let shift = step * row_offset;
                         let chunk_offset = isize::try_from(chunk_offset).unwrap();
                         #[allow(clippy::cast_possible_wrap)]
@@ -132,23 +157,130 @@ fn eval_impl<
                             panic!("invalid column {col_idx}")
                         }
                     }
-                    Periodic(col) => {
-                        let lde = periodic_column_evals_map.get(&col).unwrap();
-                        match lde {
-                            FieldVariant::Fp(lde) => EvalItem::Evals(Box::new(FieldVariant::Fp(
-                                extract_lde_chunk(lde, chunk_offset),
-                            ))),
-                            FieldVariant::Fq(lde) => EvalItem::Evals(Box::new(FieldVariant::Fq(
-                                extract_lde_chunk(lde, chunk_offset),
-                            ))),
-                        }
+                    EvalLeaf::Item(Periodic(col)) => {
+                        extract_periodic_chunk(&periodic_column_evals_map[&col], chunk_offset)
                     }
+                    EvalLeaf::Item(Fixed(i)) => extract_periodic_chunk(
+                        &periodic_column_evals_map[&fixed_columns[i]],
+                        chunk_offset,
+                    ),
                 })
                 .into_fq_array();
             chunk.copy_from_slice(&chunk_res);
         });
 }
 
+/// Leaf of an expression being evaluated over the LDE
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum EvalLeaf<T: 'static> {
+    Item(AlgebraicItem<T>),
+    /// Index of the precomputed inverses of a vanishing polynomial
+    VanishingInverse(usize),
+}
+
+/// Replaces divisions by vanishing polynomials `X^n - c` with multiplications
+/// by their precomputed inverses. Over the LDE coset `x^n` repeats with a
+/// period of `lde_len / gcd(n, lde_len)` so each denominator is inverted with a
+/// single batch inversion over one period rather than at every point.
+/// Inverses are repeated to at least `min_len` so chunks can be extracted.
+#[allow(clippy::type_complexity)]
+fn substitute_vanishing_inverses<Fp: FftField, Fq: Field>(
+    expr: &Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>,
+    x_lde: &[Fp],
+    min_len: usize,
+) -> (Expr<EvalLeaf<FieldVariant<Fp, Fq>>>, Vec<Vec<Fp>>) {
+    // `map_leaves` keeps shared nodes shared. Divisions are replaced in place
+    // so each shared node is still evaluated once
+    let mut expr = expr.map_leaves(&mut |&item| EvalLeaf::Item(item));
+    let mut denominators = Vec::new();
+    replace_vanishing_divisions(&mut expr, &mut BTreeSet::new(), &mut denominators);
+
+    let lde_len = x_lde.len();
+    let inverses = denominators
+        .into_iter()
+        .map(|(n, c)| {
+            let period = lde_len >> n.trailing_zeros().min(lde_len.trailing_zeros());
+            let mut inverses = x_lde[0..period]
+                .iter()
+                .map(|x| x.pow([n as u64]) - c)
+                .collect::<Vec<Fp>>();
+            batch_inversion(&mut inverses);
+            let mut i = 0;
+            while inverses.len() < min_len {
+                inverses.push(inverses[i]);
+                i += 1;
+            }
+            inverses
+        })
+        .collect();
+    (expr, inverses)
+}
+
+/// Replaces divisions by `X^n - c` in `node` and its descendants with
+/// multiplications by a [`EvalLeaf::VanishingInverse`] leaf. Each node is
+/// visited once regardless of how many parents share it.
+#[allow(clippy::type_complexity)]
+fn replace_vanishing_divisions<Fp: Field, Fq: Field>(
+    node: &mut Expr<EvalLeaf<FieldVariant<Fp, Fq>>>,
+    visited: &mut BTreeSet<*const RwLock<Expr<EvalLeaf<FieldVariant<Fp, Fq>>>>>,
+    denominators: &mut Vec<(usize, Fp)>,
+) {
+    let mut visit = |child: &P<Expr<EvalLeaf<FieldVariant<Fp, Fq>>>>| {
+        if visited.insert(Arc::as_ptr(child)) {
+            replace_vanishing_divisions(&mut child.write().unwrap(), visited, denominators);
+        }
+    };
+    match node {
+        Expr::Add(a, b) | Expr::Mul(a, b) | Expr::Div(a, b) => {
+            visit(a);
+            visit(b);
+        }
+        Expr::Neg(a) | Expr::Pow(a, _) => visit(a),
+        Expr::Leaf(_) => {}
+    }
+
+    let Expr::Div(numerator, denominator) = node else {
+        return;
+    };
+    let Some(vanishing_poly) = vanishing_polynomial(&denominator.read().unwrap()) else {
+        return;
+    };
+    let index = denominators
+        .iter()
+        .position(|&d| d == vanishing_poly)
+        .unwrap_or_else(|| {
+            denominators.push(vanishing_poly);
+            denominators.len() - 1
+        });
+    let numerator = Arc::clone(numerator);
+    let inverse = Expr::Leaf(EvalLeaf::VanishingInverse(index));
+    *node = Expr::Mul(numerator, Arc::new(RwLock::new(inverse)));
+}
+
+/// Returns `(n, c)` if the expression is the vanishing polynomial `X^n - c`
+fn vanishing_polynomial<Fp: Field, Fq: Field>(
+    expr: &Expr<EvalLeaf<FieldVariant<Fp, Fq>>>,
+) -> Option<(usize, Fp)> {
+    use AlgebraicItem::*;
+    let Expr::Add(lhs, rhs) = expr else {
+        return None;
+    };
+    let rhs = rhs.read().unwrap();
+    let Expr::Neg(constant) = &*rhs else {
+        return None;
+    };
+    let Expr::Leaf(EvalLeaf::Item(Constant(FieldVariant::Fp(c)))) = *constant.read().unwrap()
+    else {
+        return None;
+    };
+    let n = match &*lhs.read().unwrap() {
+        Expr::Leaf(EvalLeaf::Item(X)) => 1,
+        Expr::Pow(x, n) if matches!(*x.read().unwrap(), Expr::Leaf(EvalLeaf::Item(X))) => *n,
+        _ => return None,
+    };
+    Some((n, c))
+}
+
 /// Extracts a chunk of evaluations from a low-degree-extension
 #[inline]
 pub fn extract_lde_chunk<F: Field, const CHUNK_SIZE: usize>(
@@ -173,21 +305,29 @@ pub fn extract_lde_chunk<F: Field, const CHUNK_SIZE: usize>(
     }
 }
 
-/// Build a map from periodic column to evaluations
+/// Build a map from periodic column to evaluations. Fixed columns are read
+/// from `fixed_columns`.
 #[allow(clippy::type_complexity)]
 pub fn build_periodic_column_evals_map<
+    'a,
     Fp: GpuFftField<FftField = Fp> + FftField,
     Fq: StarkExtensionOf<Fp>,
 >(
     expr: &Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>,
+    fixed_columns: &[PeriodicColumn<'a, FieldVariant<Fp, Fq>>],
     domain_offset: Fp,
     trace_len: usize,
     blowup_factor: usize,
     min_domain_size: usize,
-) -> BTreeMap<PeriodicColumn<'static, FieldVariant<Fp, Fq>>, FieldVariant<Vec<Fp>, Vec<Fq>>> {
+) -> BTreeMap<PeriodicColumn<'a, FieldVariant<Fp, Fq>>, FieldVariant<Vec<Fp>, Vec<Fq>>> {
     let mut res = BTreeMap::new();
     expr.traverse(&mut |node| {
-        if let &Expr::Leaf(AlgebraicItem::Periodic(col)) = node {
+        let col = match node {
+            &Expr::Leaf(AlgebraicItem::Periodic(col)) => col,
+            &Expr::Leaf(AlgebraicItem::Fixed(i)) => fixed_columns[i],
+            _ => return,
+        };
+        if !res.contains_key(&col) {
             let interval_size = col.interval_size();
             let coeffs = col.coeffs();
             let is_fp = |&v| match v {
@@ -231,6 +371,21 @@ pub fn build_periodic_column_evals_map<
     res
 }
 
+/// Returns the chunk of a periodic column's evaluations starting at `offset`
+fn extract_periodic_chunk<Fp: Field, Fq: Field, const N: usize>(
+    lde: &FieldVariant<Vec<Fp>, Vec<Fq>>,
+    offset: usize,
+) -> EvalItem<'_, Fp, Fq, N> {
+    match lde {
+        FieldVariant::Fp(lde) => {
+            EvalItem::Evals(Box::new(FieldVariant::Fp(extract_lde_chunk(lde, offset))))
+        }
+        FieldVariant::Fq(lde) => {
+            EvalItem::Evals(Box::new(FieldVariant::Fq(extract_lde_chunk(lde, offset))))
+        }
+    }
+}
+
 /// Generates a preiodic low degree extension of a periodic column of values
 pub fn eval_periodic_column<F: GpuField + Field + DomainCoeff<F::FftField>>(
     domain_offset: F::FftField,
@@ -43,6 +43,15 @@ use ministark_gpu::GpuFrom;
 use ministark_gpu::GpuMul;
 use num_traits::Pow;
 
+/// Returns the memory the GPU recommends the process uses less the memory
+/// already allocated on the device
+pub fn available_gpu_bytes() -> usize {
+    let device = get_planner().command_queue.device();
+    let working_set_size = device.recommended_max_working_set_size();
+    let allocated = device.current_allocated_size();
+    usize::try_from(working_set_size.saturating_sub(allocated)).unwrap_or(usize::MAX)
+}
+
 pub fn eval<Fp: GpuFftField<FftField = Fp> + FftField, Fq: StarkExtensionOf<Fp>>(
     expr: &Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>,
     challenges: &[Fq],
@@ -112,6 +121,9 @@ pub fn eval<Fp: GpuFftField<FftField = Fp> + FftField, Fq: StarkExtensionOf<Fp>>
         &Periodic(_col) => {
             todo!()
         }
+        Fixed(_) => {
+            todo!()
+        }
         X => {
             // generate an LDE for the only X (we called reuse_shared_nodes)
             let mut x_lde = Option::take(&mut x_lde).unwrap();
@@ -31,6 +31,7 @@ pub struct FriOptions {
     folding_factor: usize,
     max_remainder_coeffs: usize,
     blowup_factor: usize,
+    max_num_layers: usize,
 }
 
 impl FriOptions {
@@ -43,12 +44,26 @@ impl FriOptions {
             folding_factor,
             max_remainder_coeffs,
             blowup_factor,
+            max_num_layers: usize::MAX,
         }
     }
 
+    /// Stops folding after `max_num_layers` layers and sends whatever remains
+    /// as the remainder. Fewer layers means fewer commitments but a larger
+    /// remainder polynomial for the verifier to check.
+    pub const fn with_max_num_layers(mut self, max_num_layers: usize) -> Self {
+        self.max_num_layers = max_num_layers;
+        self
+    }
+
+    const fn is_folded(&self, domain_size: usize, num_layers: usize) -> bool {
+        domain_size <= self.max_remainder_coeffs * self.blowup_factor
+            || num_layers >= self.max_num_layers
+    }
+
     pub const fn num_layers(&self, mut domain_size: usize) -> usize {
         let mut num_layers = 0;
-        while domain_size > self.max_remainder_coeffs * self.blowup_factor {
+        while !self.is_folded(domain_size, num_layers) {
             domain_size /= self.folding_factor;
             num_layers += 1;
         }
@@ -56,12 +71,36 @@ impl FriOptions {
     }
 
     pub const fn remainder_size(&self, mut domain_size: usize) -> usize {
-        while domain_size > self.max_remainder_coeffs * self.blowup_factor {
+        let mut num_layers = 0;
+        while !self.is_folded(domain_size, num_layers) {
             domain_size /= self.folding_factor;
+            num_layers += 1;
         }
         domain_size
     }
 
+    /// Returns the size of the domain committed to in each layer and the size
+    /// of the remainder domain when folding a domain of the given size
+    pub fn layout(&self, mut domain_size: usize) -> FriLayout {
+        let mut layer_sizes = Vec::new();
+        while !self.is_folded(domain_size, layer_sizes.len()) {
+            layer_sizes.push(domain_size);
+            domain_size /= self.folding_factor;
+        }
+        FriLayout {
+            num_layers: layer_sizes.len(),
+            layer_sizes,
+            remainder_size: domain_size,
+        }
+    }
+
+    /// Returns true if a domain of the given size can be folded into a valid
+    /// remainder. Folding a small domain by a large factor can overshoot and
+    /// leave a remainder domain smaller than the blowup factor.
+    pub const fn is_valid_domain_size(&self, domain_size: usize) -> bool {
+        self.remainder_size(domain_size) >= self.blowup_factor
+    }
+
     pub const fn domain_offset<F: GpuField>(&self) -> F::FftField
     where
         F::FftField: FftField,
@@ -72,6 +111,17 @@ impl FriOptions {
     }
 }
 
+/// Structure of the FRI layers for a given domain size (see
+/// [`FriOptions::layout`])
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct FriLayout {
+    pub num_layers: usize,
+    /// Size of the evaluation domain of each layer. Each layer is
+    /// `folding_factor` times smaller than the one before it.
+    pub layer_sizes: Vec<usize>,
+    pub remainder_size: usize,
+}
+
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
 pub struct FriProof<F: Field, D: Digest, M: MatrixMerkleTree<F>> {
     pub layers: Vec<LayerProof<F, D, M>>,
@@ -88,6 +138,23 @@ where
             remainder_coeffs,
         }
     }
+
+    /// Re-derives the folding challenge of each layer. The prover draws a
+    /// layer's challenge right after committing to the layer so the layer's
+    /// commitment is absorbed before each draw. `public_coin` must be in the
+    /// state it was in before the first layer was committed to.
+    pub fn folding_challenges(
+        &self,
+        public_coin: &mut impl PublicCoin<Field = F, Digest = D>,
+    ) -> Vec<F> {
+        self.layers
+            .iter()
+            .map(|layer| {
+                public_coin.reseed_with_digest(&layer.commitment);
+                public_coin.draw()
+            })
+            .collect()
+    }
 }
 
 struct FriLayer<F: GpuField, M: MerkleTree> {
@@ -145,6 +212,26 @@ where
         }
     }
 
+    /// Returns the evaluations of each layer in natural order for debugging a
+    /// DEEP composition that isn't low degree. Interpolating each layer should
+    /// show its degree dropping by the folding factor. Layers are kept until
+    /// [`Self::into_proof`] to open queries anyway so they cost no extra
+    /// memory. Layers are evaluated over cosets but interpolating over the
+    /// subgroup gives `p(c * x)` which has the same degree as `p(x)`.
+    pub fn debug_layers(&self) -> Vec<Vec<F>> {
+        self.layers
+            .iter()
+            .map(|layer| {
+                let cosets = &layer.evaluations;
+                let mut evaluations = (0..cosets.num_rows())
+                    .flat_map(|i| cosets.get_row(i).unwrap())
+                    .collect::<Vec<F>>();
+                bit_reverse(&mut evaluations);
+                evaluations
+            })
+            .collect()
+    }
+
     pub fn into_proof(self, positions: &[usize]) -> FriProof<F, D, M> {
         let folding_factor = self.options.folding_factor;
         // let (last_layer, initial_layers) = self.layers.split_last().unwrap();
@@ -179,10 +266,25 @@ where
     pub fn build_layers(
         &mut self,
         channel: &mut impl ProverChannel<Field = F, Digest = D>,
-        mut evaluations: GpuVec<F>,
+        evaluations: GpuVec<F>,
     ) {
+        self.build_layers_until(channel, evaluations, || false);
+    }
+
+    /// Same as [`Self::build_layers`] but `is_cancelled` is checked before
+    /// each layer. Returns false without building the remaining layers once
+    /// it returns true.
+    pub fn build_layers_until(
+        &mut self,
+        channel: &mut impl ProverChannel<Field = F, Digest = D>,
+        mut evaluations: GpuVec<F>,
+        is_cancelled: impl Fn() -> bool,
+    ) -> bool {
         assert!(self.layers.is_empty());
         for _ in 0..self.options.num_layers(evaluations.len()) {
+            if is_cancelled() {
+                return false;
+            }
             evaluations = match self.options.folding_factor {
                 2 => self.build_layer::<2>(channel, evaluations),
                 4 => self.build_layer::<4>(channel, evaluations),
@@ -192,6 +294,7 @@ where
             }
         }
         self.set_remainder(channel, evaluations);
+        true
     }
 
     /// Builds a single layer of the FRI protocol
@@ -237,7 +340,6 @@ where
     ) {
         let domain_size = evaluations.len();
         assert!(domain_size.is_power_of_two());
-        assert!(domain_size <= self.options.max_remainder_coeffs * self.options.blowup_factor);
         let domain = Radix2EvaluationDomain::new(domain_size).unwrap();
         bit_reverse(&mut evaluations);
         let coeffs = domain.ifft(&evaluations);
@@ -253,12 +355,12 @@ where
 pub enum VerificationError {
     #[snafu(display("queries do not resolve to their commitment in layer {layer}"))]
     LayerCommitmentInvalid { layer: usize },
-    #[snafu(display("degree respecting projection is invalid for layer {layer}"))]
-    InvalidDegreeRespectingProjection { layer: usize },
+    #[snafu(display("fold mismatch in layer {layer} for query {query}"))]
+    InvalidDegreeRespectingProjection { layer: usize, query: usize },
     #[snafu(display("the number of query positions does not match the number of evaluations"))]
     NumPositionEvaluationMismatch,
-    #[snafu(display("remainder is invalid"))]
-    RemainderCommitmentInvalid,
+    #[snafu(display("remainder does not match the evaluation for query {query}"))]
+    RemainderCommitmentInvalid { query: usize },
     #[snafu(display("remainder is not a degree {degree} polynomial"))]
     RemainderDegreeMismatch { degree: usize },
     #[snafu(display("{size} can't be divided by {folding_factor} (layer {layer})"))]
@@ -295,21 +397,41 @@ where
         options: FriOptions,
         proof: FriProof<F, D, M>,
         max_poly_degree: usize,
+    ) -> Result<Self, VerificationError> {
+        let layer_alphas = proof.folding_challenges(public_coin);
+        public_coin.reseed_with_field_element_vector(&proof.remainder_coeffs);
+
+        // TODO: add back in
+        // let remainder_root =
+        // Output::<D>::from_slice(&proof.remainder_commitment).clone();
+        // let remainder_root =
+        // Output::<D>::from_slice(&proof.remainder_commitment).clone();
+        // public_coin.reseed_with_hash(&remainder_root);
+        // let remainder_alpha = public_coin.draw();
+        // layer_alphas.push(remainder_alpha);
+        // layer_commitments.push(remainder_root);
+
+        Self::with_folding_challenges(options, proof, max_poly_degree, layer_alphas)
+    }
+
+    /// Same as [`Self::new`] but with folding challenges that were already
+    /// re-derived with [`FriProof::folding_challenges`]
+    pub fn with_folding_challenges(
+        options: FriOptions,
+        proof: FriProof<F, D, M>,
+        max_poly_degree: usize,
+        layer_alphas: Vec<F>,
     ) -> Result<Self, VerificationError> {
         let folding_factor = options.folding_factor;
         let domain_offset = options.domain_offset::<F>();
         let domain_size = max_poly_degree.next_power_of_two() * options.blowup_factor;
         let domain = Radix2EvaluationDomain::new_coset(domain_size, domain_offset).unwrap();
 
-        let mut layer_alphas = Vec::new();
         let mut layer_commitments = Vec::new();
         let mut layer_codeword_len = domain_size;
         for (i, layer) in proof.layers.iter().enumerate() {
             // TODO: batch merkle tree proofs
             // get the merkle root from the first merkle path
-            public_coin.reseed_with_digest(&layer.commitment);
-            let alpha = public_coin.draw();
-            layer_alphas.push(alpha);
             layer_commitments.push(layer.commitment.clone());
 
             if i != proof.layers.len() - 1 && layer_codeword_len % folding_factor != 0 {
@@ -323,18 +445,6 @@ where
             layer_codeword_len /= folding_factor;
         }
 
-        public_coin.reseed_with_field_element_vector(&proof.remainder_coeffs);
-
-        // TODO: add back in
-        // let remainder_root =
-        // Output::<D>::from_slice(&proof.remainder_commitment).clone();
-        // let remainder_root =
-        // Output::<D>::from_slice(&proof.remainder_commitment).clone();
-        // public_coin.reseed_with_hash(&remainder_root);
-        // let remainder_alpha = public_coin.draw();
-        // layer_alphas.push(remainder_alpha);
-        // layer_commitments.push(remainder_root);
-
         Ok(Self {
             options,
             layer_commitments,
@@ -344,6 +454,31 @@ where
         })
     }
 
+    /// Returns the values the first FRI layer opens at `positions`. Returns
+    /// `None` if there are no layers (only a remainder) or the layer doesn't
+    /// open one row per folded position. These values aren't checked against
+    /// the layer commitment here. That happens in [`Self::verify`].
+    pub fn first_layer_query_values(&self, positions: &[usize]) -> Option<Vec<F>> {
+        match self.options.folding_factor {
+            2 => self.first_layer_query_values_generic::<2>(positions),
+            4 => self.first_layer_query_values_generic::<4>(positions),
+            8 => self.first_layer_query_values_generic::<8>(positions),
+            16 => self.first_layer_query_values_generic::<16>(positions),
+            folding_factor => unreachable!("folding factor {folding_factor} not supported"),
+        }
+    }
+
+    fn first_layer_query_values_generic<const N: usize>(
+        &self,
+        positions: &[usize],
+    ) -> Option<Vec<F>> {
+        let layer = self.proof.layers.first()?;
+        let folded_positions = fold_positions(positions, N);
+        let (rows, _) = layer.flattenend_rows.as_chunks::<N>();
+        (rows.len() == folded_positions.len())
+            .then(|| get_query_values(rows, positions, &folded_positions))
+    }
+
     pub fn verify_generic<const N: usize>(
         self,
         positions: &[usize],
@@ -361,13 +496,16 @@ where
         // verify all layers except remainder
         for i in 0..self.options.num_layers(domain_size) {
             let folded_positions = fold_positions(&positions, N);
+            // alphas and commitments are drawn for each layer in the proof
+            let layer = layers
+                .next()
+                .ok_or(VerificationError::LayerCommitmentInvalid { layer: i })?;
             let layer_alpha = layer_alphas.next().unwrap();
             let layer_commitment = layer_commitments.next().unwrap();
-
-            // TODO: change assert to error. Check remainder
-            let layer = layers.next().unwrap();
             let (rows, _) = &layer.flattenend_rows.as_chunks::<N>();
-            assert_eq!(rows.len(), folded_positions.len());
+            if rows.len() != folded_positions.len() {
+                return Err(VerificationError::LayerCommitmentInvalid { layer: i });
+            }
 
             // verify the layer values against the layer's commitment
             M::verify_rows(
@@ -379,15 +517,12 @@ where
             .map_err(|_| VerificationError::LayerCommitmentInvalid { layer: i })?;
 
             let query_values = get_query_values(rows, &positions, &folded_positions);
-            // println!("evaluatinos: {:?}", evaluations);
-            // println!("query vals: {:?}", query_values);
-            assert_eq!(evaluations.len(), query_values.len());
-            for (i, (eval, query_val)) in zip(&evaluations, &query_values).enumerate() {
-                assert_eq!(eval, query_val, "mismatch at {i}");
-            }
-
-            if evaluations != query_values {
-                return Err(VerificationError::InvalidDegreeRespectingProjection { layer: i });
+            let mismatch = zip(&evaluations, &query_values).position(|(a, b)| a != b);
+            if let Some(query) = mismatch {
+                return Err(VerificationError::InvalidDegreeRespectingProjection {
+                    layer: i,
+                    query,
+                });
             }
 
             let polys = rows
@@ -470,7 +605,7 @@ where
                 result + coeff
             });
         if expected_evaluations[i] != y {
-            return Err(VerificationError::RemainderCommitmentInvalid);
+            return Err(VerificationError::RemainderCommitmentInvalid { query: i });
         }
     }
     Ok(())
@@ -662,3 +797,61 @@ where
     }
     LayerProof::new(rows, merkle_proof, layer.merkle_tree.root())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::FriOptions;
+    use super::FriProver;
+    use super::ProverChannel;
+    use crate::hash::Sha256HashFn;
+    use crate::merkle::MatrixMerkleTreeImpl;
+    use crate::utils::GpuAllocator;
+    use crate::utils::SerdeOutput;
+    use ark_ff::Zero;
+    use ark_poly::EvaluationDomain;
+    use ark_poly::Radix2EvaluationDomain;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+    use ministark_gpu::utils::bit_reverse;
+    use sha2::Sha256;
+
+    /// Channel that draws consecutive integers as folding challenges
+    struct CountingChannel(u64);
+
+    impl ProverChannel for CountingChannel {
+        type Digest = SerdeOutput<Sha256>;
+        type Field = Fp;
+
+        fn commit_fri_layer(&mut self, _layer_root: Self::Digest) {}
+
+        fn commit_remainder(&mut self, _remainder_coeffs: &[Fp]) {}
+
+        fn draw_fri_alpha(&mut self) -> Fp {
+            self.0 += 1;
+            Fp::from(self.0)
+        }
+    }
+
+    #[test]
+    fn debug_layers_halve_in_degree() {
+        let domain = Radix2EvaluationDomain::<Fp>::new(64).unwrap();
+        let coeffs = (1..=16u64).map(Fp::from).collect::<Vec<_>>();
+        let mut evaluations = domain.fft(&coeffs);
+        bit_reverse(&mut evaluations);
+        let options = FriOptions::new(4, 2, 4);
+        let mut prover =
+            FriProver::<Fp, SerdeOutput<Sha256>, MatrixMerkleTreeImpl<Sha256HashFn>>::new(options);
+
+        prover.build_layers(&mut CountingChannel(0), evaluations.to_vec_in(GpuAllocator));
+
+        let degrees = prover
+            .debug_layers()
+            .into_iter()
+            .map(|evaluations| {
+                let domain = Radix2EvaluationDomain::<Fp>::new(evaluations.len()).unwrap();
+                let coeffs = domain.ifft(&evaluations);
+                coeffs.iter().rposition(|c| !c.is_zero()).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(vec![15, 7], degrees);
+    }
+}
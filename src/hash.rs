@@ -1,10 +1,15 @@
 use crate::utils::SerdeOutput;
+use ark_ff::BigInteger;
 use ark_ff::Field;
+use ark_ff::PrimeField;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
+use ark_serialize::Valid;
 use core::fmt::Debug;
+use core::marker::PhantomData;
 use digest::Digest as _;
 use sha2::Sha256;
+use sha3::Keccak256;
 
 /// Trait inspired by Winterfell: <https://github.com/facebook/winterfell/blob/main/crypto/src/hash/mod.rs#L33>
 pub trait HashFn: Send + Sync + 'static {
@@ -98,3 +103,388 @@ impl<F: Field> ElementHashFn<F> for Sha256HashFn {
         Self::hash_chunks([&*byte_buffer])
     }
 }
+
+/// Keccak-256 hash function. Keccak is cheap to evaluate in the EVM which makes
+/// it the hash function of choice for proofs that are verified on Ethereum.
+pub struct Keccak256HashFn;
+
+impl HashFn for Keccak256HashFn {
+    type Digest = SerdeOutput<Keccak256>;
+
+    const COLLISION_RESISTANCE: u32 = 128;
+
+    fn hash(bytes: impl IntoIterator<Item = u8>) -> SerdeOutput<Keccak256> {
+        let bytes = bytes.into_iter().collect::<Vec<u8>>();
+        SerdeOutput::new(Keccak256::digest(bytes))
+    }
+
+    fn hash_chunks<'a>(slices: impl IntoIterator<Item = &'a [u8]>) -> SerdeOutput<Keccak256> {
+        let mut hasher = Keccak256::new();
+        slices.into_iter().for_each(|s| hasher.update(s));
+        SerdeOutput::new(hasher.finalize())
+    }
+
+    fn merge(v0: &SerdeOutput<Keccak256>, v1: &SerdeOutput<Keccak256>) -> SerdeOutput<Keccak256> {
+        let mut hasher = Keccak256::new();
+        hasher.update(**v0);
+        hasher.update(**v1);
+        SerdeOutput::new(hasher.finalize())
+    }
+
+    fn merge_with_int(seed: &SerdeOutput<Keccak256>, value: u64) -> SerdeOutput<Keccak256> {
+        let mut hasher = Keccak256::new();
+        hasher.update(**seed);
+        hasher.update(value.to_be_bytes());
+        SerdeOutput::new(hasher.finalize())
+    }
+}
+
+impl<F: Field> ElementHashFn<F> for Keccak256HashFn {
+    /// Elements are encoded as big-endian 32-byte words to match EVM
+    /// conventions. Extension field elements are encoded as the sequence of
+    /// their base field components.
+    fn hash_elements(elements: impl IntoIterator<Item = F>) -> Self::Digest {
+        let mut hasher = Keccak256::new();
+        for element in elements {
+            for component in element.to_base_prime_field_elements() {
+                hasher.update(to_evm_word(component));
+            }
+        }
+        SerdeOutput::new(hasher.finalize())
+    }
+}
+
+/// Encodes a prime field element as a big-endian 32-byte word
+///
+/// # Panics
+/// Panics if the element doesn't fit into 32 bytes
+pub fn to_evm_word<F: PrimeField>(element: F) -> [u8; 32] {
+    let bytes = element.into_bigint().to_bytes_be();
+    let (padding, bytes) = bytes.split_at(bytes.len().saturating_sub(32));
+    assert!(padding.iter().all(|&b| b == 0), "element exceeds 32 bytes");
+    let mut word = [0; 32];
+    word[32 - bytes.len()..].copy_from_slice(bytes);
+    word
+}
+
+/// Byte encoding of prime field elements for [`EncodedHashFn`]
+pub trait ElementEncoding: Send + Sync + 'static {
+    /// Appends the encoding of `element` to `bytes`
+    fn encode<F: PrimeField>(element: F, bytes: &mut Vec<u8>);
+}
+
+/// Little-endian limbs of the canonical integer. These are the same bytes as
+/// the `CanonicalSerialize` encoding of a prime field element.
+pub struct LittleEndian;
+
+impl ElementEncoding for LittleEndian {
+    fn encode<F: PrimeField>(element: F, bytes: &mut Vec<u8>) {
+        bytes.extend(element.into_bigint().to_bytes_le());
+    }
+}
+
+/// Big-endian canonical integer padded to the width of the field's limbs e.g.
+/// 8 bytes for a 64-bit field
+pub struct BigEndian;
+
+impl ElementEncoding for BigEndian {
+    fn encode<F: PrimeField>(element: F, bytes: &mut Vec<u8>) {
+        bytes.extend(element.into_bigint().to_bytes_be());
+    }
+}
+
+/// Big-endian 32-byte words (see [`to_evm_word`])
+pub struct EvmWord;
+
+impl ElementEncoding for EvmWord {
+    fn encode<F: PrimeField>(element: F, bytes: &mut Vec<u8>) {
+        bytes.extend(to_evm_word(element));
+    }
+}
+
+/// Hash function `H` that hashes field elements using the encoding `E`
+///
+/// The encoding of leaves is what an external verifier has to reproduce to
+/// recompute a Merkle root. A `MatrixMerkleTreeImpl` over
+/// `EncodedHashFn<Sha256HashFn, BigEndian>` commits to big-endian rows.
+/// Extension field elements are encoded as the sequence of their base field
+/// components. Every encoding is fixed width since concatenating variable
+/// width encodings would let different rows hash to the same leaf.
+pub struct EncodedHashFn<H: HashFn, E: ElementEncoding>(PhantomData<(H, E)>);
+
+impl<H: HashFn, E: ElementEncoding> HashFn for EncodedHashFn<H, E> {
+    type Digest = H::Digest;
+
+    const COLLISION_RESISTANCE: u32 = H::COLLISION_RESISTANCE;
+
+    fn hash(bytes: impl IntoIterator<Item = u8>) -> Self::Digest {
+        H::hash(bytes)
+    }
+
+    fn hash_chunks<'a>(chunks: impl IntoIterator<Item = &'a [u8]>) -> Self::Digest {
+        H::hash_chunks(chunks)
+    }
+
+    fn merge(v0: &Self::Digest, v1: &Self::Digest) -> Self::Digest {
+        H::merge(v0, v1)
+    }
+
+    fn merge_with_int(seed: &Self::Digest, value: u64) -> Self::Digest {
+        H::merge_with_int(seed, value)
+    }
+}
+
+impl<F: Field, H: HashFn, E: ElementEncoding> ElementHashFn<F> for EncodedHashFn<H, E> {
+    fn hash_elements(elements: impl IntoIterator<Item = F>) -> Self::Digest {
+        let mut byte_buffer = Vec::new();
+        for element in elements {
+            for component in element.to_base_prime_field_elements() {
+                E::encode(component, &mut byte_buffer);
+            }
+        }
+        H::hash_chunks([&*byte_buffer])
+    }
+}
+
+/// First `NUM_BYTES` bytes of a digest. Serializes to exactly `NUM_BYTES`
+/// bytes which is what makes Merkle paths of truncated nodes smaller (see
+/// [`crate::merkle::TruncatedMatrixMerkleTree`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TruncatedDigest<const NUM_BYTES: usize>(pub [u8; NUM_BYTES]);
+
+impl<const NUM_BYTES: usize> TruncatedDigest<NUM_BYTES> {
+    /// Collision resistance in bits of digests truncated to `NUM_BYTES` bytes
+    /// i.e. the birthday bound
+    #[allow(clippy::cast_possible_truncation)]
+    pub const COLLISION_RESISTANCE: u32 = NUM_BYTES as u32 * 4;
+
+    /// # Panics
+    /// Panics if `NUM_BYTES` is larger than 32
+    pub fn new(digest: &impl Digest) -> Self {
+        assert!(NUM_BYTES <= 32, "digests have at most 32 bytes");
+        let mut bytes = [0; NUM_BYTES];
+        bytes.copy_from_slice(&digest.as_bytes()[..NUM_BYTES]);
+        Self(bytes)
+    }
+}
+
+impl<const NUM_BYTES: usize> Default for TruncatedDigest<NUM_BYTES> {
+    fn default() -> Self {
+        Self([0; NUM_BYTES])
+    }
+}
+
+impl<const NUM_BYTES: usize> Digest for TruncatedDigest<NUM_BYTES> {
+    fn as_bytes(&self) -> [u8; 32] {
+        let mut res = [0; 32];
+        res[..NUM_BYTES].copy_from_slice(&self.0);
+        res
+    }
+}
+
+impl<const NUM_BYTES: usize> CanonicalSerialize for TruncatedDigest<NUM_BYTES> {
+    fn serialize_with_mode<W: ark_serialize::Write>(
+        &self,
+        mut writer: W,
+        _compress: ark_serialize::Compress,
+    ) -> Result<(), ark_serialize::SerializationError> {
+        Ok(writer.write_all(&self.0)?)
+    }
+
+    fn serialized_size(&self, _compress: ark_serialize::Compress) -> usize {
+        NUM_BYTES
+    }
+}
+
+impl<const NUM_BYTES: usize> Valid for TruncatedDigest<NUM_BYTES> {
+    fn check(&self) -> Result<(), ark_serialize::SerializationError> {
+        Ok(())
+    }
+}
+
+impl<const NUM_BYTES: usize> CanonicalDeserialize for TruncatedDigest<NUM_BYTES> {
+    fn deserialize_with_mode<R: ark_serialize::Read>(
+        mut reader: R,
+        _compress: ark_serialize::Compress,
+        _validate: ark_serialize::Validate,
+    ) -> Result<Self, ark_serialize::SerializationError> {
+        let mut bytes = [0; NUM_BYTES];
+        reader.read_exact(&mut bytes)?;
+        Ok(Self(bytes))
+    }
+}
+
+/// Poseidon permutation <https://eprint.iacr.org/2019/458>
+///
+/// Round constants are derived from SHA-256 and the MDS matrix is a Cauchy
+/// matrix so instances aren't compatible with other Poseidon implementations.
+/// The number of rounds must be chosen for the field and `alpha` to meet the
+/// desired security level.
+#[derive(Clone, Debug)]
+pub struct Poseidon<F: PrimeField> {
+    full_rounds: usize,
+    partial_rounds: usize,
+    alpha: u64,
+    mds: Vec<Vec<F>>,
+    round_constants: Vec<Vec<F>>,
+}
+
+impl<F: PrimeField> Poseidon<F> {
+    /// Creates a permutation over a state of `width` elements. `alpha` should
+    /// be the smallest integer coprime to `p - 1`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` isn't coprime to `p - 1`. The S-box `x^alpha` isn't a
+    /// permutation otherwise.
+    pub fn new(width: usize, full_rounds: usize, partial_rounds: usize, alpha: u64) -> Self {
+        assert!(full_rounds % 2 == 0, "full rounds must be split evenly");
+        assert!(
+            alpha > 1 && gcd(alpha, modulus_minus_one_rem::<F>(alpha)) == 1,
+            "alpha {alpha} isn't coprime to p - 1"
+        );
+        let num_rounds = full_rounds + partial_rounds;
+        let round_constants = (0..num_rounds)
+            .map(|round| {
+                (0..width)
+                    .map(|i| {
+                        let mut hasher = Sha256::new();
+                        hasher.update(b"ministark-poseidon");
+                        hasher.update((width as u64).to_be_bytes());
+                        hasher.update((round as u64).to_be_bytes());
+                        hasher.update((i as u64).to_be_bytes());
+                        F::from_le_bytes_mod_order(&hasher.finalize())
+                    })
+                    .collect()
+            })
+            .collect();
+        // Cauchy matrix `1/(x_i + y_j)` with `x_i = i` and `y_j = width + j`
+        let mds = (0..width)
+            .map(|i| {
+                (0..width)
+                    .map(|j| F::from((i + width + j) as u64).inverse().unwrap())
+                    .collect()
+            })
+            .collect();
+        Self {
+            full_rounds,
+            partial_rounds,
+            alpha,
+            mds,
+            round_constants,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.mds.len()
+    }
+
+    pub fn permute(&self, state: &mut [F]) {
+        assert_eq!(state.len(), self.width());
+        let half_full_rounds = self.full_rounds / 2;
+        let partial_rounds = half_full_rounds..half_full_rounds + self.partial_rounds;
+        for (round, constants) in self.round_constants.iter().enumerate() {
+            for (v, c) in state.iter_mut().zip(constants) {
+                *v += c;
+            }
+            if partial_rounds.contains(&round) {
+                state[0] = state[0].pow([self.alpha]);
+            } else {
+                for v in state.iter_mut() {
+                    *v = v.pow([self.alpha]);
+                }
+            }
+            let mixed = self
+                .mds
+                .iter()
+                .map(|row| row.iter().zip(&*state).map(|(&m, &v)| m * v).sum())
+                .collect::<Vec<F>>();
+            state.copy_from_slice(&mixed);
+        }
+    }
+}
+
+/// Returns `(p - 1) mod n` for the modulus `p` of `F`
+fn modulus_minus_one_rem<F: PrimeField>(n: u64) -> u64 {
+    let n = u128::from(n);
+    let p_rem = F::MODULUS
+        .as_ref()
+        .iter()
+        .rev()
+        .fold(0, |rem, &limb| ((rem << 64) + u128::from(limb)) % n);
+    // `p_rem` is less than `n` which fits in a u64
+    u64::try_from((p_rem + n - 1) % n).unwrap()
+}
+
+const fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BigEndian;
+    use super::ElementHashFn;
+    use super::EncodedHashFn;
+    use super::HashFn;
+    use super::Keccak256HashFn;
+    use super::LittleEndian;
+    use super::Sha256HashFn;
+    use ark_ff::One;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+
+    // Expected digests are taken from Solidity's `keccak256`
+
+    #[test]
+    fn keccak_empty_input_matches_evm() {
+        let digest = Keccak256HashFn::hash([]);
+
+        assert_eq!(
+            digest.as_slice(),
+            hex("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+        );
+    }
+
+    #[test]
+    fn keccak_element_encoding_matches_evm() {
+        // keccak256(abi.encode(uint256(1)))
+        let digest = <Keccak256HashFn as ElementHashFn<Fp>>::hash_elements([Fp::one()]);
+
+        assert_eq!(
+            digest.as_slice(),
+            hex("b10e2d527612073b26eecdfd717e6a320cf44b4afac2b0732d9fcbe2b7fa0cf6")
+        );
+    }
+
+    #[test]
+    fn big_endian_leaves_hash_big_endian_bytes() {
+        type BigEndianSha256 = EncodedHashFn<Sha256HashFn, BigEndian>;
+        let element = Fp::from(0x0102_0304_0506_0708u64);
+
+        let digest = <BigEndianSha256 as ElementHashFn<Fp>>::hash_elements([element]);
+
+        assert_eq!(digest, Sha256HashFn::hash([1, 2, 3, 4, 5, 6, 7, 8]));
+    }
+
+    #[test]
+    fn little_endian_leaves_match_canonical_serialization() {
+        type LittleEndianSha256 = EncodedHashFn<Sha256HashFn, LittleEndian>;
+        let element = Fp::from(0x0102_0304_0506_0708u64);
+
+        let digest = <LittleEndianSha256 as ElementHashFn<Fp>>::hash_elements([element]);
+
+        assert_eq!(
+            digest,
+            <Sha256HashFn as ElementHashFn<Fp>>::hash_elements([element])
+        );
+    }
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+}
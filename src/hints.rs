@@ -1,4 +1,5 @@
 use crate::constraints::Hint;
+use alloc::collections::BTreeSet;
 use alloc::vec::Vec;
 use ark_ff::Field;
 use ark_serialize::CanonicalDeserialize;
@@ -6,11 +7,25 @@ use ark_serialize::CanonicalSerialize;
 use core::ops::Deref;
 use core::ops::Index;
 
+/// Values derived from the public inputs and verifier challenges. Public hints
+/// are recomputed by the verifier and can be referenced by constraints. Private
+/// hints are only for use by the prover and can't appear in constraints.
 #[derive(Default, Debug, Clone, CanonicalDeserialize, CanonicalSerialize)]
-pub struct Hints<F: Field>(Vec<F>);
+pub struct Hints<F: Field> {
+    values: Vec<F>,
+    private: BTreeSet<usize>,
+}
 
 impl<F: Field> Hints<F> {
-    pub fn new(mut hints: Vec<(usize, F)>) -> Self {
+    /// Creates hints that are all public
+    pub fn new(hints: Vec<(usize, F)>) -> Self {
+        Self::new_with_private(hints, Vec::new())
+    }
+
+    /// Creates public and private hints. Indices are shared between the two.
+    pub fn new_with_private(public: Vec<(usize, F)>, private: Vec<(usize, F)>) -> Self {
+        let private_indices = private.iter().map(|(i, _)| *i).collect();
+        let mut hints = [public, private].concat();
         hints.sort();
         for [(a, _), (b, _)] in hints.array_windows() {
             assert!(a != b, "multiple hints exist at index {a}");
@@ -18,7 +33,14 @@ impl<F: Field> Hints<F> {
         for (expected, (actual, _)) in hints.iter().enumerate() {
             assert!(expected == *actual, "missing hint at index {expected}");
         }
-        Self(hints.into_iter().map(|(_, value)| value).collect())
+        Self {
+            values: hints.into_iter().map(|(_, value)| value).collect(),
+            private: private_indices,
+        }
+    }
+
+    pub fn is_private(&self, index: usize) -> bool {
+        self.private.contains(&index)
     }
 }
 
@@ -26,7 +48,7 @@ impl<F: Field> Deref for Hints<F> {
     type Target = Vec<F>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.values
     }
 }
 
@@ -34,6 +56,6 @@ impl<F: Field, H: Hint> Index<H> for Hints<F> {
     type Output = F;
 
     fn index(&self, hint: H) -> &Self::Output {
-        &self.0[hint.index()]
+        &self.values[hint.index()]
     }
 }
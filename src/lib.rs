@@ -32,23 +32,29 @@
 // TODO: make some of these modules private
 #[macro_use]
 pub mod macros;
+pub mod aggregate;
 pub mod air;
 pub mod challenges;
 pub mod channel;
 pub mod composer;
 pub mod constraints;
 pub mod debug;
+pub mod disclosure;
 pub mod eval_cpu;
 pub mod eval_gpu;
 pub mod expression;
 pub mod fri;
 pub mod hash;
 pub mod hints;
+pub mod lookup;
 pub mod matrix;
 pub mod merkle;
 pub mod proof;
 pub mod prover;
 pub mod random;
+pub mod range_check;
+pub mod schema;
+pub mod segment;
 pub mod stark;
 pub mod trace;
 pub mod utils;
@@ -63,6 +69,7 @@ use ark_ff::Field;
 use ark_poly::domain::DomainCoeff;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
+use ark_serialize::Valid;
 use core::ops::Add;
 use core::ops::AddAssign;
 use core::ops::Mul;
@@ -71,26 +78,75 @@ use core::ops::Sub;
 use core::ops::SubAssign;
 use fri::FriOptions;
 pub use matrix::Matrix;
+pub use ministark_gpu;
 use ministark_gpu::GpuAdd;
 use ministark_gpu::GpuFftField;
 use ministark_gpu::GpuField;
 use ministark_gpu::GpuFrom;
 use ministark_gpu::GpuMul;
 pub use proof::Proof;
+pub use prover::prove;
 pub use trace::Trace;
-pub use ministark_gpu;
+
+/// Vetted [`ProofOptions`] for the Goldilocks field
+///
+/// Security is conjectured (see [`ProofOptions::fri_query_security_bits`])
+/// and each query contributes `log2(lde_blowup_factor)` bits on top of the
+/// grinding bits. The field must be large enough for the target i.e. the
+/// presets assume proofs use a cubic extension of Goldilocks (~192 bits) so
+/// field security stays above the target for any LDE domain the field
+/// supports. The Merkle tree hash and public coin must also meet the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecurityPreset {
+    /// 80 bits: blowup 4 with 32 queries and 16 grinding bits
+    Fast80Bit,
+    /// 100 bits: blowup 8 with 28 queries and 16 grinding bits
+    Standard100Bit,
+    /// 128 bits: blowup 16 with 28 queries and 16 grinding bits
+    Conservative128Bit,
+}
+
+impl SecurityPreset {
+    pub const fn security_bits(self) -> u32 {
+        match self {
+            Self::Fast80Bit => 80,
+            Self::Standard100Bit => 100,
+            Self::Conservative128Bit => 128,
+        }
+    }
+}
 
 // TODO: include ability to specify:
 // - base field
 // - extension field
 // - hashing function
-#[derive(Debug, Clone, Copy, CanonicalSerialize, CanonicalDeserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ProofOptions {
     pub num_queries: u8,
+    /// Blowup of the LDE shared by the base, extension and composition trace
+    /// commitments. A smaller blowup for the low degree segments doesn't save
+    /// work: FRI queries positions of the DEEP composition's LDE and every
+    /// segment is opened at the same positions. A segment committed over a
+    /// smaller coset only contains every other query point so it would still
+    /// need evaluating over the full domain.
     pub lde_blowup_factor: u8,
     pub grinding_factor: u8,
     pub fri_folding_factor: u8,
     pub fri_max_remainder_coeffs: u8,
+    /// Number of trailing trace rows the prover replaces with random values
+    pub num_randomizers: u8,
+    /// Maximum number of FRI layers. See [`FriOptions::with_max_num_layers`]
+    pub fri_max_num_layers: Option<u8>,
+    /// Traces with at most `2^n` rows send the composition polynomial
+    /// coefficients in the proof instead of committing to them
+    pub direct_composition_log_threshold: Option<u8>,
+    /// LDE coset offset as a power of the field's multiplicative generator.
+    /// See [`ProofOptions::with_coset_offset_exponent`]
+    pub coset_offset_exponent: Option<u8>,
+    /// Number of bytes Merkle nodes are truncated to. Must match
+    /// [`merkle::MerkleTree::merkle_digest_bytes`] of the STARK's Merkle tree
+    /// e.g. [`merkle::TruncatedMatrixMerkleTree`]
+    pub merkle_digest_bytes: Option<u8>,
 }
 
 impl ProofOptions {
@@ -99,6 +155,19 @@ impl ProofOptions {
     pub const MIN_BLOWUP_FACTOR: u8 = 1;
     pub const MAX_BLOWUP_FACTOR: u8 = 128;
     pub const MAX_GRINDING_FACTOR: u8 = 50;
+    /// Traces with at most `2^n` rows send the composition polynomial
+    /// coefficients in proofs made with [`ProofOptions::recommended`]
+    pub const RECOMMENDED_DIRECT_COMPOSITION_LOG_LEN: u8 = 8;
+    /// FRI folding factor of proofs serialized before it was configurable
+    pub const LEGACY_FRI_FOLDING_FACTOR: u8 = 8;
+    /// FRI remainder size of proofs serialized before it was configurable
+    pub const LEGACY_FRI_MAX_REMAINDER_COEFFS: u8 = 64;
+    /// Leading byte of the versioned layout. The legacy layout starts with
+    /// `num_queries` which is never zero so the two can't be confused.
+    const VERSIONED_LAYOUT_TAG: u8 = 0;
+    const LAYOUT_VERSION: u8 = 2;
+    /// Layout before [`ProofOptions::merkle_digest_bytes`] was added
+    const LAYOUT_VERSION_WITHOUT_MERKLE_DIGEST_BYTES: u8 = 1;
 
     pub const fn new(
         num_queries: u8,
@@ -119,16 +188,305 @@ impl ProofOptions {
             grinding_factor,
             fri_folding_factor,
             fri_max_remainder_coeffs,
+            num_randomizers: 0,
+            fri_max_num_layers: None,
+            direct_composition_log_threshold: None,
+            coset_offset_exponent: None,
+            merkle_digest_bytes: None,
+        }
+    }
+
+    /// Returns proof options for the given security preset
+    pub const fn preset(preset: SecurityPreset) -> Self {
+        match preset {
+            SecurityPreset::Fast80Bit => Self::new(32, 4, 16, 8, 64),
+            SecurityPreset::Standard100Bit => Self::new(28, 8, 16, 8, 64),
+            SecurityPreset::Conservative128Bit => Self::new(28, 16, 16, 8, 64),
+        }
+    }
+
+    /// Returns the options of `preset` tuned for any trace length: proofs of
+    /// small traces, where the composition polynomial coefficients take less
+    /// space than their Merkle paths, send the coefficients directly. The
+    /// trace length is only known once the witness is run so the choice is
+    /// left to the prover through [`ProofOptions::with_direct_composition`].
+    pub const fn recommended(preset: SecurityPreset) -> Self {
+        Self::preset(preset).with_direct_composition(Self::RECOMMENDED_DIRECT_COMPOSITION_LOG_LEN)
+    }
+
+    /// Returns the conjectured security of the FRI queries and grinding
+    /// <https://github.com/starkware-libs/ethSTARK/blob/master/README.md#7-Measuring-Security>
+    pub fn fri_query_security_bits(&self) -> u32 {
+        let security_per_query = self.lde_blowup_factor.checked_ilog2().unwrap_or(0);
+        security_per_query * u32::from(self.num_queries) + u32::from(self.grinding_factor)
+    }
+
+    /// Sets the number of trailing trace rows that are replaced with random
+    /// values for zero-knowledge. This should be at least the number of
+    /// queries plus the constraint degree. Proving fails unless the AIR leaves
+    /// at least `num_randomizers` trailing rows unconstrained (see
+    /// [`crate::air::AirConfig::NUM_RANDOMIZER_ROWS`]).
+    pub const fn with_num_randomizers(mut self, num_randomizers: u8) -> Self {
+        self.num_randomizers = num_randomizers;
+        self
+    }
+
+    /// Caps the number of FRI layers. Folding stops once the cap is hit and
+    /// the rest is sent as a larger remainder polynomial which trades proof
+    /// size for verifier work.
+    pub const fn with_fri_max_num_layers(mut self, max_num_layers: u8) -> Self {
+        self.fri_max_num_layers = Some(max_num_layers);
+        self
+    }
+
+    /// Sends the composition polynomial coefficients directly in the proof for
+    /// traces with at most `2^log_trace_len` rows. For small traces the
+    /// coefficients are smaller than the Merkle paths needed to open the
+    /// composition trace at every query.
+    pub const fn with_direct_composition(mut self, log_trace_len: u8) -> Self {
+        self.direct_composition_log_threshold = Some(log_trace_len);
+        self
+    }
+
+    /// Offsets the LDE and constraint evaluation cosets by `g^exponent` where
+    /// `g` is the field's multiplicative generator. This overrides
+    /// [`crate::air::AirConfig::domain_offset`]. Every non-zero exponent keeps
+    /// the cosets disjoint from the trace domain so divisors such as
+    /// `X - trace_x` never vanish on a queried point.
+    pub const fn with_coset_offset_exponent(mut self, exponent: u8) -> Self {
+        assert!(
+            exponent != 0,
+            "coset offset must not be in the trace domain"
+        );
+        self.coset_offset_exponent = Some(exponent);
+        self
+    }
+
+    /// Truncates Merkle nodes to `num_bytes` bytes for smaller proofs. The
+    /// STARK's Merkle tree has to truncate to the same number of bytes (see
+    /// [`merkle::TruncatedMatrixMerkleTree`]). Truncated nodes only provide
+    /// `4 * num_bytes` bits of collision resistance.
+    pub const fn with_merkle_digest_bytes(mut self, num_bytes: u8) -> Self {
+        assert!(num_bytes > 0 && num_bytes <= 32);
+        self.merkle_digest_bytes = Some(num_bytes);
+        self
+    }
+
+    /// Returns true if the options could have been built with the constructors
+    /// and FRI supports the folding factor. Options read from untrusted bytes
+    /// should be checked before they are used to size or fold a domain: a
+    /// remainder of zero coefficients never finishes folding for example.
+    pub const fn is_valid(&self) -> bool {
+        self.num_queries >= Self::MIN_NUM_QUERIES
+            && self.num_queries <= Self::MAX_NUM_QUERIES
+            && self.lde_blowup_factor.is_power_of_two()
+            && self.lde_blowup_factor >= Self::MIN_BLOWUP_FACTOR
+            && self.lde_blowup_factor <= Self::MAX_BLOWUP_FACTOR
+            && self.grinding_factor <= Self::MAX_GRINDING_FACTOR
+            && matches!(self.fri_folding_factor, 2 | 4 | 8 | 16)
+            && self.fri_max_remainder_coeffs != 0
+            && !matches!(self.coset_offset_exponent, Some(0))
+            && !matches!(self.merkle_digest_bytes, Some(0 | 33..))
+    }
+
+    /// Returns true if the composition polynomial coefficients are sent
+    /// directly in proofs of a trace with `trace_len` rows
+    pub fn sends_composition_directly(&self, trace_len: usize) -> bool {
+        self.direct_composition_log_threshold
+            .map_or(false, |log_threshold| {
+                trace_len.ilog2() <= u32::from(log_threshold)
+            })
+    }
+
+    /// Returns true if every field missing from the legacy layout has the value
+    /// the legacy layout is read with
+    const fn fits_legacy_layout(&self) -> bool {
+        self.fri_folding_factor == Self::LEGACY_FRI_FOLDING_FACTOR
+            && self.fri_max_remainder_coeffs == Self::LEGACY_FRI_MAX_REMAINDER_COEFFS
+            && self.num_randomizers == 0
+            && self.fri_max_num_layers.is_none()
+            && self.direct_composition_log_threshold.is_none()
+            && self.coset_offset_exponent.is_none()
+            && self.merkle_digest_bytes.is_none()
+    }
+
+    /// Returns the oldest versioned layout that holds the options
+    const fn layout_version(&self) -> u8 {
+        if self.merkle_digest_bytes.is_none() {
+            Self::LAYOUT_VERSION_WITHOUT_MERKLE_DIGEST_BYTES
+        } else {
+            Self::LAYOUT_VERSION
         }
     }
 
     pub fn into_fri_options(self) -> FriOptions {
         // TODO: move fri params into struct
-        FriOptions::new(
+        let options = FriOptions::new(
             self.lde_blowup_factor.into(),
             self.fri_folding_factor.into(),
             self.fri_max_remainder_coeffs.into(),
-        )
+        );
+        match self.fri_max_num_layers {
+            Some(max_num_layers) => options.with_max_num_layers(max_num_layers.into()),
+            None => options,
+        }
+    }
+}
+
+impl CanonicalSerialize for ProofOptions {
+    /// Writes the options in the oldest layout that holds them. Public coins
+    /// are usually seeded with the serialized options so proofs made before a
+    /// layout change have to serialize to the same bytes to keep their
+    /// transcript.
+    fn serialize_with_mode<W: ark_serialize::Write>(
+        &self,
+        mut writer: W,
+        compress: ark_serialize::Compress,
+    ) -> Result<(), ark_serialize::SerializationError> {
+        if self.fits_legacy_layout() {
+            self.num_queries
+                .serialize_with_mode(&mut writer, compress)?;
+            self.lde_blowup_factor
+                .serialize_with_mode(&mut writer, compress)?;
+            self.grinding_factor
+                .serialize_with_mode(&mut writer, compress)?;
+            return Ok(());
+        }
+        Self::VERSIONED_LAYOUT_TAG.serialize_with_mode(&mut writer, compress)?;
+        let version = self.layout_version();
+        version.serialize_with_mode(&mut writer, compress)?;
+        self.num_queries
+            .serialize_with_mode(&mut writer, compress)?;
+        self.lde_blowup_factor
+            .serialize_with_mode(&mut writer, compress)?;
+        self.grinding_factor
+            .serialize_with_mode(&mut writer, compress)?;
+        self.fri_folding_factor
+            .serialize_with_mode(&mut writer, compress)?;
+        self.fri_max_remainder_coeffs
+            .serialize_with_mode(&mut writer, compress)?;
+        self.num_randomizers
+            .serialize_with_mode(&mut writer, compress)?;
+        self.fri_max_num_layers
+            .serialize_with_mode(&mut writer, compress)?;
+        self.direct_composition_log_threshold
+            .serialize_with_mode(&mut writer, compress)?;
+        self.coset_offset_exponent
+            .serialize_with_mode(&mut writer, compress)?;
+        if version == Self::LAYOUT_VERSION {
+            self.merkle_digest_bytes
+                .serialize_with_mode(&mut writer, compress)?;
+        }
+        Ok(())
+    }
+
+    fn serialized_size(&self, compress: ark_serialize::Compress) -> usize {
+        let legacy_size = self.num_queries.serialized_size(compress)
+            + self.lde_blowup_factor.serialized_size(compress)
+            + self.grinding_factor.serialized_size(compress);
+        if self.fits_legacy_layout() {
+            return legacy_size;
+        }
+        let version = self.layout_version();
+        let merkle_digest_bytes_size = if version == Self::LAYOUT_VERSION {
+            self.merkle_digest_bytes.serialized_size(compress)
+        } else {
+            0
+        };
+        legacy_size
+            + Self::VERSIONED_LAYOUT_TAG.serialized_size(compress)
+            + version.serialized_size(compress)
+            + self.fri_folding_factor.serialized_size(compress)
+            + self.fri_max_remainder_coeffs.serialized_size(compress)
+            + self.num_randomizers.serialized_size(compress)
+            + self.fri_max_num_layers.serialized_size(compress)
+            + self
+                .direct_composition_log_threshold
+                .serialized_size(compress)
+            + self.coset_offset_exponent.serialized_size(compress)
+            + merkle_digest_bytes_size
+    }
+}
+
+impl Valid for ProofOptions {
+    /// Rejects options that [`ProofOptions::is_valid`] rejects so options read
+    /// from untrusted bytes can't overflow or stall domain computations
+    fn check(&self) -> Result<(), ark_serialize::SerializationError> {
+        if self.is_valid() {
+            Ok(())
+        } else {
+            Err(ark_serialize::SerializationError::InvalidData)
+        }
+    }
+}
+
+impl CanonicalDeserialize for ProofOptions {
+    /// Reads the versioned layouts as well as the legacy layout which only
+    /// contains `num_queries`, `lde_blowup_factor` and `grinding_factor`.
+    /// Legacy options get the FRI parameters that were hardcoded at the time.
+    /// Options without `merkle_digest_bytes` don't truncate Merkle nodes.
+    fn deserialize_with_mode<R: ark_serialize::Read>(
+        mut reader: R,
+        compress: ark_serialize::Compress,
+        validate: ark_serialize::Validate,
+    ) -> Result<Self, ark_serialize::SerializationError> {
+        let tag = u8::deserialize_with_mode(&mut reader, compress, validate)?;
+        let options = if tag == Self::VERSIONED_LAYOUT_TAG {
+            Self::deserialize_versioned_layout(&mut reader, compress, validate)?
+        } else {
+            Self {
+                num_queries: tag,
+                lde_blowup_factor: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+                grinding_factor: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+                fri_folding_factor: Self::LEGACY_FRI_FOLDING_FACTOR,
+                fri_max_remainder_coeffs: Self::LEGACY_FRI_MAX_REMAINDER_COEFFS,
+                num_randomizers: 0,
+                fri_max_num_layers: None,
+                direct_composition_log_threshold: None,
+                coset_offset_exponent: None,
+                merkle_digest_bytes: None,
+            }
+        };
+        if validate == ark_serialize::Validate::Yes {
+            options.check()?;
+        }
+        Ok(options)
+    }
+}
+
+impl ProofOptions {
+    fn deserialize_versioned_layout<R: ark_serialize::Read>(
+        mut reader: R,
+        compress: ark_serialize::Compress,
+        validate: ark_serialize::Validate,
+    ) -> Result<Self, ark_serialize::SerializationError> {
+        let version = u8::deserialize_with_mode(&mut reader, compress, validate)?;
+        if version != Self::LAYOUT_VERSION
+            && version != Self::LAYOUT_VERSION_WITHOUT_MERKLE_DIGEST_BYTES
+        {
+            return Err(ark_serialize::SerializationError::InvalidData);
+        }
+
+        Ok(Self {
+            num_queries: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+            lde_blowup_factor: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+            grinding_factor: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+            fri_folding_factor: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+            fri_max_remainder_coeffs: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+            num_randomizers: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+            fri_max_num_layers: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+            direct_composition_log_threshold: <_>::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?,
+            coset_offset_exponent: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+            merkle_digest_bytes: if version == Self::LAYOUT_VERSION {
+                <_>::deserialize_with_mode(&mut reader, compress, validate)?
+            } else {
+                None
+            },
+        })
     }
 }
 
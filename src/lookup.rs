@@ -0,0 +1,158 @@
+use crate::challenges::Challenges;
+use crate::constraints::AlgebraicItem;
+use crate::constraints::Constraint;
+use crate::constraints::ExecutionTraceColumn;
+use crate::constraints::VerifierChallenge;
+use crate::expression::Expr;
+use crate::utils::FieldVariant;
+use crate::utils::GpuAllocator;
+use crate::utils::GpuVec;
+use crate::Matrix;
+use crate::StarkExtensionOf;
+use alloc::vec::Vec;
+use ark_ff::batch_inversion;
+use ark_ff::FftField;
+use ark_ff::One;
+use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use ministark_gpu::GpuFftField;
+use num_traits::Pow;
+
+/// LogUp lookup argument between two base trace columns
+///
+/// Proves every value in the `looking` column appears in the `table` column.
+/// The `multiplicity` column holds the number of times each table row is looked
+/// up. With a verifier challenge `α` the `running_sum` extension column
+/// accumulates `1/(α - looking) - multiplicity/(α - table)` row by row and must
+/// end at zero.
+///
+/// <https://eprint.iacr.org/2022/1530>
+#[derive(Clone, Copy, Debug)]
+pub struct LookupArgument {
+    /// Base column with the values being looked up
+    pub looking: usize,
+    /// Base column with the table values
+    pub table: usize,
+    /// Base column with the number of lookups into each table row
+    pub multiplicity: usize,
+    /// Extension column holding the running sum. Extension columns are indexed
+    /// after the base columns.
+    pub running_sum: usize,
+    /// Index of the verifier challenge `α`
+    pub challenge: usize,
+}
+
+impl LookupArgument {
+    /// Builds the running sum column from the base trace
+    pub fn build_running_sum<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+        challenges: &Challenges<Fq>,
+        base_trace: &Matrix<Fp>,
+    ) -> GpuVec<Fq> {
+        let alpha = challenges[self.challenge];
+        let looking = &base_trace[self.looking];
+        let table = &base_trace[self.table];
+        let multiplicity = &base_trace[self.multiplicity];
+
+        let mut looking_inverses = looking.iter().map(|&v| alpha - v).collect::<Vec<Fq>>();
+        let mut table_inverses = table.iter().map(|&v| alpha - v).collect::<Vec<Fq>>();
+        batch_inversion(&mut looking_inverses);
+        batch_inversion(&mut table_inverses);
+
+        let mut running_sum = Vec::with_capacity_in(looking.len(), GpuAllocator);
+        let mut acc = Fq::zero();
+        for ((looking_inv, table_inv), &m) in looking_inverses
+            .into_iter()
+            .zip(table_inverses)
+            .zip(multiplicity.iter())
+        {
+            acc += looking_inv - table_inv * m;
+            running_sum.push(acc);
+        }
+        running_sum
+    }
+
+    /// Returns the boundary, transition and terminal constraints of the lookup
+    pub fn constraints<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+        trace_domain: &Radix2EvaluationDomain<Fp>,
+    ) -> Vec<Constraint<FieldVariant<Fp, Fq>>> {
+        use AlgebraicItem::*;
+        let trace_len = trace_domain.size();
+        let one = Constant(FieldVariant::Fp(Fp::one()));
+        let first_trace_x = Constant(FieldVariant::Fp(trace_domain.element(0)));
+        let last_trace_x = Constant(FieldVariant::Fp(trace_domain.group_gen_inv()));
+
+        // the first row's term is the running sum itself
+        let boundary = self.step(self.running_sum.curr(), 0) / (X - first_trace_x);
+        // ensure consecutive rows differ by the next row's term except the last row
+        let transition = self.step(self.running_sum.next() - self.running_sum.curr(), 1)
+            * ((X - last_trace_x) / (X.pow(trace_len) - one));
+        // the lookup is valid if the sum of all terms is zero
+        let terminal = self.running_sum.curr() / (X - last_trace_x);
+
+        vec![
+            Constraint::new(boundary),
+            Constraint::new(transition),
+            Constraint::new(terminal),
+        ]
+    }
+
+    /// Returns `(α - f)(α - t)·delta - (α - t) + m·(α - f)` for the row at
+    /// `offset` which is zero when `delta = 1/(α - f) - m/(α - t)`.
+    fn step<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+        delta: Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>,
+        offset: isize,
+    ) -> Expr<AlgebraicItem<FieldVariant<Fp, Fq>>> {
+        let alpha = self.challenge.challenge();
+        let looking_diff = alpha.clone() - self.looking.offset(offset);
+        let table_diff = alpha - self.table.offset(offset);
+        &looking_diff * &table_diff * delta - table_diff
+            + self.multiplicity.offset(offset) * looking_diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LookupArgument;
+    use crate::challenges::Challenges;
+    use crate::utils::GpuAllocator;
+    use crate::Matrix;
+    use ark_ff::Zero;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+
+    const LOOKUP: LookupArgument = LookupArgument {
+        looking: 0,
+        table: 1,
+        multiplicity: 2,
+        running_sum: 0,
+        challenge: 0,
+    };
+
+    fn gen_matrix(looking: [u64; 4], table: [u64; 4], multiplicity: [u64; 4]) -> Matrix<Fp> {
+        let column = |values: [u64; 4]| values.map(Fp::from).to_vec_in(GpuAllocator);
+        Matrix::new(vec![column(looking), column(table), column(multiplicity)])
+    }
+
+    #[test]
+    fn running_sum_ends_at_zero_for_valid_lookup() {
+        let matrix = gen_matrix([3, 1, 3, 3], [1, 2, 3, 4], [1, 0, 3, 0]);
+        let challenges = Challenges::new(vec![Fp::from(1234567)]);
+
+        let running_sum = LOOKUP.build_running_sum(&challenges, &matrix);
+
+        assert!(running_sum.last().unwrap().is_zero());
+    }
+
+    #[test]
+    fn running_sum_is_nonzero_for_invalid_lookup() {
+        let matrix = gen_matrix([3, 1, 5, 3], [1, 2, 3, 4], [1, 0, 3, 0]);
+        let challenges = Challenges::new(vec![Fp::from(1234567)]);
+
+        let running_sum = LOOKUP.build_running_sum(&challenges, &matrix);
+
+        assert!(!running_sum.last().unwrap().is_zero());
+    }
+}
@@ -51,3 +51,96 @@ macro_rules! forward_ref_op_assign {
         }
     };
 }
+
+/// Declares the execution trace columns of an AIR by name
+///
+/// Generates a `Copy` enum with one variant per column, base columns first,
+/// along with `NUM_BASE_COLUMNS` and `NUM_EXTENSION_COLUMNS` constants and an
+/// [`ExecutionTraceColumn`](crate::constraints::ExecutionTraceColumn) impl so
+/// constraints can be written as `Column.curr()` and `Column.next()`. Indices
+/// are derived from the declaration order so a misspelled column is a compile
+/// error and every column is in range of the trace.
+///
+/// ```ignore
+/// ministark::air_columns! {
+///     pub enum FibColumn {
+///         base: [A, B],
+///         extension: [],
+///     }
+/// }
+///
+/// impl AirConfig for FibAirConfig {
+///     const NUM_BASE_COLUMNS: usize = FibColumn::NUM_BASE_COLUMNS;
+///     // ...
+/// }
+/// ```
+#[macro_export]
+macro_rules! air_columns {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            base: [$($base:ident),* $(,)?],
+            extension: [$($extension:ident),* $(,)?] $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        $vis enum $name {
+            $($base,)*
+            $($extension,)*
+        }
+
+        impl $name {
+            pub const NUM_BASE_COLUMNS: usize = <[&str]>::len(&[$(stringify!($base)),*]);
+            pub const NUM_EXTENSION_COLUMNS: usize =
+                <[&str]>::len(&[$(stringify!($extension)),*]);
+        }
+
+        impl $crate::constraints::ExecutionTraceColumn for $name {
+            fn index(&self) -> usize {
+                *self as usize
+            }
+        }
+    };
+}
+
+/// Declares the verifier challenges of an AIR by name
+///
+/// Generates a `Copy` enum with one variant per challenge, a `NUM_CHALLENGES`
+/// constant and a [`VerifierChallenge`](crate::constraints::VerifierChallenge)
+/// impl so challenges can be read as `challenges[Challenge::Beta]` and used in
+/// constraints as `Challenge::Beta.challenge()`. The number of challenges an
+/// AIR draws is derived from its constraints at runtime by
+/// [`Air::num_challenges`](crate::Air::num_challenges) so the count can only be
+/// checked against it with an assertion.
+///
+/// ```ignore
+/// ministark::air_challenges! {
+///     pub enum MemChallenge { D, E, F, Beta }
+/// }
+///
+/// assert_eq!(MemChallenge::NUM_CHALLENGES, air.num_challenges());
+/// ```
+#[macro_export]
+macro_rules! air_challenges {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident { $($challenge:ident),* $(,)? }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        $vis enum $name {
+            $($challenge,)*
+        }
+
+        impl $name {
+            pub const NUM_CHALLENGES: usize = <[&str]>::len(&[$(stringify!($challenge)),*]);
+        }
+
+        impl $crate::constraints::VerifierChallenge for $name {
+            fn index(&self) -> usize {
+                *self as usize
+            }
+        }
+    };
+}
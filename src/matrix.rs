@@ -8,9 +8,13 @@ use alloc::string::ToString;
 use alloc::vec::Vec;
 use ark_ff::FftField;
 use ark_ff::Field;
+use ark_ff::PrimeField;
 use ark_poly::domain::DomainCoeff;
 use ark_poly::domain::Radix2EvaluationDomain;
 use ark_poly::EvaluationDomain;
+use ark_serialize::Read;
+use ark_serialize::SerializationError;
+use ark_serialize::Write;
 use core::cmp::Ordering;
 use core::ops::Add;
 use core::ops::Deref;
@@ -21,12 +25,62 @@ use ministark_gpu::prelude::*;
 use ministark_gpu::utils::bit_reverse;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
+use snafu::Snafu;
+
+/// Most elements [`Matrix::deserialize_from`] allocates ahead of reading them
+pub const MAX_DESERIALIZE_CHUNK_LEN: usize = 1 << 16;
 
 /// Matrix is an array of columns.
+///
+/// Columns are always held in memory allocated by [`GpuAllocator`] so they can
+/// be shared with the GPU without copying. Backing columns with memory-mapped
+/// files isn't supported: the prover keeps the low-degree extension of every
+/// column which is `lde_blowup_factor` times larger than the trace, so a trace
+/// that doesn't fit in memory can't be proven even if its columns are mapped.
+///
+/// Columns aren't distributed across multiple GPUs either. ministark-gpu
+/// encodes every NTT on the command queue of a single global planner (see
+/// `get_planner`) and exposes no way to enumerate or target other devices.
+/// Splitting the interpolation of a wide trace column-wise would first need
+/// per-device planners in ministark-gpu.
 pub struct Matrix<F>(pub Vec<GpuVec<F>>);
 
+/// Errors that are returned when combining matrices
+#[derive(Debug, Snafu)]
+pub enum MatrixError {
+    #[snafu(display("expected {expected} rows but the matrix has {actual}"))]
+    RowCountMismatch { expected: usize, actual: usize },
+    #[snafu(display("{len} coefficients can't be split evenly into {num_cols} columns"))]
+    ReshapeMismatch { len: usize, num_cols: usize },
+}
+
+/// How [`Matrix::pad_to_power_of_two`] fills the rows it adds
+pub enum PadStrategy<'a, F> {
+    /// Repeats the last row
+    RepeatLastRow,
+    /// Fills every column with zeros
+    Zero,
+    /// Builds each row from the row before it e.g. a dummy row that copies
+    /// the previous row's registers and sets a dummy flag
+    Custom(&'a dyn Fn(&[F]) -> Vec<F>),
+}
+
 impl<F: Field> Matrix<F> {
+    /// Creates a matrix from columns.
+    ///
+    /// [`GpuVec`] fixes the allocator in the type so columns from `Vec::new()`
+    /// can't be mixed in. Columns converted with
+    /// [`crate::utils::vec_to_gpu_vec`] are still backed by the global
+    /// allocator so debug builds check they can be shared with the GPU.
     pub fn new(cols: Vec<GpuVec<F>>) -> Self {
+        #[cfg(all(debug_assertions, feature = "gpu"))]
+        for (i, col) in cols.iter().enumerate() {
+            assert!(
+                crate::utils::is_gpu_accessible(col),
+                "column {i} isn't GPU accessible. Allocate it with `GpuAllocator` instead of \
+                converting a `Vec` with `vec_to_gpu_vec`"
+            );
+        }
         Self(cols)
     }
 
@@ -46,6 +100,59 @@ impl<F: Field> Matrix<F> {
         Self::new(cols)
     }
 
+    /// Creates a matrix from columns of integers e.g. the registers of a VM.
+    /// Values are reduced into the field so ones at or above the modulus wrap
+    /// around. In a 64-bit field like Goldilocks almost every `u64` is already
+    /// canonical.
+    pub fn from_u64_columns(columns: Vec<Vec<u64>>) -> Self
+    where
+        F: PrimeField,
+    {
+        let cols = ark_std::cfg_iter!(columns)
+            .map(|column| {
+                let mut col = Vec::with_capacity_in(column.len(), GpuAllocator);
+                col.extend(column.iter().map(|&value| F::from(value)));
+                col
+            })
+            .collect();
+        Self::new(cols)
+    }
+
+    /// Creates a matrix with `num_cols` columns from a flattened row-major list
+    /// of values. Returns `None` if the number of values isn't a multiple of
+    /// `num_cols`.
+    pub fn from_flattened_rows(values: &[F], num_cols: usize) -> Option<Self> {
+        if num_cols == 0 || values.len() % num_cols != 0 {
+            return None;
+        }
+        let num_rows = values.len() / num_cols;
+        let mut cols = (0..num_cols)
+            .map(|_| Vec::with_capacity_in(num_rows, GpuAllocator))
+            .collect::<Vec<GpuVec<F>>>();
+        for row in values.chunks(num_cols) {
+            for (col, value) in cols.iter_mut().zip(row) {
+                col.push(*value);
+            }
+        }
+        Some(Self::new(cols))
+    }
+
+    /// Splits the first `len` coefficients of the composition polynomial into
+    /// `num_cols` columns so column `i` holds coefficients `i`, `i + num_cols`,
+    /// ... Coefficients past `len` must be zero and are dropped. `poly` can be
+    /// in any allocation e.g. evaluations interpolated on the CPU.
+    pub fn from_composition_poly(
+        poly: &[F],
+        len: usize,
+        num_cols: usize,
+    ) -> Result<Self, MatrixError> {
+        let coeffs = poly
+            .get(..len)
+            .ok_or(MatrixError::ReshapeMismatch { len, num_cols })?;
+        Self::from_flattened_rows(coeffs, num_cols)
+            .ok_or(MatrixError::ReshapeMismatch { len, num_cols })
+    }
+
     /// Creates a matrix from row-major list of arrays
     pub fn from_arrays<const NUM_COLS: usize>(rows: &[[F; NUM_COLS]]) -> Self {
         let num_rows = rows.len();
@@ -76,12 +183,168 @@ impl<F: Field> Matrix<F> {
         expected_len
     }
 
-    pub fn append(&mut self, other: Self) {
-        for col in other.0 {
-            self.0.push(col);
+    /// Writes the matrix in a compact binary format e.g. for caching a trace.
+    /// The number of columns and rows are written as little-endian `u64`s
+    /// followed by each column's elements as the raw little-endian `u64` limbs
+    /// of their canonical base field coordinates. Wrap `writer` in a buffer
+    /// when writing to a file.
+    pub fn serialize_to<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        writer.write_all(&(self.num_cols() as u64).to_le_bytes())?;
+        writer.write_all(&(self.num_rows() as u64).to_le_bytes())?;
+        let mut bytes = Vec::new();
+        for column in &self.0 {
+            bytes.clear();
+            for value in column.iter() {
+                for coord in value.to_base_prime_field_elements() {
+                    for limb in coord.into_bigint().as_ref() {
+                        bytes.extend_from_slice(&limb.to_le_bytes());
+                    }
+                }
+            }
+            writer.write_all(&bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a matrix written by [`Self::serialize_to`]. Limbs that aren't the
+    /// canonical encoding of a field element are rejected. The dimensions are
+    /// read from the input so columns are read in chunks of at most
+    /// [`MAX_DESERIALIZE_CHUNK_LEN`] elements and only grow as data arrives.
+    pub fn deserialize_from<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let read_len = |reader: &mut R| {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes)?;
+            usize::try_from(u64::from_le_bytes(bytes)).map_err(|_| SerializationError::InvalidData)
+        };
+        let num_cols = read_len(&mut reader)?;
+        let num_rows = read_len(&mut reader)?;
+        let num_coords = F::extension_degree() as usize;
+        let num_limbs = <F::BasePrimeField as PrimeField>::BigInt::default()
+            .as_ref()
+            .len();
+        let element_bytes = num_coords * num_limbs * 8;
+        num_rows
+            .checked_mul(element_bytes)
+            .and_then(|column_bytes| column_bytes.checked_mul(num_cols))
+            .ok_or(SerializationError::InvalidData)?;
+        // empty columns aren't backed by any input so their number is capped
+        if num_rows == 0 && num_cols > MAX_DESERIALIZE_CHUNK_LEN {
+            return Err(SerializationError::InvalidData);
+        }
+        let chunk_len = num_rows.min(MAX_DESERIALIZE_CHUNK_LEN);
+        let mut bytes = vec![0; chunk_len * element_bytes];
+        let mut coords = Vec::with_capacity(num_coords);
+        let mut columns = Vec::with_capacity(num_cols.min(MAX_DESERIALIZE_CHUNK_LEN));
+        for _ in 0..num_cols {
+            let mut column = Vec::with_capacity_in(chunk_len, GpuAllocator);
+            while column.len() < num_rows {
+                let chunk_len = chunk_len.min(num_rows - column.len());
+                let chunk_bytes = &mut bytes[..chunk_len * element_bytes];
+                reader.read_exact(chunk_bytes)?;
+                for value_bytes in chunk_bytes.chunks_exact(element_bytes) {
+                    coords.clear();
+                    for coord_bytes in value_bytes.chunks_exact(num_limbs * 8) {
+                        let mut bigint = <F::BasePrimeField as PrimeField>::BigInt::default();
+                        let limbs = bigint.as_mut().iter_mut();
+                        for (limb, limb_bytes) in limbs.zip(coord_bytes.chunks_exact(8)) {
+                            *limb = u64::from_le_bytes(limb_bytes.try_into().unwrap());
+                        }
+                        let coord = F::BasePrimeField::from_bigint(bigint)
+                            .ok_or(SerializationError::InvalidData)?;
+                        coords.push(coord);
+                    }
+                    let value = F::from_base_prime_field_elems(&coords)
+                        .ok_or(SerializationError::InvalidData)?;
+                    column.push(value);
+                }
+            }
+            columns.push(column);
+        }
+        Ok(Self::new(columns))
+    }
+
+    /// Extends every column to the next power of two number of rows. Matrices
+    /// with a power of two number of rows are left unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the matrix has no rows and the strategy needs a previous row
+    /// or a custom row has a different number of values than there are
+    /// columns.
+    pub fn pad_to_power_of_two(&mut self, strategy: PadStrategy<F>) {
+        let num_rows = self.num_rows();
+        let padded_len = num_rows.next_power_of_two();
+        if num_rows == padded_len {
+            return;
+        }
+        match strategy {
+            PadStrategy::RepeatLastRow => {
+                for column in &mut self.0 {
+                    let last = *column.last().expect("matrix has no rows");
+                    column.resize(padded_len, last);
+                }
+            }
+            PadStrategy::Zero => {
+                for column in &mut self.0 {
+                    column.resize(padded_len, F::zero());
+                }
+            }
+            PadStrategy::Custom(next_row) => {
+                let last_row = num_rows.checked_sub(1).and_then(|row| self.get_row(row));
+                let mut row = last_row.expect("matrix has no rows");
+                for _ in num_rows..padded_len {
+                    row = next_row(&row);
+                    assert_eq!(self.num_cols(), row.len(), "padding row has wrong width");
+                    for (column, &value) in self.0.iter_mut().zip(&row) {
+                        column.push(value);
+                    }
+                }
+            }
         }
     }
 
+    /// Appends the columns of `other` e.g. to merge the base and extension
+    /// columns of a trace. Either matrix can have no columns.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if both matrices have columns but a different number of
+    /// rows. `self` is left unchanged in that case.
+    pub fn append(&mut self, other: Self) -> Result<(), MatrixError> {
+        if !self.0.is_empty() && !other.0.is_empty() {
+            let (expected, actual) = (self.num_rows(), other.num_rows());
+            if expected != actual {
+                return Err(MatrixError::RowCountMismatch { expected, actual });
+            }
+        }
+        self.0.extend(other.0);
+        Ok(())
+    }
+
+    /// Appends an extension field column as `[Fq:F]` columns that hold the
+    /// coordinates of each value over `F`. Constraints read the value back with
+    /// [`extension_column`](crate::constraints::extension_column).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the matrix has columns and `values` has a different
+    /// number of rows.
+    pub fn append_extension_column<Fq: Field<BasePrimeField = F>>(
+        &mut self,
+        values: &[Fq],
+    ) -> Result<(), MatrixError> {
+        let degree = usize::try_from(Fq::extension_degree()).unwrap();
+        let mut cols = (0..degree)
+            .map(|_| Vec::with_capacity_in(values.len(), GpuAllocator))
+            .collect::<Vec<GpuVec<F>>>();
+        for value in values {
+            for (col, coord) in cols.iter_mut().zip(value.to_base_prime_field_elements()) {
+                col.push(coord);
+            }
+        }
+        self.append(Self::new(cols))
+    }
+
     pub fn join(mut matrices: Vec<Self>) -> Self {
         let mut accumulator = Vec::new();
         for matrix in &mut matrices {
@@ -207,12 +470,18 @@ impl<F: Field> Matrix<F> {
         self
     }
 
-    /// Evaluates the columns of the matrix
-    pub fn into_evaluations(self, domain: Radix2EvaluationDomain<F::FftField>) -> Self
+    /// Evaluates the columns of the matrix. The domain doesn't have to relate
+    /// to the domain the columns were interpolated over. Columns with more
+    /// coefficients than the domain has elements are first reduced modulo the
+    /// domain's vanishing polynomial rather than truncated.
+    pub fn into_evaluations(mut self, domain: Radix2EvaluationDomain<F::FftField>) -> Self
     where
         F: GpuField + DomainCoeff<F::FftField>,
         F::FftField: FftField,
     {
+        for column in &mut self.0 {
+            reduce_mod_vanishing_poly(column, &domain);
+        }
         // TODO: using the newtype pattern for type safety would be cool
         // i.e. take as input Matrix<Polynomials> and return Matrix<Evaluations>
         // https://doc.rust-lang.org/book/ch19-04-advanced-types.html
@@ -222,6 +491,10 @@ impl<F: Field> Matrix<F> {
         return self.into_evaluations_gpu(domain);
     }
 
+    /// Evaluates the columns of the matrix and stores the evaluations in
+    /// bit-reversed order. Trace LDEs are committed in this order so the
+    /// evaluations FRI folds together (`x` and `-x` etc.) are contiguous and
+    /// query positions index directly into the bit-reversed rows.
     pub fn into_bit_reversed_evaluations(self, domain: Radix2EvaluationDomain<F::FftField>) -> Self
     where
         F: GpuField + DomainCoeff<F::FftField>,
@@ -242,6 +515,41 @@ impl<F: Field> Matrix<F> {
         self.clone().into_evaluations(domain)
     }
 
+    /// Low degree extends the columns of the matrix from the trace domain to
+    /// the LDE domain. Only a single copy of the matrix is made since the
+    /// intermediate coefficients are evaluated in place.
+    pub fn low_degree_extend(
+        &self,
+        trace_domain: Radix2EvaluationDomain<F::FftField>,
+        lde_domain: Radix2EvaluationDomain<F::FftField>,
+    ) -> Self
+    where
+        F: GpuField + DomainCoeff<F::FftField>,
+        F::FftField: FftField,
+    {
+        self.clone()
+            .into_polynomials(trace_domain)
+            .into_evaluations(lde_domain)
+    }
+
+    /// Same as [`Self::low_degree_extend`] but the evaluations are stored in
+    /// bit-reversed order.
+    pub fn bit_reversed_low_degree_extend(
+        &self,
+        trace_domain: Radix2EvaluationDomain<F::FftField>,
+        lde_domain: Radix2EvaluationDomain<F::FftField>,
+    ) -> Self
+    where
+        F: GpuField + DomainCoeff<F::FftField>,
+        F::FftField: FftField,
+    {
+        self.clone()
+            .into_polynomials(trace_domain)
+            .into_bit_reversed_evaluations(lde_domain)
+    }
+
+    /// Evaluates the columns of the matrix in bit-reversed order. See
+    /// [`Self::into_bit_reversed_evaluations`].
     pub fn bit_reversed_evaluate(&self, domain: Radix2EvaluationDomain<F::FftField>) -> Self
     where
         F: GpuField + DomainCoeff<F::FftField>,
@@ -305,6 +613,10 @@ impl<F: Field> Matrix<F> {
             .collect()
     }
 
+    /// Returns the degree of each column's polynomial i.e. the index of the
+    /// highest nonzero coefficient. The matrix must be in coefficient form (see
+    /// [`Self::interpolate`]). Useful for checking columns have the expected
+    /// structure before proving e.g. that a boundary column is constant.
     pub fn column_degrees(&self) -> Vec<usize> {
         self.0
             .iter()
@@ -394,6 +706,33 @@ impl<F: Field> Matrix<F> {
     }
 }
 
+/// Reduces the coefficients of a polynomial modulo `Xⁿ - hⁿ`, the vanishing
+/// polynomial of a coset `hH` of size `n`, so it has the same evaluations
+/// over the domain with at most `n` coefficients
+fn reduce_mod_vanishing_poly<F: GpuField + DomainCoeff<F::FftField>>(
+    coeffs: &mut GpuVec<F>,
+    domain: &Radix2EvaluationDomain<F::FftField>,
+) where
+    F::FftField: FftField,
+{
+    let n = domain.size();
+    if coeffs.len() <= n {
+        return;
+    }
+    let offset_pow_n = domain.coset_offset_pow_size();
+    let mut shift = F::FftField::ONE;
+    let (low, high) = coeffs.split_at_mut(n);
+    for chunk in high.chunks(n) {
+        shift *= offset_pow_n;
+        for (coeff, &high_coeff) in low.iter_mut().zip(chunk) {
+            let mut high_coeff = high_coeff;
+            high_coeff *= shift;
+            *coeff += high_coeff;
+        }
+    }
+    coeffs.truncate(n);
+}
+
 impl<F: Field> Clone for Matrix<F> {
     fn clone(&self) -> Self {
         Self(
@@ -453,3 +792,183 @@ impl<F: Field> TryFrom<Matrix<F>> for GpuVec<F> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Matrix;
+    use super::MatrixError;
+    use super::PadStrategy;
+    use crate::utils::is_gpu_accessible;
+    use crate::utils::tests::gen_fib_matrix;
+    use crate::utils::GpuAllocator;
+    use ark_ff::Field;
+    use ark_ff::One;
+    use ark_poly::EvaluationDomain;
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_serialize::SerializationError;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+
+    #[test]
+    fn append_rejects_mismatched_row_counts() {
+        let mut matrix = gen_fib_matrix::<Fp>(8);
+        let other = gen_fib_matrix::<Fp>(16);
+
+        let result = matrix.append(other);
+
+        assert!(matches!(
+            result,
+            Err(MatrixError::RowCountMismatch {
+                expected: 8,
+                actual: 16
+            })
+        ));
+        assert_eq!(2, matrix.num_cols());
+        matrix.append(gen_fib_matrix(8)).unwrap();
+        assert_eq!(4, matrix.num_cols());
+    }
+
+    #[test]
+    fn pad_to_power_of_two_repeats_the_last_row() {
+        let mut matrix = gen_fib_matrix::<Fp>(128);
+        for column in &mut matrix.0 {
+            column.truncate(100);
+        }
+        let last_row = matrix.get_row(99).unwrap();
+
+        matrix.pad_to_power_of_two(PadStrategy::RepeatLastRow);
+
+        assert_eq!(128, matrix.num_rows());
+        assert!((100..128).all(|row| matrix.get_row(row).unwrap() == last_row));
+    }
+
+    #[test]
+    fn pad_to_power_of_two_builds_custom_rows_from_the_previous_row() {
+        let mut matrix = Matrix::new(vec![[1u64, 2, 3].map(Fp::from).to_vec_in(GpuAllocator)]);
+
+        matrix.pad_to_power_of_two(PadStrategy::Custom(&|row| vec![row[0].double()]));
+
+        assert_eq!([1u64, 2, 3, 6].map(Fp::from), *matrix[0]);
+    }
+
+    #[test]
+    fn from_u64_columns_reduces_values_above_the_modulus() {
+        let modulus = 0xFFFF_FFFF_0000_0001;
+        let columns = vec![
+            vec![0, 7, modulus - 1],
+            vec![modulus, modulus + 5, u64::MAX],
+        ];
+
+        let matrix = Matrix::<Fp>::from_u64_columns(columns);
+
+        assert_eq!([Fp::from(0), Fp::from(7), -Fp::one()], *matrix[0]);
+        assert_eq!(
+            [Fp::from(0), Fp::from(5), Fp::from(0xFFFF_FFFEu64)],
+            *matrix[1]
+        );
+    }
+
+    #[test]
+    fn from_flattened_rows_splits_values_into_columns() {
+        let values = (0..6u64).map(Fp::from).collect::<Vec<_>>();
+
+        let matrix = Matrix::from_flattened_rows(&values, 3).unwrap();
+
+        assert_eq!(3, matrix.num_cols());
+        assert_eq!(2, matrix.num_rows());
+        assert_eq!([Fp::from(1), Fp::from(4)], *matrix[1]);
+    }
+
+    #[test]
+    fn from_composition_poly_accepts_global_allocations() {
+        let poly = (0..8u64).map(Fp::from).collect::<Vec<_>>();
+
+        let matrix = Matrix::from_composition_poly(&poly, 6, 2).unwrap();
+
+        assert_eq!(3, matrix.num_rows());
+        assert_eq!([Fp::from(1), Fp::from(3), Fp::from(5)], *matrix[1]);
+        assert!(matches!(
+            Matrix::from_composition_poly(&poly, 9, 3),
+            Err(MatrixError::ReshapeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn from_flattened_rows_rejects_uneven_length() {
+        let values = vec![Fp::one(); 7];
+
+        assert!(Matrix::from_flattened_rows(&values, 3).is_none());
+    }
+
+    #[test]
+    fn serialization_round_trip() {
+        let matrix = gen_fib_matrix::<Fp>(16);
+        let mut bytes = Vec::new();
+
+        matrix.serialize_to(&mut bytes).unwrap();
+        let deserialized = Matrix::<Fp>::deserialize_from(&*bytes).unwrap();
+
+        assert_eq!(16 + 2 * 16 * 8, bytes.len());
+        assert_eq!(2u64.to_le_bytes(), bytes[..8]);
+        assert_eq!(16u64.to_le_bytes(), bytes[8..16]);
+        // raw limbs of the canonical value rather than the Montgomery form
+        assert_eq!(1u64.to_le_bytes(), bytes[16..24]);
+        assert_eq!(matrix.0, deserialized.0);
+    }
+
+    #[test]
+    fn oversized_header_fails_without_allocating() {
+        let mut bytes = Vec::new();
+        bytes.extend(1u64.to_le_bytes());
+        bytes.extend((1u64 << 40).to_le_bytes());
+        bytes.extend([0; 8]);
+
+        assert!(Matrix::<Fp>::deserialize_from(&*bytes).is_err());
+
+        let mut bytes = Vec::new();
+        bytes.extend(u64::MAX.to_le_bytes());
+        bytes.extend(0u64.to_le_bytes());
+
+        assert!(matches!(
+            Matrix::<Fp>::deserialize_from(&*bytes),
+            Err(SerializationError::InvalidData)
+        ));
+    }
+
+    #[test]
+    fn gpu_allocated_columns_are_gpu_accessible() {
+        let mut col = Vec::with_capacity_in(8, GpuAllocator);
+        col.resize(8, Fp::one());
+
+        assert!(is_gpu_accessible(&col));
+        assert_eq!(8, Matrix::new(vec![col]).num_rows());
+    }
+
+    #[test]
+    fn evaluate_over_a_smaller_unrelated_domain() {
+        let trace_domain = Radix2EvaluationDomain::<Fp>::new(16).unwrap();
+        let domain = Radix2EvaluationDomain::<Fp>::new_coset(4, Fp::from(7u64)).unwrap();
+        let matrix = gen_fib_matrix::<Fp>(16);
+
+        let polys = matrix.interpolate(trace_domain);
+        let evals = polys.evaluate(domain);
+
+        for (i, x) in domain.elements().enumerate() {
+            assert_eq!(polys.evaluate_at(x), evals.get_row(i).unwrap());
+        }
+    }
+
+    #[test]
+    fn column_degrees_of_interpolated_columns() {
+        let domain = Radix2EvaluationDomain::<Fp>::new(8).unwrap();
+        let constant = vec![Fp::from(7u64); 8].to_vec_in(GpuAllocator);
+        let linear = domain
+            .elements()
+            .collect::<Vec<_>>()
+            .to_vec_in(GpuAllocator);
+        let matrix = Matrix::new(vec![constant, linear]);
+
+        let polys = matrix.interpolate(domain);
+
+        assert_eq!(vec![0, 1], polys.column_degrees());
+    }
+}
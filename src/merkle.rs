@@ -1,6 +1,7 @@
 use crate::hash::Digest;
 use crate::hash::ElementHashFn;
 use crate::hash::HashFn;
+use crate::hash::TruncatedDigest;
 use crate::Matrix;
 use alloc::vec::Vec;
 use ark_ff::Field;
@@ -9,6 +10,8 @@ use ark_serialize::CanonicalSerialize;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use snafu::Snafu;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::iter::zip;
@@ -23,6 +26,8 @@ pub enum Error {
     NumberOfLeavesNotPowerOfTwo { n: usize },
     #[snafu(display("leaf index `{i}` cannot exceed the number of leaves (`{n}`)"))]
     LeafIndexOutOfBounds { i: usize, n: usize },
+    #[snafu(display("leaf `{i}` does not match the proof"))]
+    LeafMismatch { i: usize },
     #[snafu(display("proof is invalid"))]
     InvalidProof,
 }
@@ -50,6 +55,14 @@ pub trait MerkleTree: Sized + Send + Sync + Clone {
 
     /// Returns the number of security bits
     fn security_level_bits() -> u32;
+
+    /// Returns the number of bytes nodes are truncated to or `None` if they
+    /// aren't. Proofs declare this in
+    /// [`ProofOptions::merkle_digest_bytes`](crate::ProofOptions::merkle_digest_bytes)
+    /// and the prover and verifier reject options that don't match.
+    fn merkle_digest_bytes() -> Option<u8> {
+        None
+    }
 }
 
 // TODO: all these merkle tree abstractions are way out of control. need to
@@ -292,10 +305,15 @@ impl<C: MerkleTreeConfig> MerkleTree for MerkleTreeImpl<C> {
     }
 }
 
-/// Merkle tree that supports proving/verifying rows of a matrix
+/// Commitment to the rows of a matrix
 ///
 /// Inspired by plonky3's MMCS
 /// <https://github.com/Plonky3/Plonky3/blob/main/commit/src/mmcs.rs>
+///
+/// The prover and verifier only commit to and open traces through this trait
+/// (see [`Stark::MerkleTree`](crate::stark::Stark::MerkleTree)) so vector
+/// commitments other than Merkle trees can be plugged in e.g.
+/// [`FlatMatrixCommitment`].
 pub trait MatrixMerkleTree<T>: MerkleTree + Sized {
     fn from_matrix(m: &Matrix<T>) -> Self;
 
@@ -376,14 +394,361 @@ impl<F: Field, H: ElementHashFn<F> + Send + Sync + 'static> MatrixMerkleTree<F>
             .iter()
             .map(|r| H::hash_elements(r.as_ref().iter().copied()))
             .collect::<Vec<_>>();
-        if proof.initial_leaves == initial_leaves {
-            Self::verify(root, proof, &indices)
-        } else {
-            Err(Error::InvalidProof)
+        if proof.initial_leaves.len() != initial_leaves.len() {
+            return Err(Error::InvalidProof);
+        }
+        let mismatch = zip(&proof.initial_leaves, &initial_leaves).position(|(a, b)| a != b);
+        if let Some(i) = mismatch {
+            return Err(Error::LeafMismatch { i: indices[i] });
+        }
+        Self::verify(root, proof, &indices)
+    }
+}
+
+/// Matrix Merkle tree with `LEAF_WIDTH` consecutive rows packed into each leaf
+///
+/// A leaf is the hash of its rows' hashes. Wider leaves give a shorter tree
+/// and shorter Merkle paths but opening a row also requires the hashes of the
+/// other rows in its leaf. `LEAF_WIDTH = 1` gives the same commitment as
+/// [`MatrixMerkleTreeImpl`].
+pub struct PackedMatrixMerkleTree<H: HashFn, const LEAF_WIDTH: usize> {
+    merkle_tree: MerkleTreeImpl<HashedLeafConfig<H>>,
+    row_hashes: Vec<H::Digest>,
+}
+
+/// Proof for rows of a [`PackedMatrixMerkleTree`]
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalDeserialize, CanonicalSerialize)]
+pub struct PackedMerkleProof<D: CanonicalDeserialize + CanonicalSerialize + Clone> {
+    pub view: MerkleView<D, D>,
+    /// Hashes of the rows that share a leaf with an opened row but aren't opened
+    pub row_hashes: Vec<D>,
+}
+
+impl<H: HashFn, const LEAF_WIDTH: usize> Clone for PackedMatrixMerkleTree<H, LEAF_WIDTH> {
+    fn clone(&self) -> Self {
+        Self {
+            merkle_tree: self.merkle_tree.clone(),
+            row_hashes: self.row_hashes.clone(),
+        }
+    }
+}
+
+impl<H: HashFn, const LEAF_WIDTH: usize> PackedMatrixMerkleTree<H, LEAF_WIDTH> {
+    fn new(row_hashes: Vec<H::Digest>) -> Result<Self, Error> {
+        assert!(LEAF_WIDTH.is_power_of_two());
+        let leaves = row_hashes
+            .chunks(LEAF_WIDTH)
+            .map(hash_packed_leaf::<H>)
+            .collect();
+        Ok(Self {
+            merkle_tree: MerkleTreeImpl::new(leaves)?,
+            row_hashes,
+        })
+    }
+}
+
+impl<H: HashFn, const LEAF_WIDTH: usize> MerkleTree for PackedMatrixMerkleTree<H, LEAF_WIDTH> {
+    type Proof = PackedMerkleProof<H::Digest>;
+    type Root = H::Digest;
+
+    fn root(&self) -> Self::Root {
+        self.merkle_tree.root()
+    }
+
+    fn prove(&self, indices: &[usize]) -> Result<Self::Proof, Error> {
+        let n = self.row_hashes.len();
+        if let Some(&i) = indices.iter().find(|&&i| i >= n) {
+            return Err(Error::LeafIndexOutOfBounds { i, n });
+        }
+        let leaf_ids = packed_leaf_ids::<LEAF_WIDTH>(indices);
+        let opened_row_ids = indices.iter().copied().collect::<BTreeSet<usize>>();
+        let row_hashes = leaf_ids
+            .iter()
+            .flat_map(|leaf_id| leaf_id * LEAF_WIDTH..(leaf_id + 1) * LEAF_WIDTH)
+            .filter(|row_id| !opened_row_ids.contains(row_id))
+            .map(|row_id| self.row_hashes[row_id].clone())
+            .collect();
+        Ok(PackedMerkleProof {
+            view: self.merkle_tree.prove(&leaf_ids)?,
+            row_hashes,
+        })
+    }
+
+    /// Only checks the packed leaves in the proof resolve to the root. Use
+    /// [`MatrixMerkleTree::verify_rows`] to check rows against the leaves.
+    fn verify(root: &Self::Root, proof: Self::Proof, indices: &[usize]) -> Result<(), Error> {
+        let leaf_ids = packed_leaf_ids::<LEAF_WIDTH>(indices);
+        MerkleTreeImpl::<HashedLeafConfig<H>>::verify(root, proof.view, &leaf_ids)
+    }
+
+    fn security_level_bits() -> u32 {
+        H::COLLISION_RESISTANCE
+    }
+}
+
+impl<F: Field, H: ElementHashFn<F> + Send + Sync + 'static, const LEAF_WIDTH: usize>
+    MatrixMerkleTree<F> for PackedMatrixMerkleTree<H, LEAF_WIDTH>
+{
+    /// # Panics
+    ///
+    /// Panics unless the rows pack into a power of two number of leaves and
+    /// there are at least two leaves
+    fn from_matrix(m: &Matrix<F>) -> Self {
+        let num_rows = m.num_rows();
+        Self::new(hash_rows::<F, H>(m)).unwrap_or_else(|err| {
+            panic!("can't pack {num_rows} rows into leaves of {LEAF_WIDTH} rows: {err}")
+        })
+    }
+
+    fn verify_rows(
+        root: &Self::Root,
+        row_ids: &[usize],
+        rows: &[impl AsRef<[F]>],
+        proof: Self::Proof,
+    ) -> Result<(), Error> {
+        let opened_row_hashes = zip(row_ids, rows)
+            .map(|(&i, row)| (i, H::hash_elements(row.as_ref().iter().copied())))
+            .collect::<BTreeMap<_, _>>();
+        let leaf_ids = packed_leaf_ids::<LEAF_WIDTH>(row_ids);
+        let mut other_row_hashes = proof.row_hashes.into_iter();
+        let mut leaves = Vec::new();
+        for leaf_id in &leaf_ids {
+            let mut leaf_row_hashes = Vec::new();
+            for row_id in leaf_id * LEAF_WIDTH..(leaf_id + 1) * LEAF_WIDTH {
+                let row_hash = match opened_row_hashes.get(&row_id) {
+                    Some(row_hash) => row_hash.clone(),
+                    None => other_row_hashes.next().ok_or(Error::InvalidProof)?,
+                };
+                leaf_row_hashes.push(row_hash);
+            }
+            leaves.push(hash_packed_leaf::<H>(&leaf_row_hashes));
+        }
+        if other_row_hashes.next().is_some() || proof.view.initial_leaves.len() != leaves.len() {
+            return Err(Error::InvalidProof);
+        }
+        let mismatch = zip(&proof.view.initial_leaves, &leaves).position(|(a, b)| a != b);
+        if let Some(i) = mismatch {
+            return Err(Error::LeafMismatch {
+                i: leaf_ids[i] * LEAF_WIDTH,
+            });
+        }
+        MerkleTreeImpl::<HashedLeafConfig<H>>::verify(root, proof.view, &leaf_ids)
+    }
+}
+
+/// Matrix Merkle tree with every node below the root truncated to `NUM_BYTES`
+///
+/// Authentication paths shrink by a factor of `32 / NUM_BYTES` but collision
+/// resistance drops to `4 * NUM_BYTES` bits. The root is the full hash of the
+/// truncated top node so it can still be absorbed by the public coin. Proofs
+/// carry the truncated top node to check paths against.
+pub struct TruncatedMatrixMerkleTree<H: HashFn, const NUM_BYTES: usize> {
+    merkle_tree: MerkleTreeImpl<TruncatedNodeConfig<H, NUM_BYTES>>,
+}
+
+/// Proof for rows of a [`TruncatedMatrixMerkleTree`]
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalDeserialize, CanonicalSerialize)]
+pub struct TruncatedMerkleProof<const NUM_BYTES: usize> {
+    pub view: MerkleView<TruncatedDigest<NUM_BYTES>, TruncatedDigest<NUM_BYTES>>,
+    /// Truncated node the root is the hash of
+    pub top_node: TruncatedDigest<NUM_BYTES>,
+}
+
+impl<H: HashFn, const NUM_BYTES: usize> Clone for TruncatedMatrixMerkleTree<H, NUM_BYTES> {
+    fn clone(&self) -> Self {
+        Self {
+            merkle_tree: self.merkle_tree.clone(),
+        }
+    }
+}
+
+impl<H: HashFn, const NUM_BYTES: usize> MerkleTree for TruncatedMatrixMerkleTree<H, NUM_BYTES> {
+    type Proof = TruncatedMerkleProof<NUM_BYTES>;
+    type Root = H::Digest;
+
+    fn root(&self) -> Self::Root {
+        H::hash_chunks([self.merkle_tree.root().0.as_slice()])
+    }
+
+    fn prove(&self, indices: &[usize]) -> Result<Self::Proof, Error> {
+        Ok(TruncatedMerkleProof {
+            view: self.merkle_tree.prove(indices)?,
+            top_node: self.merkle_tree.root(),
+        })
+    }
+
+    fn verify(root: &Self::Root, proof: Self::Proof, indices: &[usize]) -> Result<(), Error> {
+        if H::hash_chunks([proof.top_node.0.as_slice()]) != *root {
+            return Err(Error::InvalidProof);
         }
+        MerkleTreeImpl::<TruncatedNodeConfig<H, NUM_BYTES>>::verify(
+            &proof.top_node,
+            proof.view,
+            indices,
+        )
+    }
+
+    fn security_level_bits() -> u32 {
+        TruncatedNodeConfig::<H, NUM_BYTES>::security_level_bits()
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn merkle_digest_bytes() -> Option<u8> {
+        Some(NUM_BYTES as u8)
     }
 }
 
+impl<F: Field, H: ElementHashFn<F> + Send + Sync + 'static, const NUM_BYTES: usize>
+    MatrixMerkleTree<F> for TruncatedMatrixMerkleTree<H, NUM_BYTES>
+{
+    fn from_matrix(m: &Matrix<F>) -> Self {
+        let leaves = hash_rows::<F, H>(m)
+            .iter()
+            .map(TruncatedDigest::new)
+            .collect();
+        Self {
+            merkle_tree: MerkleTreeImpl::new(leaves).unwrap(),
+        }
+    }
+
+    fn verify_rows(
+        root: &Self::Root,
+        row_ids: &[usize],
+        rows: &[impl AsRef<[F]>],
+        proof: Self::Proof,
+    ) -> Result<(), Error> {
+        // remove duplicates and sort
+        let mut instances = zip(row_ids, rows).collect::<Vec<_>>();
+        instances.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        instances.dedup_by(|(a, _), (b, _)| a == b);
+
+        let (indices, rows): (Vec<_>, Vec<_>) = instances.into_iter().unzip();
+        let initial_leaves = rows
+            .iter()
+            .map(|r| TruncatedDigest::new(&H::hash_elements(r.as_ref().iter().copied())))
+            .collect::<Vec<_>>();
+        if proof.view.initial_leaves.len() != initial_leaves.len() {
+            return Err(Error::InvalidProof);
+        }
+        let mismatch = zip(&proof.view.initial_leaves, &initial_leaves).position(|(a, b)| a != b);
+        if let Some(i) = mismatch {
+            return Err(Error::LeafMismatch { i: indices[i] });
+        }
+        Self::verify(root, proof, &indices)
+    }
+}
+
+/// Nodes of a [`TruncatedMatrixMerkleTree`]
+pub struct TruncatedNodeConfig<H: HashFn, const NUM_BYTES: usize>(PhantomData<H>);
+
+impl<H: HashFn, const NUM_BYTES: usize> MerkleTreeConfig for TruncatedNodeConfig<H, NUM_BYTES> {
+    type Digest = TruncatedDigest<NUM_BYTES>;
+    type Leaf = TruncatedDigest<NUM_BYTES>;
+
+    fn hash_leaves(depth: u32, l0: &Self::Leaf, l1: &Self::Leaf) -> Self::Digest {
+        Self::hash_nodes(depth, l0, l1)
+    }
+
+    fn hash_nodes(_: u32, n0: &Self::Digest, n1: &Self::Digest) -> Self::Digest {
+        TruncatedDigest::new(&H::hash_chunks([n0.0.as_slice(), n1.0.as_slice()]))
+    }
+
+    fn security_level_bits() -> u32 {
+        H::COLLISION_RESISTANCE.min(TruncatedDigest::<NUM_BYTES>::COLLISION_RESISTANCE)
+    }
+}
+
+/// Commits to a matrix by hashing all its row hashes together
+///
+/// Openings contain the hash of every row so proofs grow linearly with the
+/// number of rows. Only intended for testing and as a reference for
+/// implementing other row commitments.
+pub struct FlatMatrixCommitment<H: HashFn> {
+    row_hashes: Vec<H::Digest>,
+}
+
+impl<H: HashFn> Clone for FlatMatrixCommitment<H> {
+    fn clone(&self) -> Self {
+        Self {
+            row_hashes: self.row_hashes.clone(),
+        }
+    }
+}
+
+impl<H: HashFn> MerkleTree for FlatMatrixCommitment<H> {
+    type Proof = Vec<H::Digest>;
+    type Root = H::Digest;
+
+    fn root(&self) -> Self::Root {
+        hash_packed_leaf::<H>(&self.row_hashes)
+    }
+
+    fn prove(&self, indices: &[usize]) -> Result<Self::Proof, Error> {
+        let n = self.row_hashes.len();
+        if let Some(&i) = indices.iter().find(|&&i| i >= n) {
+            return Err(Error::LeafIndexOutOfBounds { i, n });
+        }
+        Ok(self.row_hashes.clone())
+    }
+
+    fn verify(root: &Self::Root, proof: Self::Proof, indices: &[usize]) -> Result<(), Error> {
+        let n = proof.len();
+        if let Some(&i) = indices.iter().find(|&&i| i >= n) {
+            return Err(Error::LeafIndexOutOfBounds { i, n });
+        }
+        if hash_packed_leaf::<H>(&proof) != *root {
+            return Err(Error::InvalidProof);
+        }
+        Ok(())
+    }
+
+    fn security_level_bits() -> u32 {
+        H::COLLISION_RESISTANCE
+    }
+}
+
+impl<F: Field, H: ElementHashFn<F> + Send + Sync + 'static> MatrixMerkleTree<F>
+    for FlatMatrixCommitment<H>
+{
+    fn from_matrix(m: &Matrix<F>) -> Self {
+        Self {
+            row_hashes: hash_rows::<F, H>(m),
+        }
+    }
+
+    fn verify_rows(
+        root: &Self::Root,
+        row_ids: &[usize],
+        rows: &[impl AsRef<[F]>],
+        proof: Self::Proof,
+    ) -> Result<(), Error> {
+        for (&i, row) in zip(row_ids, rows) {
+            let row_hash = H::hash_elements(row.as_ref().iter().copied());
+            if proof.get(i) != Some(&row_hash) {
+                return Err(Error::LeafMismatch { i });
+            }
+        }
+        Self::verify(root, proof, row_ids)
+    }
+}
+
+/// Returns the sorted and deduplicated indices of the leaves holding `row_ids`
+fn packed_leaf_ids<const LEAF_WIDTH: usize>(row_ids: &[usize]) -> Vec<usize> {
+    let mut leaf_ids = row_ids
+        .iter()
+        .map(|row_id| row_id / LEAF_WIDTH)
+        .collect::<Vec<_>>();
+    leaf_ids.sort_unstable();
+    leaf_ids.dedup();
+    leaf_ids
+}
+
+fn hash_packed_leaf<H: HashFn>(row_hashes: &[H::Digest]) -> H::Digest {
+    let mut row_hashes = row_hashes.iter().cloned();
+    let first = row_hashes.next().unwrap_or_default();
+    row_hashes.fold(first, |leaf, row_hash| H::merge(&leaf, &row_hash))
+}
+
 pub struct HashedLeafConfig<H: HashFn>(PhantomData<H>);
 
 impl<H: HashFn> Clone for HashedLeafConfig<H> {
@@ -409,6 +774,26 @@ impl<H: HashFn> MerkleTreeConfig for HashedLeafConfig<H> {
     }
 }
 
+/// Number of bytes in each leaf of [`public_input_root`]
+pub const PUBLIC_INPUT_CHUNK_SIZE: usize = 1024;
+
+/// Commits to a large public input (e.g. a program's source or initial
+/// memory) with a Merkle tree over [`PUBLIC_INPUT_CHUNK_SIZE`] byte chunks.
+/// The prover and verifier absorb the root into the transcript instead of the
+/// whole input and individual chunks can be opened against it. The length is
+/// committed too so inputs that only differ by padding get different roots.
+pub fn public_input_root<H: HashFn>(bytes: &[u8]) -> H::Digest {
+    let mut leaves = bytes
+        .chunks(PUBLIC_INPUT_CHUNK_SIZE)
+        .map(|chunk| H::hash(chunk.iter().copied()))
+        .collect::<Vec<_>>();
+    // pad to a tree with a power-of-two number of leaves
+    let num_leaves = leaves.len().next_power_of_two().max(2);
+    leaves.resize(num_leaves, H::hash([]));
+    let tree = MerkleTreeImpl::<HashedLeafConfig<H>>::new(leaves).unwrap();
+    H::merge_with_int(&tree.root(), bytes.len() as u64)
+}
+
 pub fn hash_rows<F: Field, H: ElementHashFn<F>>(matrix: &Matrix<F>) -> Vec<H::Digest> {
     let num_rows = matrix.num_rows();
     let mut row_hashes = vec![H::Digest::default(); num_rows];
@@ -435,6 +820,10 @@ pub fn hash_rows<F: Field, H: ElementHashFn<F>>(matrix: &Matrix<F>) -> Vec<H::Di
     row_hashes
 }
 
+/// Builds the internal nodes of a Merkle tree. Each thread builds a subtree
+/// over its own slice of leaves and the few nodes above the subtrees are
+/// hashed last. Together with [`hash_rows`] this hashes both the leaves and
+/// the internal nodes in parallel. Nodes are the same as the serial build.
 #[cfg(feature = "parallel")]
 pub fn build_merkle_nodes<C: MerkleTreeConfig>(leaves: &[C::Leaf]) -> Vec<C::Digest> {
     let n = leaves.len();
@@ -510,17 +899,22 @@ pub fn build_merkle_nodes<C: MerkleTreeConfig>(leaves: &[C::Leaf]) -> Vec<C::Dig
 #[cfg(test)]
 mod tests {
     use super::Error;
+    use super::FlatMatrixCommitment;
     use super::MatrixMerkleTree;
     use super::MatrixMerkleTreeImpl;
     use super::MerkleTree;
     use super::MerkleTreeConfig;
     use super::MerkleTreeImpl;
+    use super::public_input_root;
+    use super::PackedMatrixMerkleTree;
+    use super::TruncatedMatrixMerkleTree;
     use crate::hash::HashFn;
     use crate::hash::Sha256HashFn;
     use crate::utils::GpuAllocator;
     use crate::utils::SerdeOutput;
     use crate::Matrix;
     use ark_ff::MontFp as Fp;
+    use ark_serialize::CanonicalSerialize;
     use digest::Digest;
     use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
     use sha2::Sha256;
@@ -551,6 +945,112 @@ mod tests {
         MatrixMerkleTreeImpl::<Sha256HashFn>::verify_rows(&commitment, &row_ids, &rows, proof)
     }
 
+    #[test]
+    fn wider_leaves_give_shorter_paths() -> Result<(), Error> {
+        type NarrowTree = PackedMatrixMerkleTree<Sha256HashFn, 1>;
+        type WideTree = PackedMatrixMerkleTree<Sha256HashFn, 4>;
+        let column = (0..16).map(|i| Fp::from(i as u64)).collect::<Vec<_>>();
+        let matrix = Matrix::new(vec![column.to_vec_in(GpuAllocator)]);
+        let row_ids = [3, 9];
+        let rows = row_ids.map(|i| [column[i]]);
+        let narrow_tree = NarrowTree::from_matrix(&matrix);
+        let wide_tree = WideTree::from_matrix(&matrix);
+
+        let narrow_proof = MatrixMerkleTree::<Fp>::prove_rows(&narrow_tree, &row_ids)?;
+        let wide_proof = MatrixMerkleTree::<Fp>::prove_rows(&wide_tree, &row_ids)?;
+
+        assert_eq!(4, narrow_proof.view.height);
+        assert_eq!(2, wide_proof.view.height);
+        assert!(narrow_proof.row_hashes.is_empty());
+        assert_eq!(6, wide_proof.row_hashes.len());
+        NarrowTree::verify_rows(&narrow_tree.root(), &row_ids, &rows, narrow_proof)?;
+        WideTree::verify_rows(&wide_tree.root(), &row_ids, &rows, wide_proof)
+    }
+
+    #[test]
+    #[should_panic(expected = "can't pack 4 rows into leaves of 4 rows")]
+    fn packing_rows_into_a_single_leaf_panics() {
+        let column = (0..4).map(|i| Fp::from(i as u64)).collect::<Vec<_>>();
+        let matrix = Matrix::new(vec![column.to_vec_in(GpuAllocator)]);
+        let _ = PackedMatrixMerkleTree::<Sha256HashFn, 4>::from_matrix(&matrix);
+    }
+
+    #[test]
+    fn truncated_nodes_give_smaller_paths() -> Result<(), Error> {
+        type TruncatedTree = TruncatedMatrixMerkleTree<Sha256HashFn, 16>;
+        let column = (0..16).map(|i| Fp::from(i as u64)).collect::<Vec<_>>();
+        let matrix = Matrix::new(vec![column.to_vec_in(GpuAllocator)]);
+        let row_ids = [3, 9];
+        let rows = row_ids.map(|i| [column[i]]);
+        let tree = MatrixMerkleTreeImpl::<Sha256HashFn>::from_matrix(&matrix);
+        let truncated_tree = TruncatedTree::from_matrix(&matrix);
+
+        let proof = MatrixMerkleTree::<Fp>::prove_rows(&tree, &row_ids)?;
+        let truncated_proof = MatrixMerkleTree::<Fp>::prove_rows(&truncated_tree, &row_ids)?;
+
+        assert!(truncated_proof.compressed_size() < proof.compressed_size());
+        assert_eq!(64, TruncatedTree::security_level_bits());
+        TruncatedTree::verify_rows(&truncated_tree.root(), &row_ids, &rows, truncated_proof)
+    }
+
+    #[test]
+    fn flat_commitment_round_trips() -> Result<(), Error> {
+        type Commitment = FlatMatrixCommitment<Sha256HashFn>;
+        let column = (0..8).map(|i| Fp::from(i as u64)).collect::<Vec<_>>();
+        let matrix = Matrix::new(vec![column.to_vec_in(GpuAllocator)]);
+        let commitment = Commitment::from_matrix(&matrix);
+        let row_ids = [2, 5];
+        let rows = row_ids.map(|i| [column[i]]);
+
+        let proof = MatrixMerkleTree::<Fp>::prove_rows(&commitment, &row_ids)?;
+
+        Commitment::verify_rows(&commitment.root(), &row_ids, &rows, proof)
+    }
+
+    #[test]
+    fn tampered_row_fails_verification() -> Result<(), Error> {
+        let column = (0..8).map(|i| Fp::from(i as u64)).collect::<Vec<_>>();
+        let matrix = Matrix::new(vec![column.to_vec_in(GpuAllocator)]);
+        let tree = MatrixMerkleTreeImpl::<Sha256HashFn>::from_matrix(&matrix);
+        let row_ids = [2, 5];
+        let rows = [[column[2]], [Fp::from(9u8)]];
+
+        let proof = MatrixMerkleTree::<Fp>::prove_rows(&tree, &row_ids)?;
+        let result =
+            MatrixMerkleTreeImpl::<Sha256HashFn>::verify_rows(&tree.root(), &row_ids, &rows, proof);
+
+        assert!(matches!(result, Err(Error::LeafMismatch { i: 5 })));
+        Ok(())
+    }
+
+    #[test]
+    fn tampered_path_fails_verification() -> Result<(), Error> {
+        let leaves = vec![1u32, 2, 3, 4, 5, 6, 7, 8];
+        let tree = MerkleTreeImpl::<UnhashedLeafConfig>::new(leaves)?;
+        let i = 3;
+
+        let mut proof = tree.prove(&[i])?;
+        proof.nodes[0] = SerdeOutput::new(Sha256::digest(b"tampered"));
+        let result = MerkleTreeImpl::<UnhashedLeafConfig>::verify(&tree.root(), proof, &[i]);
+
+        assert!(matches!(result, Err(Error::InvalidProof)));
+        Ok(())
+    }
+
+    #[test]
+    fn public_input_root_changes_with_any_chunk() {
+        let input = (0..=255u8).cycle().take(10 * 1024).collect::<Vec<_>>();
+        let mut changed_input = input.clone();
+        changed_input[9000] ^= 1;
+        let truncated_input = &input[..input.len() - 1];
+
+        let root = public_input_root::<Sha256HashFn>(&input);
+
+        assert_eq!(root, public_input_root::<Sha256HashFn>(&input));
+        assert_ne!(root, public_input_root::<Sha256HashFn>(&changed_input));
+        assert_ne!(root, public_input_root::<Sha256HashFn>(truncated_input));
+    }
+
     #[test]
     fn verify_hashed_leaves() -> Result<(), Error> {
         let leaves = [1u32, 2, 3, 4, 5, 6, 7, 8];
@@ -580,6 +1080,26 @@ mod tests {
         MerkleTreeImpl::<UnhashedLeafConfig>::verify(&commitment, proof, &[i])
     }
 
+    #[test]
+    fn nodes_match_level_by_level_hashing() -> Result<(), Error> {
+        let leaves = (0..1 << 12).collect::<Vec<u32>>();
+        let tree = MerkleTreeImpl::<UnhashedLeafConfig>::new(leaves.clone())?;
+
+        let mut layer = leaves
+            .chunks(2)
+            .map(|pair| UnhashedLeafConfig::hash_leaves(0, &pair[0], &pair[1]))
+            .collect::<Vec<_>>();
+        while layer.len() > 1 {
+            layer = layer
+                .chunks(2)
+                .map(|pair| UnhashedLeafConfig::hash_nodes(0, &pair[0], &pair[1]))
+                .collect();
+        }
+
+        assert_eq!(layer[0], tree.root());
+        Ok(())
+    }
+
     struct HashedLeafConfig;
 
     impl MerkleTreeConfig for HashedLeafConfig {
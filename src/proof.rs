@@ -1,21 +1,49 @@
+use crate::challenges::Challenges;
+use crate::channel::TranscriptCursor;
+use crate::channel::TranscriptEvent;
+use crate::channel::TranscriptStep;
+use crate::composer::DeepCompositionCoeffs;
+use crate::fri::FriLayout;
 use crate::fri::FriProof;
 use crate::merkle::MerkleTree;
+use crate::random::draw_multiple;
 use crate::random::PublicCoin;
 use crate::stark::Stark;
 use crate::trace::Queries;
+use crate::trace::MIN_TRACE_LEN;
 use crate::utils::field_bits;
+use crate::utils::horner_evaluate;
+use crate::Air;
 use crate::ProofOptions;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use ark_ff::Field;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
 use ark_serialize::Valid;
+use core::iter::zip;
+use sha2::Digest as _;
+use sha2::Sha256;
+use snafu::Snafu;
 
 /// A proof generated by a mini-stark prover
 pub struct Proof<C: Stark> {
     pub options: ProofOptions,
     pub trace_len: usize,
+    /// Auxiliary data bound into the proof transcript (see [`Stark::aux_data`])
+    pub aux_data: Vec<C::Fp>,
+    /// Root of the Merkle tree over rows of the base trace LDE. The LDE is
+    /// evaluated over a coset that's disjoint from the trace domain so a
+    /// single trace cell (e.g. a checkpoint for a fraud proof) can't be opened
+    /// against this root. Expose checkpoints as public hints bound by boundary
+    /// constraints (see [`crate::segment::state_boundary_constraints`]) or
+    /// commit to them with a [`crate::disclosure::DisclosureTree`] and bind
+    /// its root to the proof through [`Stark::aux_data`].
     pub base_trace_commitment: C::Digest,
+    /// `None` if the AIR has no extension columns. Nothing about the extension
+    /// trace is committed to, opened or drawn for in that case.
     pub extension_trace_commitment: Option<C::Digest>,
-    pub composition_trace_commitment: C::Digest,
+    pub composition_trace_commitment: CompositionCommitment<C::Digest, C::Fq>,
     pub fri_proof: FriProof<C::Fq, C::Digest, C::MerkleTree>,
     pub pow_nonce: u64,
     pub trace_queries: Queries<C>,
@@ -28,6 +56,7 @@ impl<C: Stark> Clone for Proof<C> {
         Self {
             options: self.options,
             trace_len: self.trace_len,
+            aux_data: self.aux_data.clone(),
             base_trace_commitment: self.base_trace_commitment.clone(),
             extension_trace_commitment: self.extension_trace_commitment.clone(),
             composition_trace_commitment: self.composition_trace_commitment.clone(),
@@ -48,6 +77,7 @@ impl<C: Stark> CanonicalSerialize for Proof<C> {
     ) -> Result<(), ark_serialize::SerializationError> {
         self.options.serialize_with_mode(&mut writer, compress)?;
         self.trace_len.serialize_with_mode(&mut writer, compress)?;
+        self.aux_data.serialize_with_mode(&mut writer, compress)?;
         self.base_trace_commitment
             .serialize_with_mode(&mut writer, compress)?;
         self.extension_trace_commitment
@@ -68,6 +98,7 @@ impl<C: Stark> CanonicalSerialize for Proof<C> {
     fn serialized_size(&self, compress: ark_serialize::Compress) -> usize {
         self.options.serialized_size(compress)
             + self.trace_len.serialized_size(compress)
+            + self.aux_data.serialized_size(compress)
             + self.base_trace_commitment.serialized_size(compress)
             + self.extension_trace_commitment.serialized_size(compress)
             + self.composition_trace_commitment.serialized_size(compress)
@@ -80,8 +111,16 @@ impl<C: Stark> CanonicalSerialize for Proof<C> {
 }
 
 impl<C: Stark> Valid for Proof<C> {
+    /// Rejects trace lengths whose LDE domain can't be indexed. The options
+    /// are checked when they're read.
     #[inline]
     fn check(&self) -> Result<(), ark_serialize::SerializationError> {
+        let lde_blowup_factor = usize::from(self.options.lde_blowup_factor);
+        if !self.trace_len.is_power_of_two()
+            || self.trace_len.checked_mul(lde_blowup_factor).is_none()
+        {
+            return Err(ark_serialize::SerializationError::InvalidData);
+        }
         Ok(())
     }
 }
@@ -92,9 +131,10 @@ impl<C: Stark> CanonicalDeserialize for Proof<C> {
         compress: ark_serialize::Compress,
         validate: ark_serialize::Validate,
     ) -> Result<Self, ark_serialize::SerializationError> {
-        Ok(Self {
+        let proof = Self {
             options: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
             trace_len: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+            aux_data: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
             base_trace_commitment: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
             extension_trace_commitment: <_>::deserialize_with_mode(
                 &mut reader,
@@ -115,7 +155,79 @@ impl<C: Stark> CanonicalDeserialize for Proof<C> {
                 compress,
                 validate,
             )?,
-        })
+        };
+        if validate == ark_serialize::Validate::Yes {
+            proof.check()?;
+        }
+        Ok(proof)
+    }
+}
+
+/// How a proof binds the composition polynomial
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompositionCommitment<D, F> {
+    /// Merkle root of the composition trace LDE
+    Root(D),
+    /// Composition polynomial coefficients sent in the clear. The verifier
+    /// evaluates these itself at the query positions.
+    Coeffs(Vec<F>),
+}
+
+impl<D: CanonicalSerialize, F: CanonicalSerialize> CanonicalSerialize
+    for CompositionCommitment<D, F>
+{
+    fn serialize_with_mode<W: ark_serialize::Write>(
+        &self,
+        mut writer: W,
+        compress: ark_serialize::Compress,
+    ) -> Result<(), ark_serialize::SerializationError> {
+        match self {
+            Self::Root(root) => {
+                0u8.serialize_with_mode(&mut writer, compress)?;
+                root.serialize_with_mode(&mut writer, compress)
+            }
+            Self::Coeffs(coeffs) => {
+                1u8.serialize_with_mode(&mut writer, compress)?;
+                coeffs.serialize_with_mode(&mut writer, compress)
+            }
+        }
+    }
+
+    fn serialized_size(&self, compress: ark_serialize::Compress) -> usize {
+        1 + match self {
+            Self::Root(root) => root.serialized_size(compress),
+            Self::Coeffs(coeffs) => coeffs.serialized_size(compress),
+        }
+    }
+}
+
+impl<D: Valid, F: Valid> Valid for CompositionCommitment<D, F> {
+    fn check(&self) -> Result<(), ark_serialize::SerializationError> {
+        Ok(())
+    }
+}
+
+impl<D: CanonicalDeserialize, F: CanonicalDeserialize> CanonicalDeserialize
+    for CompositionCommitment<D, F>
+{
+    fn deserialize_with_mode<R: ark_serialize::Read>(
+        mut reader: R,
+        compress: ark_serialize::Compress,
+        validate: ark_serialize::Validate,
+    ) -> Result<Self, ark_serialize::SerializationError> {
+        match u8::deserialize_with_mode(&mut reader, compress, validate)? {
+            0 => Ok(Self::Root(<_>::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?)),
+            1 => Ok(Self::Coeffs(<_>::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?)),
+            _ => Err(ark_serialize::SerializationError::InvalidData),
+        }
     }
 }
 
@@ -124,25 +236,532 @@ impl<C: Stark> Proof<C> {
     // also https://github.com/starkware-libs/ethSTARK/blob/master/README.md#7-Measuring-Security
     // https://eprint.iacr.org/2020/654.pdf section 7.2 for proven security
     pub fn security_level_bits(&self) -> u32 {
-        let field_security = {
-            let lde_domain_size = self.trace_len * self.options.lde_blowup_factor as usize;
-            let extension_field_bits = field_bits::<C::Fq>();
-            extension_field_bits - lde_domain_size.ilog2()
-        };
+        security_level_bits::<C>(self.options, self.trace_len)
+    }
+
+    /// Checks the shape of the proof against `options` without the AIR or any
+    /// field arithmetic. The options and trace length must be valid (see
+    /// [`ProofOptions::is_valid`]), the FRI layers must match the number the
+    /// LDE domain folds into, each layer must open whole cosets for at most
+    /// `num_queries` queries and the remainder must have the expected number
+    /// of coefficients. Passing this doesn't mean the proof is valid. It only
+    /// filters out proofs that can't be.
+    pub fn check_well_formed(&self, options: ProofOptions) -> Result<(), MalformedProof> {
+        if self.options != options {
+            return Err(MalformedProof::OptionsMismatch);
+        }
+        if !options.is_valid() {
+            return Err(MalformedProof::InvalidOptions);
+        }
+        let trace_len = self.trace_len;
+        if !trace_len.is_power_of_two() || trace_len < MIN_TRACE_LEN {
+            return Err(MalformedProof::InvalidTraceLength { trace_len });
+        }
+
+        let fri_options = options.into_fri_options();
+        let lde_blowup_factor = options.lde_blowup_factor;
+        let lde_domain_size = trace_len
+            .checked_mul(usize::from(lde_blowup_factor))
+            .ok_or(MalformedProof::DomainTooLarge {
+                trace_len,
+                lde_blowup_factor,
+            })?;
+        let expected = fri_options.num_layers(lde_domain_size);
+        let actual = self.fri_proof.layers.len();
+        if expected != actual {
+            return Err(MalformedProof::FriLayerCountMismatch { expected, actual });
+        }
 
-        let fri_query_security = {
-            let grinding_factor = u32::from(self.options.grinding_factor);
-            let security_per_query = self.options.lde_blowup_factor.ilog2();
-            let num_fri_quiries = u32::from(self.options.num_queries);
-            security_per_query * num_fri_quiries + grinding_factor
+        let folding_factor = usize::from(options.fri_folding_factor);
+        let max_num_values = usize::from(options.num_queries) * folding_factor;
+        for (layer, layer_proof) in self.fri_proof.layers.iter().enumerate() {
+            let num_values = layer_proof.flattenend_rows.len();
+            if num_values == 0 || num_values % folding_factor != 0 || num_values > max_num_values {
+                return Err(MalformedProof::InvalidFriLayerOpening { layer, num_values });
+            }
+        }
+
+        let remainder_size = fri_options.remainder_size(lde_domain_size);
+        let expected = remainder_size / usize::from(options.lde_blowup_factor);
+        let actual = self.fri_proof.remainder_coeffs.len();
+        if expected != actual {
+            return Err(MalformedProof::RemainderSizeMismatch { expected, actual });
+        }
+        Ok(())
+    }
+
+    /// Serializes the proof after a [`ProofHeader`] so its parameters can be
+    /// read from the bytes alone
+    pub fn to_self_describing_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        ProofHeader::new(self)
+            .serialize_compressed(&mut bytes)
+            .unwrap();
+        self.serialize_compressed(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Deserializes a proof written by [`Proof::to_self_describing_bytes`].
+    /// Fails if the header doesn't describe the proof that follows it.
+    pub fn from_self_describing_bytes(
+        mut bytes: &[u8],
+    ) -> Result<Self, ark_serialize::SerializationError> {
+        let header = ProofHeader::deserialize_compressed(&mut bytes)?;
+        let proof = Self::deserialize_compressed(&mut bytes)?;
+        if header != ProofHeader::new(&proof) {
+            return Err(ark_serialize::SerializationError::InvalidData);
+        }
+        Ok(proof)
+    }
+
+    /// Re-derives the out-of-domain point `z` by replaying the transcript of a
+    /// proof generated for `claim` without a domain separator
+    pub fn ood_point(&self, claim: &C) -> C::Fq {
+        self.ood_evals(claim).z
+    }
+
+    /// Re-derives the out-of-domain point and the randomness the constraint
+    /// composition depends on and pairs them with the proof's out-of-domain
+    /// evaluations. Nothing is checked so this also works on invalid proofs.
+    pub fn ood_evals(&self, claim: &C) -> OodEvals<C::Fq> {
+        let air = Air::new(self.trace_len, claim.get_public_inputs(), self.options);
+        let transcript = self.replay_transcript(claim, &air, None, None);
+        let trace = zip(
+            air.trace_arguments(),
+            self.execution_trace_ood_evals.iter().copied(),
+        );
+        OodEvals {
+            z: transcript.z,
+            challenges: Challenges::new(transcript.challenges),
+            composition_coeffs: transcript.composition_coeffs,
+            trace: trace.collect(),
+            composition: self.composition_trace_ood_evals.clone(),
+        }
+    }
+
+    /// Replays the Fiat-Shamir transcript of a proof generated for `claim`
+    /// without a domain separator. Every value absorbed or drawn is returned
+    /// in order so a third party (e.g. an arbiter settling a dispute) can
+    /// recompute any challenge from the proof alone. Nothing is checked so
+    /// this also works on invalid proofs.
+    pub fn transcript_log(&self, claim: &C) -> Vec<TranscriptEvent<C::Fq, C::Digest>> {
+        let air = Air::new(self.trace_len, claim.get_public_inputs(), self.options);
+        let mut log = Vec::new();
+        self.replay_transcript(claim, &air, None, Some(&mut log));
+        log
+    }
+
+    /// Replays the Fiat-Shamir transcript of the proof. This is the replay
+    /// behind the verifier, [`Self::ood_evals`] and [`Self::transcript_log`].
+    /// Every value absorbed or drawn is appended to `log` if there is one.
+    /// Nothing is checked so this also works on invalid proofs.
+    pub(crate) fn replay_transcript(
+        &self,
+        claim: &C,
+        air: &Air<C::AirConfig>,
+        domain_separator: Option<&[u8]>,
+        mut log: Option<&mut Vec<TranscriptEvent<C::Fq, C::Digest>>>,
+    ) -> ReplayedTranscript<C::Fq> {
+        use TranscriptEvent::*;
+        // events are built lazily so replaying without a log doesn't clone
+        let mut record = |event: &dyn Fn() -> TranscriptEvent<C::Fq, C::Digest>| {
+            if let Some(log) = log.as_mut() {
+                log.push(event());
+            }
         };
+        let mut public_coin = claim.gen_public_coin(air);
+        let mut transcript = TranscriptCursor::default();
+        if let Some(domain_separator) = domain_separator {
+            transcript.advance(TranscriptStep::DomainSeparator);
+            public_coin.reseed_with_bytes(domain_separator);
+        }
+
+        if !self.aux_data.is_empty() {
+            transcript.advance(TranscriptStep::AuxData);
+            let values = Vec::from_iter(self.aux_data.iter().copied().map(C::Fq::from));
+            public_coin.reseed_with_field_elements(&values);
+            record(&|| AbsorbElements {
+                step: TranscriptStep::AuxData,
+                values: values.clone(),
+            });
+        }
+
+        transcript.advance(TranscriptStep::BaseTrace);
+        public_coin.reseed_with_digest(&self.base_trace_commitment);
+        record(&|| AbsorbDigest {
+            step: TranscriptStep::BaseTrace,
+            digest: self.base_trace_commitment.clone(),
+        });
+        if let Some(beacon) = claim.randomness_beacon() {
+            transcript.advance(TranscriptStep::RandomnessBeacon);
+            public_coin.reseed_with_field_elements(&[beacon]);
+            record(&|| AbsorbElements {
+                step: TranscriptStep::RandomnessBeacon,
+                values: vec![beacon],
+            });
+        }
+        transcript.advance(TranscriptStep::Challenges);
+        let challenges = draw_multiple(&mut public_coin, air.num_challenges());
+        record(&|| Draw {
+            step: TranscriptStep::Challenges,
+            values: challenges.clone(),
+        });
+
+        if let Some(commitment) = &self.extension_trace_commitment {
+            transcript.advance(TranscriptStep::ExtensionTrace);
+            public_coin.reseed_with_digest(commitment);
+            record(&|| AbsorbDigest {
+                step: TranscriptStep::ExtensionTrace,
+                digest: commitment.clone(),
+            });
+        }
+
+        transcript.advance(TranscriptStep::CompositionCoeffs);
+        let num_composition_coeffs = air.num_composition_constraint_coeffs();
+        let composition_coeffs = draw_multiple(&mut public_coin, num_composition_coeffs);
+        record(&|| Draw {
+            step: TranscriptStep::CompositionCoeffs,
+            values: composition_coeffs.clone(),
+        });
+        transcript.advance(TranscriptStep::CompositionTrace);
+        match &self.composition_trace_commitment {
+            CompositionCommitment::Root(root) => {
+                public_coin.reseed_with_digest(root);
+                record(&|| AbsorbDigest {
+                    step: TranscriptStep::CompositionTrace,
+                    digest: root.clone(),
+                });
+            }
+            CompositionCommitment::Coeffs(coeffs) => {
+                public_coin.reseed_with_field_element_vector(coeffs);
+                record(&|| AbsorbElements {
+                    step: TranscriptStep::CompositionTrace,
+                    values: coeffs.clone(),
+                });
+            }
+        }
+
+        transcript.advance(TranscriptStep::OodPoint);
+        let z = claim.gen_ood_point(&mut public_coin, air);
+        record(&|| Draw {
+            step: TranscriptStep::OodPoint,
+            values: vec![z],
+        });
+        transcript.advance(TranscriptStep::OodEvals);
+        let ood_evals = [
+            &*self.execution_trace_ood_evals,
+            &self.composition_trace_ood_evals,
+        ]
+        .concat();
+        public_coin.reseed_with_field_elements(&ood_evals);
+        record(&|| AbsorbElements {
+            step: TranscriptStep::OodEvals,
+            values: ood_evals.clone(),
+        });
 
-        let merkle_tree_security = C::MerkleTree::security_level_bits();
-        let public_coin_security = C::PublicCoin::security_level_bits();
+        transcript.advance(TranscriptStep::DeepCoeffs);
+        let deep_coeffs = claim.gen_deep_coeffs(&mut public_coin, air);
+        record(&|| Draw {
+            step: TranscriptStep::DeepCoeffs,
+            values: [
+                &*deep_coeffs.execution_trace,
+                &deep_coeffs.composition_trace,
+                &[deep_coeffs.degree.0, deep_coeffs.degree.1],
+            ]
+            .concat(),
+        });
+
+        transcript.advance(TranscriptStep::Fri);
+        let fri_alphas = self.fri_proof.folding_challenges(&mut public_coin);
+        for (layer, &alpha) in zip(&self.fri_proof.layers, &fri_alphas) {
+            record(&|| AbsorbDigest {
+                step: TranscriptStep::Fri,
+                digest: layer.commitment.clone(),
+            });
+            record(&|| Draw {
+                step: TranscriptStep::Fri,
+                values: vec![alpha],
+            });
+        }
+        let remainder_coeffs = &self.fri_proof.remainder_coeffs;
+        public_coin.reseed_with_field_element_vector(remainder_coeffs);
+        record(&|| AbsorbElements {
+            step: TranscriptStep::Fri,
+            values: remainder_coeffs.clone(),
+        });
 
-        field_security
-            .min(fri_query_security)
-            .min(merkle_tree_security)
-            .min(public_coin_security)
+        let ProofOptions {
+            grinding_factor,
+            num_queries,
+            ..
+        } = self.options;
+        let is_proof_of_work_valid = grinding_factor == 0
+            || public_coin.verify_proof_of_work(grinding_factor, self.pow_nonce);
+        // positions are only ever drawn from the transcript. The proof doesn't
+        // carry them so openings at positions of the prover's choosing can't
+        // resolve
+        transcript.advance(TranscriptStep::QueryPositions);
+        let query_positions = Vec::from_iter(public_coin.draw_queries_after_grinding(
+            grinding_factor,
+            self.pow_nonce,
+            num_queries.into(),
+            air.trace_len() * air.lde_blowup_factor(),
+        ));
+        record(&|| DrawQueries {
+            nonce: self.pow_nonce,
+            positions: query_positions.clone(),
+        });
+
+        ReplayedTranscript {
+            challenges,
+            composition_coeffs,
+            z,
+            deep_coeffs,
+            fri_alphas,
+            is_proof_of_work_valid,
+            query_positions,
+        }
     }
 }
+
+/// Values drawn from the public coin by [`Proof::replay_transcript`]
+pub(crate) struct ReplayedTranscript<F> {
+    pub challenges: Vec<F>,
+    pub composition_coeffs: Vec<F>,
+    pub z: F,
+    pub deep_coeffs: DeepCompositionCoeffs<F>,
+    pub fri_alphas: Vec<F>,
+    /// Whether the nonce passes the proof of work check. Always `true` without
+    /// grinding
+    pub is_proof_of_work_valid: bool,
+    pub query_positions: Vec<usize>,
+}
+
+/// Parameters of a proof that can be read without knowing the [`Stark`] it was
+/// generated for (see [`Proof::to_self_describing_bytes`]). The constraints
+/// still have to come from the AIR.
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ProofHeader {
+    /// Limbs of the base field characteristic, least significant first
+    pub field_characteristic: Vec<u64>,
+    pub extension_degree: u64,
+    /// Type name of the Merkle tree which includes the hash function. Like
+    /// [`Stark::air_id`] it isn't guaranteed to be stable between compiler
+    /// versions.
+    pub hash_id: Vec<u8>,
+    pub options: ProofOptions,
+    pub trace_len: usize,
+    pub fri_layout: FriLayout,
+}
+
+impl ProofHeader {
+    /// # Panics
+    ///
+    /// Panics if the proof's options aren't valid (see
+    /// [`ProofOptions::is_valid`]). Options of deserialized proofs are always
+    /// valid.
+    pub fn new<C: Stark>(proof: &Proof<C>) -> Self {
+        assert!(proof.options.is_valid(), "invalid proof options");
+        let lde_blowup_factor = usize::from(proof.options.lde_blowup_factor);
+        let lde_domain_size = proof.trace_len.saturating_mul(lde_blowup_factor);
+        Self {
+            field_characteristic: C::Fp::characteristic().to_vec(),
+            extension_degree: C::Fq::extension_degree(),
+            hash_id: core::any::type_name::<C::MerkleTree>().as_bytes().to_vec(),
+            options: proof.options,
+            trace_len: proof.trace_len,
+            fri_layout: proof.options.into_fri_options().layout(lde_domain_size),
+        }
+    }
+}
+
+/// Small record of a proving event. A service can store receipts for logging
+/// and indexing and fetch full proofs on demand. See
+/// [`Stark::prove_with_receipt`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Receipt {
+    /// See [`Stark::statement_hash`]
+    pub statement_hash: [u8; 32],
+    /// SHA-256 of the compressed proof bytes. This identifies the proof rather
+    /// than the proving event: without randomizers the prover is deterministic
+    /// so proving a statement again with the same options gives the same hash.
+    pub proof_hash: [u8; 32],
+    pub options: ProofOptions,
+    /// Seconds since the Unix epoch when the receipt was made. The clock is
+    /// only read with the `std` feature. Without it this is `0`.
+    pub timestamp: u64,
+}
+
+impl Receipt {
+    pub fn new<C: Stark>(claim: &C, proof: &Proof<C>) -> Self {
+        let mut proof_bytes = Vec::new();
+        proof.serialize_compressed(&mut proof_bytes).unwrap();
+        #[cfg(feature = "std")]
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        #[cfg(not(feature = "std"))]
+        let timestamp = 0;
+        Self {
+            statement_hash: claim.statement_hash(),
+            proof_hash: Sha256::digest(proof_bytes).into(),
+            options: proof.options,
+            timestamp,
+        }
+    }
+}
+
+/// Returns the conjectured security level in bits of proofs for a trace of
+/// length `trace_len` generated with `options` (see
+/// [`Proof::security_level_bits`])
+pub fn security_level_bits<C: Stark>(options: ProofOptions, trace_len: usize) -> u32 {
+    // domains too large to index have no field security left
+    let field_security = trace_len
+        .checked_mul(usize::from(options.lde_blowup_factor))
+        .and_then(|lde_domain_size| lde_domain_size.checked_ilog2())
+        .map_or(0, |log_domain_size| {
+            field_bits::<C::Fq>().saturating_sub(log_domain_size)
+        });
+
+    let fri_query_security = options.fri_query_security_bits();
+
+    let merkle_tree_security = C::MerkleTree::security_level_bits();
+    let public_coin_security = C::PublicCoin::security_level_bits();
+
+    field_security
+        .min(fri_query_security)
+        .min(merkle_tree_security)
+        .min(public_coin_security)
+}
+
+/// Structural defects found by [`Proof::check_well_formed`]
+#[derive(Debug, Snafu)]
+pub enum MalformedProof {
+    #[snafu(display("proof was generated with different options"))]
+    OptionsMismatch,
+    #[snafu(display("proof options are out of range or use an unsupported FRI folding factor"))]
+    InvalidOptions,
+    #[snafu(display("trace length {trace_len} isn't a power of two of at least {MIN_TRACE_LEN}"))]
+    InvalidTraceLength { trace_len: usize },
+    #[snafu(display("LDE domain of {trace_len} rows blown up {lde_blowup_factor}x overflows"))]
+    DomainTooLarge {
+        trace_len: usize,
+        lde_blowup_factor: u8,
+    },
+    #[snafu(display("expected {expected} FRI layers but the proof has {actual}"))]
+    FriLayerCountMismatch { expected: usize, actual: usize },
+    #[snafu(display("FRI layer {layer} opens {num_values} values which isn't a valid opening"))]
+    InvalidFriLayerOpening { layer: usize, num_values: usize },
+    #[snafu(display("expected {expected} FRI remainder coefficients but the proof has {actual}"))]
+    RemainderSizeMismatch { expected: usize, actual: usize },
+}
+
+/// Out-of-domain evaluations of a proof along with the transcript randomness
+/// needed to check them (see [`Proof::ood_evals`])
+#[derive(Debug, Clone)]
+pub struct OodEvals<F: Field> {
+    /// The out-of-domain point
+    pub z: F,
+    pub challenges: Challenges<F>,
+    pub composition_coeffs: Vec<F>,
+    /// Execution trace evaluations at `z⋅ω^offset` keyed by `(column, offset)`
+    pub trace: BTreeMap<(usize, isize), F>,
+    /// Composition trace column evaluations at `z^num_columns`
+    pub composition: Vec<F>,
+}
+
+impl<F: Field> OodEvals<F> {
+    /// Returns the composition polynomial's value at `z` according to the
+    /// composition trace evaluations. For a valid proof this equals
+    /// [`ood_constraint_evaluation`](crate::verifier::ood_constraint_evaluation)
+    /// evaluated on the trace evaluations.
+    pub fn composition_value(&self) -> F {
+        horner_evaluate(&self.composition, &self.z)
+    }
+}
+
+/// Requirements a protocol places on the proofs it accepts
+///
+/// [`ProofPolicy::check`] only reads the options and shape of a proof so a
+/// gateway can reject under-parameterized proofs before the expensive
+/// [`Stark::verify`]. Passing the policy doesn't mean the proof is valid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ProofPolicy {
+    pub min_queries: u8,
+    pub min_blowup_factor: u8,
+    pub min_grinding_factor: u8,
+    /// Maximum size in bytes of the compressed proof
+    pub max_proof_size: Option<usize>,
+}
+
+impl ProofPolicy {
+    /// Checks the proof's options against the policy and that the proof is
+    /// well formed (see [`Proof::check_well_formed`]). Out of range options
+    /// are rejected before anything is derived from them.
+    pub fn check<C: Stark>(&self, proof: &Proof<C>) -> Result<(), PolicyViolation> {
+        if !proof.options.is_valid() {
+            return Err(PolicyViolation::Malformed {
+                source: MalformedProof::InvalidOptions,
+            });
+        }
+        let ProofOptions {
+            num_queries,
+            lde_blowup_factor,
+            grinding_factor,
+            ..
+        } = proof.options;
+        if num_queries < self.min_queries {
+            return Err(PolicyViolation::InsufficientQueries {
+                num_queries,
+                min_queries: self.min_queries,
+            });
+        }
+        if lde_blowup_factor < self.min_blowup_factor {
+            return Err(PolicyViolation::InsufficientBlowup {
+                lde_blowup_factor,
+                min_blowup_factor: self.min_blowup_factor,
+            });
+        }
+        if grinding_factor < self.min_grinding_factor {
+            return Err(PolicyViolation::InsufficientGrinding {
+                grinding_factor,
+                min_grinding_factor: self.min_grinding_factor,
+            });
+        }
+        if let Some(max_size) = self.max_proof_size {
+            let size = proof.compressed_size();
+            if size > max_size {
+                return Err(PolicyViolation::ProofTooLarge { size, max_size });
+            }
+        }
+        proof
+            .check_well_formed(proof.options)
+            .map_err(|source| PolicyViolation::Malformed { source })
+    }
+}
+
+/// Requirement of a [`ProofPolicy`] that a proof doesn't meet
+#[derive(Debug, Snafu)]
+pub enum PolicyViolation {
+    #[snafu(display("proof has {num_queries} queries but at least {min_queries} are required"))]
+    InsufficientQueries { num_queries: u8, min_queries: u8 },
+    #[snafu(display(
+        "proof has blowup factor {lde_blowup_factor} but at least {min_blowup_factor} is required"
+    ))]
+    InsufficientBlowup {
+        lde_blowup_factor: u8,
+        min_blowup_factor: u8,
+    },
+    #[snafu(display(
+        "proof has grinding factor {grinding_factor} but at least {min_grinding_factor} is \
+         required"
+    ))]
+    InsufficientGrinding {
+        grinding_factor: u8,
+        min_grinding_factor: u8,
+    },
+    #[snafu(display("proof is {size} bytes but at most {max_size} are allowed"))]
+    ProofTooLarge { size: usize, max_size: usize },
+    #[snafu(display("proof is malformed: {source}"))]
+    Malformed { source: MalformedProof },
+}
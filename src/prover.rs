@@ -1,195 +1,818 @@
+use crate::air::lde_domain_size;
 use crate::air::AirConfig;
+use crate::air::CompiledAir;
 use crate::challenges::Challenges;
 use crate::channel::ProverChannel;
+use crate::channel::TranscriptStep;
 use crate::composer::DeepPolyComposer;
 use crate::fri::FriProver;
+use crate::hints::Hints;
+use crate::merkle;
 use crate::merkle::MatrixMerkleTree;
 use crate::merkle::MerkleTree;
+use crate::proof::security_level_bits;
 use crate::random::draw_multiple;
 use crate::stark::Stark;
 use crate::trace::Queries;
+use crate::trace::TraceError;
 use crate::utils::GpuAllocator;
 use crate::utils::GpuVec;
 use crate::Air;
 use crate::Matrix;
 use crate::Proof;
 use crate::ProofOptions;
+use crate::SecurityPreset;
 use crate::Trace;
 use alloc::vec::Vec;
 use ark_ff::Field;
 use ark_poly::EvaluationDomain;
 use ministark_gpu::utils::bit_reverse;
+#[cfg(debug_assertions)]
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
 
-pub struct Instant{}
+/// Proving with options below this conjectured security level in bits emits a
+/// [`tracing`] warning. Weaker options are still allowed e.g. for fast tests.
+pub const MIN_RECOMMENDED_SECURITY_BITS: u32 = 80;
+
+pub struct Instant {
+    #[cfg(feature = "std")]
+    start: std::time::Instant,
+}
 
 impl Instant {
     pub fn now() -> Self {
-        Instant{}
+        Instant {
+            #[cfg(feature = "std")]
+            start: std::time::Instant::now(),
+        }
     }
     pub fn elapsed(&self) -> &str {
         " [UNKNOWN] "
     }
+
+    /// Nanoseconds since [`Self::now`]. Always `0` without the `std` feature.
+    pub fn elapsed_ns(&self) -> u64 {
+        #[cfg(feature = "std")]
+        return self
+            .start
+            .elapsed()
+            .as_nanos()
+            .try_into()
+            .unwrap_or(u64::MAX);
+        #[cfg(not(feature = "std"))]
+        return 0;
+    }
+}
+
+/// Time spent in each stage of proof generation in nanoseconds
+///
+/// The clock is only read with the `std` feature. Without it every field is
+/// `0`.
+#[derive(Debug, Clone, Default)]
+pub struct ProveStats {
+    pub base_trace_ns: u64,
+    pub extension_ns: u64,
+    pub constraint_eval_ns: u64,
+    pub composition_ns: u64,
+    pub deep_ns: u64,
+    pub fri_ns: u64,
+    pub total_ns: u64,
+}
+
+/// Proves `claim` at the security level of `preset` with options chosen by
+/// [`ProofOptions::recommended`]. The trace is validated as with
+/// [`Stark::prove`] which should be used for control over the options.
+///
+/// # Errors
+///
+/// Returns [`ProvingError::TraceTooShort`] if the trace has fewer rows than
+/// [`Air::min_trace_len`] for the chosen options
+pub fn prove<S: Stark>(
+    claim: &S,
+    witness: S::Witness,
+    preset: SecurityPreset,
+) -> Result<Proof<S>, ProvingError> {
+    let start = Instant::now();
+    let trace = claim.generate_trace(witness);
+    let options = ProofOptions::recommended(preset);
+    let min_trace_len = Air::<S::AirConfig>::min_trace_len(options);
+    if trace.len() < min_trace_len {
+        return Err(ProvingError::TraceTooShort {
+            trace_len: trace.len(),
+            min_trace_len,
+        });
+    }
+    prove_generated_trace(
+        claim,
+        options,
+        trace,
+        start,
+        None,
+        |trace, challenges, _| trace.build_extension_columns(challenges),
+    )
+    .map(|(proof, _)| proof)
 }
 
-#[allow(clippy::too_many_lines)]
 pub fn default_prove<S: Stark>(
     this: &S,
     options: ProofOptions,
     witness: S::Witness,
+    domain_separator: Option<&[u8]>,
 ) -> Result<Proof<S>, ProvingError> {
-    let now = Instant::now();
+    default_prove_with_stats(this, options, witness, domain_separator).map(|(proof, _)| proof)
+}
+
+/// Same as [`default_prove`] but also returns the time spent in each stage
+pub fn default_prove_with_stats<S: Stark>(
+    this: &S,
+    options: ProofOptions,
+    witness: S::Witness,
+    domain_separator: Option<&[u8]>,
+) -> Result<(Proof<S>, ProveStats), ProvingError> {
+    prove_with_builder(
+        this,
+        options,
+        witness,
+        domain_separator,
+        |trace, challenges, _| trace.build_extension_columns(challenges),
+    )
+}
+
+/// Same as [`default_prove`] but the extension trace is built by
+/// `build_extension` rather than [`Trace::build_extension_columns`].
+///
+/// The builder is called after the base trace commitment has been absorbed
+/// into the transcript. `challenges` are the first [`Air::num_challenges`]
+/// draws from the public coin at that point and `hints` are derived from them
+/// with [`AirConfig::gen_hints`]. Since these values are Fiat-Shamir dependent
+/// the extension trace can only be computed once they are known.
+pub fn default_prove_with_extension<S: Stark>(
+    this: &S,
+    options: ProofOptions,
+    witness: S::Witness,
+    domain_separator: Option<&[u8]>,
+    build_extension: impl FnOnce(&S::Trace, &Challenges<S::Fq>, &Hints<S::Fq>) -> Option<Matrix<S::Fq>>,
+) -> Result<Proof<S>, ProvingError> {
+    prove_with_builder(this, options, witness, domain_separator, build_extension)
+        .map(|(proof, _)| proof)
+}
+
+fn prove_with_builder<S: Stark>(
+    this: &S,
+    options: ProofOptions,
+    witness: S::Witness,
+    domain_separator: Option<&[u8]>,
+    build_extension: impl FnOnce(&S::Trace, &Challenges<S::Fq>, &Hints<S::Fq>) -> Option<Matrix<S::Fq>>,
+) -> Result<(Proof<S>, ProveStats), ProvingError> {
+    let start = Instant::now();
     let trace = this.generate_trace(witness);
+    prove_generated_trace(
+        this,
+        options,
+        trace,
+        start,
+        domain_separator,
+        build_extension,
+    )
+}
+
+/// Proves a trace generated at `start` by [`Stark::generate_trace`]
+fn prove_generated_trace<S: Stark>(
+    this: &S,
+    options: ProofOptions,
+    trace: S::Trace,
+    start: Instant,
+    domain_separator: Option<&[u8]>,
+    build_extension: impl FnOnce(&S::Trace, &Challenges<S::Fq>, &Hints<S::Fq>) -> Option<Matrix<S::Fq>>,
+) -> Result<(Proof<S>, ProveStats), ProvingError> {
+    let public_inputs = this.get_public_inputs();
+    trace
+        .validate(S::AirConfig::num_base_columns(&public_inputs))
+        .map_err(|source| ProvingError::InvalidTrace { source })?;
+    if lde_domain_size::<S::AirConfig>(trace.len(), options).is_none() {
+        return Err(ProvingError::DomainTooLarge {
+            trace_len: trace.len(),
+            lde_blowup_factor: options.lde_blowup_factor.into(),
+        });
+    }
     println!(
         "Generated execution trace (cols={}, rows={}) in {:.0?}",
         trace.base_columns().num_cols(),
         trace.base_columns().num_rows(),
-        now.elapsed(),
+        start.elapsed(),
     );
 
     let now = Instant::now();
-    let air = Air::new(trace.len(), this.get_public_inputs(), options);
-    let public_coin = this.gen_public_coin(&air);
-    let mut channel = ProverChannel::<S>::new(&air, public_coin);
+    let context = ProverContext::new(trace.len(), public_inputs, options);
     println!("Init air: {:?}", now.elapsed());
 
-    let now = Instant::now();
-    let trace_xs = air.trace_domain();
-    let lde_xs = air.lde_domain();
-    let base_trace = trace.base_columns();
-    assert_eq!(S::AirConfig::NUM_BASE_COLUMNS, base_trace.num_cols());
-    let base_trace_polys = base_trace.interpolate(trace_xs);
-    let mut base_trace_lde = base_trace_polys.bit_reversed_evaluate(lde_xs);
-    let base_trace_tree = S::MerkleTree::from_matrix(&base_trace_lde);
-    println!("Base trace commitment: {:?}", now.elapsed());
-
-    channel.commit_base_trace(base_trace_tree.root());
-    let num_challenges = air.num_challenges();
-    let challenges = Challenges::new(draw_multiple(&mut channel.public_coin, num_challenges));
-    let hints = air.gen_hints(&challenges);
+    let (proof, mut stats) = context.prove_trace(this, trace, domain_separator, build_extension)?;
+    stats.total_ns = start.elapsed_ns();
+    Ok((proof, stats))
+}
 
-    let now = Instant::now();
-    let extension_trace = trace.build_extension_columns(&challenges);
-    let num_extension_cols = extension_trace.as_ref().map_or(0, Matrix::num_cols);
-    assert_eq!(S::AirConfig::NUM_EXTENSION_COLUMNS, num_extension_cols);
-    let extension_trace_polys = extension_trace.as_ref().map(|t| t.interpolate(trace_xs));
-    let mut extension_trace_lde = extension_trace_polys
-        .as_ref()
-        .map(|p| p.bit_reversed_evaluate(lde_xs));
-    let extension_trace_tree = extension_trace_lde.as_ref().map(S::MerkleTree::from_matrix);
-    if let Some(t) = extension_trace_tree.as_ref() {
-        channel.commit_extension_trace(t.root());
-    }
-    println!("Extension trace commitment: {:?}", now.elapsed());
-
-    #[cfg(debug_assertions)]
-    this.validate_constraints(&challenges, &hints, base_trace, extension_trace.as_ref());
-    drop((trace, extension_trace));
-
-    let composition_trace_polys: Matrix<S::Fq>;
-    let composition_trace_lde: Matrix<S::Fq>;
-    let composition_trace_tree: S::MerkleTree;
-    {
-        // To prevent allocating more memory, just re-order the values in the trace to
-        // be in natural order. Note that for the remainder of the protocol the trace
-        // should entirely be in bit-reversed order hence why this function is
-        // called again at the end of the block.
-        let ce_lde_xs = air.ce_domain();
-        let ce_domain_size = ce_lde_xs.size();
-        let base_trace_ce_cols = bit_reverse_ce_trace(ce_domain_size, &mut base_trace_lde);
-        let extension_trace_ce_cols = extension_trace_lde
-            .as_mut()
-            .map(|t| bit_reverse_ce_trace(ce_domain_size, t));
-
-        let num_composition_coeffs = air.num_composition_constraint_coeffs();
-        let composition_coeffs = draw_multiple(&mut channel.public_coin, num_composition_coeffs);
-        let x_lde = ce_lde_xs.elements().collect::<Vec<_>>();
+/// Reusable prover state for generating many proofs that share the same trace
+/// length and proof options. The AIR constraints, composition constraint and
+/// constraint evaluation domain are built once and shared between proofs.
+pub struct ProverContext<S: Stark> {
+    air: Air<S::AirConfig>,
+    ce_domain_xs: GpuVec<S::Fp>,
+}
+
+impl<S: Stark> ProverContext<S> {
+    pub fn new(
+        trace_len: usize,
+        public_inputs: <S::AirConfig as AirConfig>::PublicInputs,
+        options: ProofOptions,
+    ) -> Self {
+        Self::from_air(Air::new(trace_len, public_inputs, options))
+    }
+
+    /// Creates a context from a compiled AIR e.g. one loaded from disk at
+    /// startup rather than rebuilt
+    pub fn from_compiled(
+        compiled: CompiledAir<S::AirConfig>,
+        public_inputs: <S::AirConfig as AirConfig>::PublicInputs,
+        options: ProofOptions,
+    ) -> Self {
+        Self::from_air(Air::from_compiled(compiled, public_inputs, options))
+    }
+
+    fn from_air(air: Air<S::AirConfig>) -> Self {
+        let ce_domain = air.ce_domain();
+        let mut ce_domain_xs = Vec::with_capacity_in(ce_domain.size(), GpuAllocator);
+        ce_domain_xs.extend(ce_domain.elements());
+        Self { air, ce_domain_xs }
+    }
+
+    pub const fn air(&self) -> &Air<S::AirConfig> {
+        &self.air
+    }
+
+    /// Generates a proof re-using the state of this context. Proofs are
+    /// identical to those generated by [`default_prove`].
+    pub fn prove(
+        &mut self,
+        this: &S,
+        witness: S::Witness,
+        domain_separator: Option<&[u8]>,
+    ) -> Result<Proof<S>, ProvingError> {
+        let trace = this.generate_trace(witness);
+        self.air.set_public_inputs(this.get_public_inputs());
+        trace
+            .validate(self.air.num_base_columns())
+            .map_err(|source| ProvingError::InvalidTrace { source })?;
+        if trace.len() != self.air.trace_len() {
+            return Err(ProvingError::TraceLengthMismatch {
+                expected: self.air.trace_len(),
+                actual: trace.len(),
+            });
+        }
+        let (proof, _) =
+            self.prove_trace(this, trace, domain_separator, |trace, challenges, _| {
+                trace.build_extension_columns(challenges)
+            })?;
+        Ok(proof)
+    }
+
+    /// Runs each stage of the prover in turn
+    ///
+    /// The stages can't be pipelined. Every Merkle root is absorbed by the
+    /// public coin before the next stage's inputs are drawn from it: extension
+    /// columns need challenges drawn after the base trace commitment and
+    /// constraint evaluation needs composition coefficients drawn after the
+    /// extension trace commitment. Overlapping hashing with the next NTT would
+    /// mean drawing randomness before the commitment it must come after.
+    #[allow(clippy::too_many_lines)]
+    fn prove_trace(
+        &self,
+        this: &S,
+        trace: S::Trace,
+        domain_separator: Option<&[u8]>,
+        build_extension: impl FnOnce(
+            &S::Trace,
+            &Challenges<S::Fq>,
+            &Hints<S::Fq>,
+        ) -> Option<Matrix<S::Fq>>,
+    ) -> Result<(Proof<S>, ProveStats), ProvingError> {
+        let start = Instant::now();
+        let mut stats = ProveStats::default();
+        let air = &self.air;
+        let options = air.options();
+        let lde_domain_size = air.trace_len() * air.lde_blowup_factor();
+        let fri_options = options.into_fri_options();
+        if !fri_options.is_valid_domain_size(lde_domain_size) {
+            return Err(ProvingError::InfeasibleFriParameters {
+                lde_domain_size,
+                remainder_size: fri_options.remainder_size(lde_domain_size),
+            });
+        }
+        let security_bits = security_level_bits::<S>(options, air.trace_len());
+        if security_bits < MIN_RECOMMENDED_SECURITY_BITS {
+            tracing::warn!(
+                "proof options only provide {security_bits} bits of conjectured security. At \
+                 least {MIN_RECOMMENDED_SECURITY_BITS} bits are recommended"
+            );
+        }
+        let merkle_digest_bytes = S::MerkleTree::merkle_digest_bytes();
+        if options.merkle_digest_bytes != merkle_digest_bytes {
+            return Err(ProvingError::MerkleDigestBytesMismatch {
+                declared: options.merkle_digest_bytes,
+                actual: merkle_digest_bytes,
+            });
+        }
+        let merkle_security_bits = S::MerkleTree::security_level_bits();
+        let query_security_bits = options.fri_query_security_bits();
+        if merkle_digest_bytes.is_some() && merkle_security_bits < query_security_bits {
+            tracing::warn!(
+                "truncated Merkle nodes only provide {merkle_security_bits} bits of collision \
+                 resistance which undercuts the {query_security_bits} bits of the FRI queries"
+            );
+        }
+        if let Some((constraint, (column, offset))) = air.find_out_of_range_offset() {
+            return Err(ProvingError::OffsetOutOfRange {
+                constraint,
+                column,
+                offset,
+            });
+        }
+        for column in air.unused_extension_columns() {
+            tracing::warn!("extension column {column} isn't referenced by any constraint");
+        }
+        let num_queries = usize::from(options.num_queries);
+        if num_queries > lde_domain_size {
+            return Err(ProvingError::TooManyQueries {
+                requested: num_queries,
+                available: lde_domain_size,
+            });
+        }
+        let num_randomizers = usize::from(options.num_randomizers);
+        let max_randomizers = S::AirConfig::NUM_RANDOMIZER_ROWS.min(air.trace_len() - 1);
+        if num_randomizers > max_randomizers {
+            return Err(ProvingError::TooManyRandomizers {
+                num_randomizers,
+                max_randomizers,
+            });
+        }
+        #[cfg(feature = "gpu")]
+        {
+            let required = air.estimated_gpu_bytes();
+            let available = crate::eval_gpu::available_gpu_bytes();
+            if required > available {
+                return Err(ProvingError::InsufficientGpuMemory {
+                    required,
+                    available,
+                });
+            }
+        }
+        let mut rng = this.gen_randomizer_rng();
+        // spot checks draw from their own RNG so randomizers are the same in
+        // debug and release builds
+        #[cfg(debug_assertions)]
+        let mut spot_check_rng = ChaCha20Rng::from_entropy();
+        let public_coin = this.gen_public_coin(air);
+        let mut channel = ProverChannel::<S>::new(air, public_coin, domain_separator);
+        channel.commit_aux_data(this.aux_data());
 
         let now = Instant::now();
-        let composition_evals = S::AirConfig::eval_constraint(
-            air.composition_constraint(),
-            &challenges,
-            &hints,
-            &composition_coeffs,
-            air.ce_blowup_factor(),
-            x_lde.to_vec_in(GpuAllocator),
-            &base_trace_ce_cols,
-            extension_trace_ce_cols.as_deref(),
+        let trace_xs = air.trace_domain();
+        let lde_xs = air.lde_domain();
+        let randomized_base_trace;
+        let base_trace = if num_randomizers == 0 {
+            trace.base_columns()
+        } else {
+            randomized_base_trace =
+                randomize_trailing_rows(trace.base_columns(), num_randomizers, &mut rng);
+            &randomized_base_trace
+        };
+        if base_trace.num_cols() != air.num_base_columns() {
+            let source = TraceError::NumColumnsMismatch {
+                expected: air.num_base_columns(),
+                actual: base_trace.num_cols(),
+            };
+            return Err(ProvingError::InvalidTrace { source });
+        }
+        let base_trace_polys = base_trace.interpolate(trace_xs);
+        let mut base_trace_lde = base_trace_polys.bit_reversed_evaluate(lde_xs);
+        #[cfg(debug_assertions)]
+        spot_check_lde(
+            base_trace,
+            &base_trace_polys,
+            &base_trace_lde,
+            |i| trace_xs.element(i),
+            |i| lde_xs.element(i),
+            &mut spot_check_rng,
         );
-        println!("Constraint eval: {:?}", now.elapsed());
+        let base_trace_tree = S::MerkleTree::from_matrix(&base_trace_lde);
+        println!("Base trace commitment: {:?}", now.elapsed());
+        stats.base_trace_ns = now.elapsed_ns();
+
+        channel.commit_base_trace(base_trace_tree.root());
+        if let Some(beacon) = this.randomness_beacon() {
+            channel.commit_randomness_beacon(beacon);
+        }
+        ensure_not_cancelled(this)?;
+        let num_challenges = air.num_challenges();
+        channel.transcript.advance(TranscriptStep::Challenges);
+        let challenges = Challenges::new(draw_multiple(&mut channel.public_coin, num_challenges));
+        let hints = air.gen_hints(&challenges);
+        if let Some(index) = air.find_private_hint(&hints) {
+            return Err(ProvingError::PrivateHintInConstraint { index });
+        }
 
         let now = Instant::now();
-        let composition_poly =
-            GpuVec::try_from(composition_evals.into_polynomials(air.ce_domain())).unwrap();
-        let mut composition_trace_cols = (0..air.ce_blowup_factor())
-            .map(|_| Vec::with_capacity_in(air.trace_len(), GpuAllocator))
-            .collect::<Vec<_>>();
-        for chunk in composition_poly.chunks(composition_trace_cols.len()) {
-            for i in 0..composition_trace_cols.len() {
-                composition_trace_cols[i].push(chunk[i]);
+        let extension_trace = build_extension(&trace, &challenges, &hints);
+        validate_extension_trace::<S::AirConfig>(extension_trace.as_ref(), air.trace_len())
+            .map_err(|source| ProvingError::InvalidExtensionTrace { source })?;
+        // check the trace the prover was given rather than the randomized one
+        #[cfg(debug_assertions)]
+        this.validate_constraints(
+            air,
+            &challenges,
+            &hints,
+            trace.base_columns(),
+            extension_trace.as_ref(),
+        )?;
+        let extension_trace = extension_trace.map(|t| {
+            if num_randomizers == 0 {
+                t
+            } else {
+                randomize_trailing_rows(&t, num_randomizers, &mut rng)
             }
+        });
+        let extension_trace_polys = extension_trace.as_ref().map(|t| t.interpolate(trace_xs));
+        let mut extension_trace_lde = extension_trace_polys
+            .as_ref()
+            .map(|p| p.bit_reversed_evaluate(lde_xs));
+        #[cfg(debug_assertions)]
+        if let (Some(t), Some(p), Some(lde)) = (
+            &extension_trace,
+            &extension_trace_polys,
+            &extension_trace_lde,
+        ) {
+            spot_check_lde(
+                t,
+                p,
+                lde,
+                |i| S::Fq::from(trace_xs.element(i)),
+                |i| S::Fq::from(lde_xs.element(i)),
+                &mut spot_check_rng,
+            );
+        }
+        let extension_trace_tree = extension_trace_lde.as_ref().map(S::MerkleTree::from_matrix);
+        if let Some(t) = extension_trace_tree.as_ref() {
+            channel.commit_extension_trace(t.root());
         }
-        composition_trace_polys = Matrix::new(composition_trace_cols);
-        composition_trace_lde = composition_trace_polys.bit_reversed_evaluate(air.lde_domain());
-        composition_trace_tree = S::MerkleTree::from_matrix(&composition_trace_lde);
-        channel.commit_composition_trace(composition_trace_tree.root());
-        println!("Composition trace commitment: {:?}", now.elapsed());
+        println!("Extension trace commitment: {:?}", now.elapsed());
+        stats.extension_ns = now.elapsed_ns();
+        ensure_not_cancelled(this)?;
+        drop((trace, extension_trace));
 
-        bit_reverse_ce_trace(ce_domain_size, &mut base_trace_lde);
-        extension_trace_lde
-            .as_mut()
-            .map(|t| bit_reverse_ce_trace(ce_domain_size, t));
-    }
+        let composition_trace_polys: Matrix<S::Fq>;
+        let composition_trace_lde: Option<Matrix<S::Fq>>;
+        let composition_trace_tree: Option<S::MerkleTree>;
+        {
+            // To prevent allocating more memory, just re-order the values in the trace to
+            // be in natural order. Note that for the remainder of the protocol the trace
+            // should entirely be in bit-reversed order hence why this function is
+            // called again at the end of the block.
+            let ce_lde_xs = air.ce_domain();
+            let ce_domain_size = ce_lde_xs.size();
+            let base_trace_ce_cols = bit_reverse_ce_trace(ce_domain_size, &mut base_trace_lde);
+            let extension_trace_ce_cols = extension_trace_lde
+                .as_mut()
+                .map(|t| bit_reverse_ce_trace(ce_domain_size, t));
 
-    let now = Instant::now();
-    let z = channel.get_ood_point();
-    let mut deep_poly_composer = DeepPolyComposer::new(
-        &air,
-        z,
-        base_trace_polys,
-        extension_trace_polys,
-        composition_trace_polys,
-    );
-    let (execution_trace_oods, composition_trace_oods) = deep_poly_composer.get_ood_evals();
-    channel.send_ood_evals(execution_trace_oods, composition_trace_oods);
+            let num_composition_coeffs = air.num_composition_constraint_coeffs();
+            channel
+                .transcript
+                .advance(TranscriptStep::CompositionCoeffs);
+            let composition_coeffs =
+                draw_multiple(&mut channel.public_coin, num_composition_coeffs);
 
-    let deep_coeffs = this.gen_deep_coeffs(&mut channel.public_coin, &air);
-    let deep_composition_poly = deep_poly_composer.into_deep_poly(deep_coeffs);
-    // let deep_xs = Radix2EvaluationDomain::new(lde_xs.size());
-    let deep_composition_lde = deep_composition_poly.into_bit_reversed_evaluations(lde_xs);
-    println!("Deep composition: {:?}", now.elapsed());
+            let now = Instant::now();
+            let composition_evals = S::AirConfig::eval_constraint(
+                air.composition_constraint(),
+                &challenges,
+                &hints,
+                &air.fixed_columns(),
+                &composition_coeffs,
+                air.ce_blowup_factor(),
+                air.domain_offset(),
+                &self.ce_domain_xs,
+                &base_trace_ce_cols,
+                extension_trace_ce_cols.as_deref(),
+            );
+            println!("Constraint eval: {:?}", now.elapsed());
+            stats.constraint_eval_ns = now.elapsed_ns();
 
-    let now = Instant::now();
-    let fri_options = options.into_fri_options();
-    let mut fri_prover = FriProver::<S::Fq, S::Digest, S::MerkleTree>::new(fri_options);
-    fri_prover.build_layers(&mut channel, deep_composition_lde.try_into().unwrap());
-    println!("FRI: {:?}", now.elapsed());
+            let now = Instant::now();
+            let composition_poly =
+                GpuVec::try_from(composition_evals.into_polynomials(air.ce_domain()))
+                    .map_err(|reason| ProvingError::InvalidMatrixShape { reason })?;
+            // coefficients above the composition degree are all zero
+            let composition_len = air.composition_degree() + 1;
+            let num_composition_cols = air.num_composition_columns();
+            composition_trace_polys = Matrix::from_composition_poly(
+                &composition_poly,
+                composition_len,
+                num_composition_cols,
+            )
+            .map_err(|_| ProvingError::CompositionReshapeMismatch {
+                len: composition_len,
+                cols: num_composition_cols,
+            })?;
+            if options.sends_composition_directly(air.trace_len()) {
+                // small traces skip the LDE and the verifier evaluates the coefficients
+                channel.commit_composition_coeffs(composition_poly[..composition_len].to_vec());
+                composition_trace_lde = None;
+                composition_trace_tree = None;
+            } else {
+                let lde = composition_trace_polys.bit_reversed_evaluate(air.lde_domain());
+                let tree = S::MerkleTree::from_matrix(&lde);
+                channel.commit_composition_trace(tree.root());
+                composition_trace_lde = Some(lde);
+                composition_trace_tree = Some(tree);
+            }
+            println!("Composition trace commitment: {:?}", now.elapsed());
+            stats.composition_ns = now.elapsed_ns();
 
-    let now = Instant::now();
-    channel.grind_fri_commitments();
-    println!("Proof of work: {:?}", now.elapsed());
-
-    let query_positions = Vec::from_iter(channel.get_fri_query_positions());
-    let fri_proof = fri_prover.into_proof(&query_positions);
-
-    let queries = Queries::new(
-        &base_trace_lde,
-        extension_trace_lde.as_ref(),
-        &composition_trace_lde,
-        &base_trace_tree,
-        extension_trace_tree.as_ref(),
-        &composition_trace_tree,
-        &query_positions,
-    );
-    Ok(channel.build_proof(queries, fri_proof))
+            bit_reverse_ce_trace(ce_domain_size, &mut base_trace_lde);
+            extension_trace_lde
+                .as_mut()
+                .map(|t| bit_reverse_ce_trace(ce_domain_size, t));
+        }
+        ensure_not_cancelled(this)?;
+
+        let now = Instant::now();
+        channel.transcript.advance(TranscriptStep::OodPoint);
+        let z = this.gen_ood_point(&mut channel.public_coin, air);
+        let mut deep_poly_composer = DeepPolyComposer::new(
+            air,
+            z,
+            base_trace_polys,
+            extension_trace_polys,
+            composition_trace_polys,
+        );
+        let (execution_trace_oods, composition_trace_oods) = deep_poly_composer.get_ood_evals();
+        channel.send_ood_evals(execution_trace_oods, composition_trace_oods);
+
+        channel.transcript.advance(TranscriptStep::DeepCoeffs);
+        let deep_coeffs = this.gen_deep_coeffs(&mut channel.public_coin, air);
+        #[cfg(debug_assertions)]
+        let deep_composition_poly =
+            deep_poly_composer.into_deep_poly_with_degree_check(deep_coeffs)?;
+        #[cfg(not(debug_assertions))]
+        let deep_composition_poly = deep_poly_composer.into_deep_poly(deep_coeffs);
+        // let deep_xs = Radix2EvaluationDomain::new(lde_xs.size());
+        let deep_composition_lde = deep_composition_poly.into_bit_reversed_evaluations(lde_xs);
+        println!("Deep composition: {:?}", now.elapsed());
+        stats.deep_ns = now.elapsed_ns();
+        ensure_not_cancelled(this)?;
+
+        let now = Instant::now();
+        let mut fri_prover = FriProver::<S::Fq, S::Digest, S::MerkleTree>::new(fri_options);
+        let deep_composition_lde = deep_composition_lde
+            .try_into()
+            .map_err(|reason| ProvingError::InvalidMatrixShape { reason })?;
+        channel.transcript.advance(TranscriptStep::Fri);
+        let completed = fri_prover
+            .build_layers_until(&mut channel, deep_composition_lde, || this.is_cancelled());
+        if !completed {
+            return Err(ProvingError::Cancelled);
+        }
+        println!("FRI: {:?}", now.elapsed());
+        stats.fri_ns = now.elapsed_ns();
+
+        let now = Instant::now();
+        channel.grind_fri_commitments(this.grinding_budget(), |attempts| {
+            this.on_grinding_progress(attempts);
+        })?;
+        println!("Proof of work: {:?}", now.elapsed());
+
+        let query_positions = Vec::from_iter(channel.get_fri_query_positions());
+        let fri_proof = fri_prover.into_proof(&query_positions);
+
+        let queries = Queries::new(
+            &base_trace_lde,
+            extension_trace_lde.as_ref(),
+            composition_trace_lde.as_ref(),
+            &base_trace_tree,
+            extension_trace_tree.as_ref(),
+            composition_trace_tree.as_ref(),
+            &query_positions,
+        )
+        .map_err(|source| ProvingError::QueryOpening { source })?;
+        stats.total_ns = start.elapsed_ns();
+        Ok((channel.build_proof(queries, fri_proof), stats))
+    }
 }
 
 /// Errors that can occur during the proving stage
+///
+/// Proving returns one of these rather than panicking for any trace, witness
+/// or options that type check.
 #[derive(Debug)]
 pub enum ProvingError {
     Fail,
+    /// The trace length differs from the length a [`ProverContext`] was built for
+    TraceLengthMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    /// The execution trace doesn't have the shape expected by the AIR
+    InvalidTrace {
+        source: TraceError,
+    },
+    /// The extension trace doesn't have the shape expected by the AIR
+    InvalidExtensionTrace {
+        source: TraceError,
+    },
+    /// A matrix expected to have a single column has a different shape
+    InvalidMatrixShape {
+        reason: String,
+    },
+    /// A constraint doesn't hold at a row of the trace. Only checked in debug
+    /// builds
+    UnsatisfiedConstraint {
+        constraint: usize,
+        row: usize,
+    },
+    /// A constraint's denominator doesn't divide its numerator so the
+    /// constraint is a rational function rather than a polynomial e.g. it's
+    /// divided by `X - t` where `t` isn't a root. Only checked in debug builds
+    ConstraintNotDivisible {
+        constraint: usize,
+    },
+    /// No proof of work nonce satisfies the grinding factor
+    ProofOfWorkNotFound {
+        grinding_factor: u8,
+    },
+    /// No proof of work nonce was found within [`Stark::grinding_budget`]
+    GrindingExceededBudget {
+        grinding_factor: u8,
+        max_attempts: u64,
+    },
+    /// The trace commitments can't be opened at the query positions
+    QueryOpening {
+        source: merkle::Error,
+    },
+    /// Randomizer rows would overwrite rows the constraints apply to. There
+    /// can be at most [`AirConfig::NUM_RANDOMIZER_ROWS`] and fewer than the
+    /// number of trace rows
+    TooManyRandomizers {
+        num_randomizers: usize,
+        max_randomizers: usize,
+    },
+    /// The trace has fewer rows than [`Air::min_trace_len`] for the options
+    /// picked by [`prove`]
+    TraceTooShort {
+        trace_len: usize,
+        min_trace_len: usize,
+    },
+    /// The LDE domain exceeds the largest power-of-two subgroup of the field
+    DomainTooLarge {
+        trace_len: usize,
+        lde_blowup_factor: usize,
+    },
+    /// The composition polynomial can't be split evenly into columns
+    CompositionReshapeMismatch {
+        len: usize,
+        cols: usize,
+    },
+    /// A constraint references a private hint which the verifier can't reproduce
+    PrivateHintInConstraint {
+        index: usize,
+    },
+    /// A constraint reads a row offset that wraps around the trace. See
+    /// [`Air::find_out_of_range_offset`]
+    OffsetOutOfRange {
+        constraint: usize,
+        column: usize,
+        offset: isize,
+    },
+    /// [`ProofOptions::merkle_digest_bytes`] doesn't match the truncation of
+    /// [`Stark::MerkleTree`] (see [`MerkleTree::merkle_digest_bytes`])
+    MerkleDigestBytesMismatch {
+        declared: Option<u8>,
+        actual: Option<u8>,
+    },
+    /// FRI folding of the LDE domain leaves a remainder domain smaller than the
+    /// blowup factor. Use a longer trace or a smaller FRI folding factor.
+    InfeasibleFriParameters {
+        lde_domain_size: usize,
+        remainder_size: usize,
+    },
+    /// The DEEP composition polynomial's degree is too high for FRI. Either a
+    /// constraint doesn't hold or the composition is wrong. Only checked in
+    /// debug builds
+    DeepPolyDegreeTooHigh {
+        degree: usize,
+        max_degree: usize,
+    },
+    /// More queries were requested than there are positions in the LDE domain.
+    /// Duplicate query positions are dropped so extra queries add no security.
+    TooManyQueries {
+        requested: usize,
+        available: usize,
+    },
+    /// [`Stark::is_cancelled`] returned true at a checkpoint between stages
+    Cancelled,
+    /// The LDE buffers need more GPU memory than the device has available.
+    /// See [`Air::estimated_gpu_bytes`]
+    InsufficientGpuMemory {
+        required: usize,
+        available: usize,
+    },
     // TODO
 }
 
+/// Returns [`ProvingError::Cancelled`] if the caller no longer wants the proof
+fn ensure_not_cancelled<S: Stark>(this: &S) -> Result<(), ProvingError> {
+    if this.is_cancelled() {
+        Err(ProvingError::Cancelled)
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks the extension trace has [`AirConfig::NUM_EXTENSION_COLUMNS`] columns
+/// of length `trace_len`
+fn validate_extension_trace<A: AirConfig>(
+    extension_trace: Option<&Matrix<A::Fq>>,
+    trace_len: usize,
+) -> Result<(), TraceError> {
+    let columns = extension_trace.map_or(&[][..], |trace| trace.0.as_slice());
+    if columns.len() != A::NUM_EXTENSION_COLUMNS {
+        return Err(TraceError::NumColumnsMismatch {
+            expected: A::NUM_EXTENSION_COLUMNS,
+            actual: columns.len(),
+        });
+    }
+    if let Some(col) = columns.iter().position(|column| column.len() != trace_len) {
+        return Err(TraceError::ColumnLengthMismatch {
+            col,
+            expected: trace_len,
+            actual: columns[col].len(),
+        });
+    }
+    Ok(())
+}
+
+/// Returns a copy of the matrix with the last `num_rows` rows replaced by
+/// random values
+fn randomize_trailing_rows<F: Field>(
+    matrix: &Matrix<F>,
+    num_rows: usize,
+    rng: &mut ChaCha20Rng,
+) -> Matrix<F> {
+    let mut matrix = matrix.clone();
+    for column in matrix.iter_mut() {
+        let n = column.len();
+        for value in &mut column[n - num_rows..] {
+            *value = F::rand(rng);
+        }
+    }
+    matrix
+}
+
+/// Checks a few random rows of `trace` are interpolated by `polys` and a few
+/// random rows of the bit-reversed `lde` are evaluations of `polys`. This is
+/// cheap and catches a faulty NTT before the rest of the proof is generated.
+/// `trace_x` and `lde_x` return the `i`th element of each domain.
+#[cfg(debug_assertions)]
+fn spot_check_lde<F: Field>(
+    trace: &Matrix<F>,
+    polys: &Matrix<F>,
+    lde: &Matrix<F>,
+    trace_x: impl Fn(usize) -> F,
+    lde_x: impl Fn(usize) -> F,
+    rng: &mut ChaCha20Rng,
+) {
+    use ministark_gpu::utils::bit_reverse_index;
+    use rand::Rng;
+    const NUM_CHECKS: usize = 4;
+    let (trace_len, lde_len) = (trace.num_rows(), lde.num_rows());
+    for _ in 0..NUM_CHECKS {
+        let row = rng.gen_range(0..trace_len);
+        let expected = polys.evaluate_at(trace_x(row));
+        assert_eq!(
+            trace.get_row(row).unwrap(),
+            expected,
+            "trace row {row} isn't interpolated"
+        );
+
+        let position = rng.gen_range(0..lde_len);
+        let expected = polys.evaluate_at(lde_x(bit_reverse_index(lde_len, position)));
+        assert_eq!(
+            lde.get_row(position).unwrap(),
+            expected,
+            "LDE row {position} is incorrect"
+        );
+    }
+}
+
 /// Bit reverses the first ce_domain_size many values of the matrix columns.
 /// Returns a slice to the portion of the columns that were bit reversed
 fn bit_reverse_ce_trace<F: Field>(ce_domain_size: usize, trace: &mut Matrix<F>) -> Vec<&[F]> {
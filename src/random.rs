@@ -1,12 +1,20 @@
+use crate::hash::to_evm_word;
 use crate::hash::Digest;
 use crate::hash::ElementHashFn;
 use crate::hash::HashFn;
+use crate::hash::Keccak256HashFn;
+use crate::hash::Poseidon;
+use crate::utils::SerdeOutput;
 use alloc::vec::Vec;
 use ark_ff::Field;
+use ark_ff::PrimeField;
+use core::ops::Range;
+use digest::Digest as _;
 use rand::Rng;
 use rand::RngCore;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
+use sha3::Keccak256;
 use std::collections::BTreeSet;
 use std::fmt::Debug;
 use std::marker::PhantomData;
@@ -40,16 +48,46 @@ pub trait PublicCoin: Sized + Send + Sync + Debug {
 
     fn reseed_with_int(&mut self, val: u64);
 
+    /// Absorbs an arbitrary sequence of bytes e.g. a domain separator
+    fn reseed_with_bytes(&mut self, bytes: &[u8]);
+
     fn draw(&mut self) -> Self::Field;
 
     /// Draws a maximum of n unique queries in the range `[0, domain_size)`
     fn draw_queries(&mut self, max_n: usize, domain_size: usize) -> BTreeSet<usize>;
 
+    /// Absorbs the proof of work nonce then draws query positions. The nonce
+    /// must be absorbed first otherwise grinding doesn't constrain which
+    /// positions are queried. Nothing is absorbed if `proof_of_work_bits` is
+    /// zero since there's no nonce.
+    fn draw_queries_after_grinding(
+        &mut self,
+        proof_of_work_bits: u8,
+        nonce: u64,
+        max_n: usize,
+        domain_size: usize,
+    ) -> BTreeSet<usize> {
+        if proof_of_work_bits != 0 {
+            self.reseed_with_int(nonce);
+        }
+        self.draw_queries(max_n, domain_size)
+    }
+
     fn grind_proof_of_work(&self, proof_of_work_bits: u8) -> Option<u64> {
+        self.grind_proof_of_work_in_range(proof_of_work_bits, 1..u64::MAX)
+    }
+
+    /// Searches `nonces` for a nonce that satisfies the proof of work. Uses
+    /// every thread in rayon's pool with the `parallel` feature.
+    fn grind_proof_of_work_in_range(
+        &self,
+        proof_of_work_bits: u8,
+        nonces: Range<u64>,
+    ) -> Option<u64> {
         #[cfg(not(feature = "parallel"))]
-        return (1..u64::MAX).find(|&nonce| self.verify_proof_of_work(proof_of_work_bits, nonce));
+        return { nonces }.find(|&nonce| self.verify_proof_of_work(proof_of_work_bits, nonce));
         #[cfg(feature = "parallel")]
-        return (1..u64::MAX)
+        return nonces
             .into_par_iter()
             .find_any(|&nonce| self.verify_proof_of_work(proof_of_work_bits, nonce));
     }
@@ -126,6 +164,11 @@ impl<F: Field, H: ElementHashFn<F>> PublicCoin for PublicCoinImpl<F, H> {
         self.bytes = Vec::new();
     }
 
+    fn reseed_with_bytes(&mut self, bytes: &[u8]) {
+        let bytes_digest = H::hash_chunks([bytes]);
+        self.reseed_with_digest(&bytes_digest);
+    }
+
     fn verify_proof_of_work(&self, proof_of_work_bits: u8, nonce: u64) -> bool {
         let digest = H::merge_with_int(&self.seed, nonce);
         leading_zeros(&digest.as_bytes()) >= u32::from(proof_of_work_bits)
@@ -178,6 +221,284 @@ impl<F: Field, H: HashFn> RngCore for PublicCoinImpl<F, H> {
     }
 }
 
+/// Parameters of the Poseidon sponge used by [`PoseidonPublicCoin`]
+pub trait PoseidonParams: Send + Sync + 'static {
+    type Field: PrimeField;
+
+    /// Number of state elements absorbed or squeezed per permutation
+    const RATE: usize;
+    /// Number of state elements never directly absorbed into or squeezed from
+    const CAPACITY: usize;
+    const ALPHA: u64;
+    const FULL_ROUNDS: usize;
+    const PARTIAL_ROUNDS: usize;
+}
+
+/// Public coin backed by a Poseidon duplex sponge
+///
+/// Unlike [`PublicCoinImpl`] field elements are absorbed and drawn without any
+/// byte serialization so the same challenges can be cheaply recomputed by a
+/// verifier that is itself being proven (e.g. for recursion). Digests and
+/// bytes are packed into field elements before being absorbed.
+pub struct PoseidonPublicCoin<F: Field, P: PoseidonParams<Field = F::BasePrimeField>, D: Digest> {
+    permutation: Poseidon<P::Field>,
+    state: Vec<P::Field>,
+    /// Position of the next rate element to absorb into or squeeze from
+    position: usize,
+    squeezing: bool,
+    _phantom: PhantomData<(F, D)>,
+}
+
+impl<F: Field, P: PoseidonParams<Field = F::BasePrimeField>, D: Digest>
+    PoseidonPublicCoin<F, P, D>
+{
+    fn absorb(&mut self, elements: impl IntoIterator<Item = P::Field>) {
+        for element in elements {
+            if self.squeezing {
+                self.squeezing = false;
+                self.position = 0;
+            }
+            if self.position == P::RATE {
+                self.permutation.permute(&mut self.state);
+                self.position = 0;
+            }
+            self.state[P::CAPACITY + self.position] += element;
+            self.position += 1;
+        }
+    }
+
+    fn squeeze(&mut self) -> P::Field {
+        if !self.squeezing || self.position == P::RATE {
+            self.permutation.permute(&mut self.state);
+            self.squeezing = true;
+            self.position = 0;
+        }
+        let element = self.state[P::CAPACITY + self.position];
+        self.position += 1;
+        element
+    }
+
+    fn absorb_bytes(&mut self, bytes: &[u8]) {
+        // pack as many bytes into each element as fit below the modulus
+        let bytes_per_element = (P::Field::MODULUS_BIT_SIZE as usize - 1) / 8;
+        self.absorb([P::Field::from(bytes.len() as u64)]);
+        let elements = bytes
+            .chunks(bytes_per_element)
+            .map(P::Field::from_le_bytes_mod_order)
+            .collect::<Vec<_>>();
+        self.absorb(elements);
+    }
+}
+
+impl<F: Field, P: PoseidonParams<Field = F::BasePrimeField>, D: Digest> Debug
+    for PoseidonPublicCoin<F, P, D>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PoseidonPublicCoin")
+            .field("state", &self.state)
+            .field("position", &self.position)
+            .field("squeezing", &self.squeezing)
+            .finish()
+    }
+}
+
+impl<F: Field, P: PoseidonParams<Field = F::BasePrimeField>, D: Digest> Clone
+    for PoseidonPublicCoin<F, P, D>
+{
+    fn clone(&self) -> Self {
+        Self {
+            permutation: self.permutation.clone(),
+            state: self.state.clone(),
+            position: self.position,
+            squeezing: self.squeezing,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<F: Field, P: PoseidonParams<Field = F::BasePrimeField>, D: Digest> PublicCoin
+    for PoseidonPublicCoin<F, P, D>
+{
+    type Digest = D;
+    type Field = F;
+
+    fn new(digest: D) -> Self {
+        let width = P::RATE + P::CAPACITY;
+        let mut coin = Self {
+            permutation: Poseidon::new(width, P::FULL_ROUNDS, P::PARTIAL_ROUNDS, P::ALPHA),
+            state: vec![P::Field::ZERO; width],
+            position: 0,
+            squeezing: false,
+            _phantom: PhantomData,
+        };
+        coin.reseed_with_digest(&digest);
+        coin
+    }
+
+    fn reseed_with_digest(&mut self, val: &D) {
+        self.absorb_bytes(&val.as_bytes());
+    }
+
+    fn reseed_with_field_elements(&mut self, vals: &[F]) {
+        for val in vals {
+            self.absorb(val.to_base_prime_field_elements());
+        }
+    }
+
+    fn reseed_with_int(&mut self, val: u64) {
+        // split into 32 bit limbs since the field may be smaller than 64 bits
+        let limbs = [val & u64::from(u32::MAX), val >> 32];
+        self.absorb(limbs.map(P::Field::from));
+    }
+
+    fn reseed_with_bytes(&mut self, bytes: &[u8]) {
+        self.absorb_bytes(bytes);
+    }
+
+    fn draw(&mut self) -> F {
+        let elements = (0..F::extension_degree())
+            .map(|_| self.squeeze())
+            .collect::<Vec<_>>();
+        F::from_base_prime_field_elems(&elements).unwrap()
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn draw_queries(&mut self, max_n: usize, domain_size: usize) -> BTreeSet<usize> {
+        assert!(domain_size.is_power_of_two());
+        (0..max_n)
+            .map(|_| {
+                let bits = self.squeeze().into_bigint().as_ref()[0];
+                bits as usize & (domain_size - 1)
+            })
+            .collect()
+    }
+
+    fn verify_proof_of_work(&self, proof_of_work_bits: u8, nonce: u64) -> bool {
+        let mut coin = self.clone();
+        coin.reseed_with_int(nonce);
+        let element = coin.squeeze().into_bigint();
+        element.as_ref()[0].trailing_zeros() >= u32::from(proof_of_work_bits)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn security_level_bits() -> u32 {
+        P::CAPACITY as u32 * P::Field::MODULUS_BIT_SIZE / 2
+    }
+}
+
+/// Public coin for proofs that are verified on Ethereum
+///
+/// Field elements and integers are absorbed as big-endian 32-byte words (see
+/// [`to_evm_word`]) with `seed = keccak256(seed || words)` so an EVM verifier
+/// can replay the transcript with `abi.encodePacked`. Each draw is
+/// `keccak256(seed || counter)` with the counter as a word. Field elements are
+/// the digest reduced modulo the base field and query positions are the
+/// digest's low bits.
+pub struct KeccakPublicCoin<F: Field> {
+    pub seed: SerdeOutput<Keccak256>,
+    counter: u64,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: Field> KeccakPublicCoin<F> {
+    fn absorb(&mut self, bytes: &[u8]) {
+        let mut hasher = Keccak256::new();
+        hasher.update(*self.seed);
+        hasher.update(bytes);
+        self.seed = SerdeOutput::new(hasher.finalize());
+        self.counter = 0;
+    }
+
+    fn hash_with_word(&self, value: u64) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+        hasher.update(*self.seed);
+        hasher.update(u64_to_evm_word(value));
+        SerdeOutput::<Keccak256>::new(hasher.finalize()).as_bytes()
+    }
+
+    fn next_word(&mut self) -> [u8; 32] {
+        self.counter += 1;
+        self.hash_with_word(self.counter)
+    }
+}
+
+impl<F: Field> Debug for KeccakPublicCoin<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeccakPublicCoin")
+            .field("seed", &self.seed)
+            .field("counter", &self.counter)
+            .finish()
+    }
+}
+
+impl<F: Field> PublicCoin for KeccakPublicCoin<F> {
+    type Digest = SerdeOutput<Keccak256>;
+    type Field = F;
+
+    fn new(digest: SerdeOutput<Keccak256>) -> Self {
+        Self {
+            seed: digest,
+            counter: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn reseed_with_digest(&mut self, val: &SerdeOutput<Keccak256>) {
+        self.absorb(&val.as_bytes());
+    }
+
+    fn reseed_with_field_elements(&mut self, vals: &[F]) {
+        let words = vals
+            .iter()
+            .flat_map(Field::to_base_prime_field_elements)
+            .flat_map(to_evm_word)
+            .collect::<Vec<u8>>();
+        self.absorb(&words);
+    }
+
+    fn reseed_with_int(&mut self, val: u64) {
+        self.absorb(&u64_to_evm_word(val));
+    }
+
+    fn reseed_with_bytes(&mut self, bytes: &[u8]) {
+        self.absorb(bytes);
+    }
+
+    fn draw(&mut self) -> F {
+        let elements = (0..F::extension_degree())
+            .map(|_| F::BasePrimeField::from_be_bytes_mod_order(&self.next_word()))
+            .collect::<Vec<_>>();
+        F::from_base_prime_field_elems(&elements).unwrap()
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn draw_queries(&mut self, max_n: usize, domain_size: usize) -> BTreeSet<usize> {
+        assert!(domain_size.is_power_of_two());
+        (0..max_n)
+            .map(|_| {
+                let word = self.next_word();
+                let bits = u64::from_be_bytes(word[24..].try_into().unwrap());
+                bits as usize & (domain_size - 1)
+            })
+            .collect()
+    }
+
+    fn verify_proof_of_work(&self, proof_of_work_bits: u8, nonce: u64) -> bool {
+        leading_zeros(&self.hash_with_word(nonce)) >= u32::from(proof_of_work_bits)
+    }
+
+    fn security_level_bits() -> u32 {
+        Keccak256HashFn::COLLISION_RESISTANCE
+    }
+}
+
+/// Encodes an integer as a big-endian 32-byte word i.e. a `uint256`
+fn u64_to_evm_word(value: u64) -> [u8; 32] {
+    let mut word = [0; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
 pub fn leading_zeros(hash: &[u8]) -> u32 {
     let mut zeros = 0;
     for byte in hash {
@@ -194,3 +515,104 @@ pub fn leading_zeros(hash: &[u8]) -> u32 {
 pub fn draw_multiple<P: PublicCoin>(public_coin: &mut P, n: usize) -> Vec<P::Field> {
     (0..n).map(|_| public_coin.draw()).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::KeccakPublicCoin;
+    use super::PoseidonParams;
+    use super::PoseidonPublicCoin;
+    use super::PublicCoin;
+    use crate::hash::HashFn;
+    use crate::hash::Keccak256HashFn;
+    use crate::utils::SerdeOutput;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+    use sha2::Sha256;
+    use std::collections::BTreeSet;
+
+    struct GoldilocksParams;
+
+    impl PoseidonParams for GoldilocksParams {
+        type Field = Fp;
+        const RATE: usize = 8;
+        const CAPACITY: usize = 4;
+        const ALPHA: u64 = 7;
+        const FULL_ROUNDS: usize = 8;
+        const PARTIAL_ROUNDS: usize = 22;
+    }
+
+    type Coin = PoseidonPublicCoin<Fp, GoldilocksParams, SerdeOutput<Sha256>>;
+
+    #[test]
+    fn poseidon_coins_with_same_transcript_draw_same_challenges() {
+        let mut prover_coin = Coin::new(SerdeOutput::default());
+        let mut verifier_coin = Coin::new(SerdeOutput::default());
+
+        prover_coin.reseed_with_field_elements(&[Fp::from(1u64), Fp::from(2u64)]);
+        verifier_coin.reseed_with_field_elements(&[Fp::from(1u64), Fp::from(2u64)]);
+
+        assert_eq!(prover_coin.draw(), verifier_coin.draw());
+        assert_eq!(
+            prover_coin.draw_queries(4, 1024),
+            verifier_coin.draw_queries(4, 1024)
+        );
+    }
+
+    /// `x^3` isn't a permutation of the Goldilocks field since 3 divides `p - 1`
+    struct CubingParams;
+
+    impl PoseidonParams for CubingParams {
+        type Field = Fp;
+        const RATE: usize = 8;
+        const CAPACITY: usize = 4;
+        const ALPHA: u64 = 3;
+        const FULL_ROUNDS: usize = 8;
+        const PARTIAL_ROUNDS: usize = 22;
+    }
+
+    #[test]
+    #[should_panic(expected = "alpha 3 isn't coprime to p - 1")]
+    fn poseidon_coin_rejects_alpha_dividing_the_group_order() {
+        let _ = PoseidonPublicCoin::<Fp, CubingParams, SerdeOutput<Sha256>>::new(
+            SerdeOutput::default(),
+        );
+    }
+
+    #[test]
+    fn poseidon_coin_challenges_depend_on_transcript() {
+        let mut coin_a = Coin::new(SerdeOutput::default());
+        let mut coin_b = Coin::new(SerdeOutput::default());
+
+        coin_a.reseed_with_int(1);
+        coin_b.reseed_with_int(2);
+
+        assert_ne!(coin_a.draw(), coin_b.draw());
+    }
+
+    #[test]
+    fn query_positions_depend_on_grinding_nonce() {
+        let mut coin_a = Coin::new(SerdeOutput::default());
+        let mut coin_b = Coin::new(SerdeOutput::default());
+
+        let positions_a = coin_a.draw_queries_after_grinding(8, 1, 16, 1024);
+        let positions_b = coin_b.draw_queries_after_grinding(8, 2, 16, 1024);
+
+        assert_ne!(positions_a, positions_b);
+    }
+
+    /// Challenges an EVM verifier has to reproduce. Computed independently
+    /// from `keccak256(abi.encodePacked(..))` of the absorbed words.
+    #[test]
+    fn keccak_coin_matches_transcript_vector() {
+        let seed = Keccak256HashFn::hash_chunks([b"ministark".as_slice()]);
+        let mut coin = KeccakPublicCoin::<Fp>::new(seed);
+
+        coin.reseed_with_field_elements(&[Fp::from(1u64), Fp::from(2u64)]);
+        coin.reseed_with_int(3);
+
+        assert_eq!(Fp::from(10453453631942342248u64), coin.draw());
+        assert_eq!(
+            BTreeSet::from([75, 402, 831, 902]),
+            coin.draw_queries(4, 1024)
+        );
+    }
+}
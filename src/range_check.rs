@@ -0,0 +1,216 @@
+use crate::constraints::AlgebraicItem;
+use crate::constraints::Constraint;
+use crate::constraints::ExecutionTraceColumn;
+use crate::expression::Expr;
+use crate::utils::FieldVariant;
+use crate::utils::GpuAllocator;
+use crate::Matrix;
+use crate::StarkExtensionOf;
+use alloc::vec::Vec;
+use ark_ff::BigInteger;
+use ark_ff::FftField;
+use ark_ff::Field;
+use ark_ff::One;
+use ark_ff::PrimeField;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use ministark_gpu::GpuFftField;
+use num_traits::Pow;
+
+/// Range checks base trace columns by decomposing their values into bits
+///
+/// Each check adds one auxiliary base column per bit. Constraints enforce
+/// every auxiliary column is binary and the bits of each row recompose to the
+/// checked value so a value that doesn't fit in the bit width can't be
+/// decomposed. Auxiliary columns come after the AIR's own base columns in the
+/// order the checks were added, least significant bit first.
+///
+/// ```ignore
+/// // column 3 must be 16-bit
+/// let range_checks = RangeCheckBuilder::new(NUM_COLUMNS).check(3, 16);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RangeCheckBuilder {
+    first_column: usize,
+    checks: Vec<(usize, u32)>,
+}
+
+impl RangeCheckBuilder {
+    /// `first_column` is the index of the first auxiliary column i.e. the
+    /// number of base columns before range checking
+    pub const fn new(first_column: usize) -> Self {
+        Self {
+            first_column,
+            checks: Vec::new(),
+        }
+    }
+
+    /// Requires every value of a base column to fit in `bit_width` bits
+    #[must_use]
+    pub fn check(mut self, column: usize, bit_width: u32) -> Self {
+        assert!(
+            column < self.first_column,
+            "column {column} is not an AIR column"
+        );
+        assert!(bit_width > 0, "bit width must be positive");
+        self.checks.push((column, bit_width));
+        self
+    }
+
+    /// Returns the number of auxiliary columns
+    pub fn num_columns(&self) -> usize {
+        self.checks
+            .iter()
+            .map(|&(_, bit_width)| bit_width as usize)
+            .sum()
+    }
+
+    /// Appends the bit decomposition of each checked column to the base trace.
+    /// Only the low `bit_width` bits of a value are decomposed so an out of
+    /// range value leaves the constraints unsatisfied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `base_trace` doesn't have `first_column` columns or a bit
+    /// width isn't smaller than the field's modulus. A sum of bits that can
+    /// wrap around the modulus wouldn't check the range.
+    pub fn append_columns<F: PrimeField>(&self, base_trace: &mut Matrix<F>) {
+        assert_eq!(self.first_column, base_trace.num_cols());
+        let mut bit_columns = Vec::new();
+        for &(column, bit_width) in &self.checks {
+            assert!(
+                bit_width < F::MODULUS_BIT_SIZE,
+                "bit width is too large for the field"
+            );
+            let values = base_trace[column]
+                .iter()
+                .map(|v| v.into_bigint())
+                .collect::<Vec<F::BigInt>>();
+            for bit in 0..bit_width as usize {
+                let mut bits = Vec::with_capacity_in(values.len(), GpuAllocator);
+                bits.extend(values.iter().map(|v| F::from(v.get_bit(bit))));
+                bit_columns.push(bits);
+            }
+        }
+        base_trace.append(Matrix::new(bit_columns)).unwrap();
+    }
+
+    /// Returns a constraint for each auxiliary column enforcing it's binary and
+    /// a constraint for each check enforcing the bits recompose to the value
+    ///
+    /// # Panics
+    ///
+    /// Panics if a bit width isn't smaller than the field's modulus. See
+    /// [`Self::append_columns`].
+    pub fn constraints<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+        trace_domain: &Radix2EvaluationDomain<Fp>,
+    ) -> Vec<Constraint<FieldVariant<Fp, Fq>>> {
+        use AlgebraicItem::*;
+        let trace_len = trace_domain.size();
+        let one = Constant(FieldVariant::Fp(Fp::one()));
+        let mut constraints = Vec::new();
+        let mut bit_column = self.first_column;
+        for &(column, bit_width) in &self.checks {
+            assert!(
+                bit_width < Fp::BasePrimeField::MODULUS_BIT_SIZE,
+                "bit width is too large for the field"
+            );
+            let bits = bit_column..bit_column + bit_width as usize;
+            bit_column = bits.end;
+            for bit in bits.clone() {
+                let binary = bit.curr() * (bit.curr() - one);
+                constraints.push(Constraint::new(binary / (X.pow(trace_len) - one)));
+            }
+            let mut power = Fp::one();
+            let recomposed = bits
+                .map(|bit| {
+                    let term = bit.curr() * Constant(FieldVariant::Fp(power));
+                    power.double_in_place();
+                    term
+                })
+                .sum::<Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>>();
+            let recomposition = column.curr() - recomposed;
+            constraints.push(Constraint::new(recomposition / (X.pow(trace_len) - one)));
+        }
+        constraints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RangeCheckBuilder;
+    use crate::constraints::AlgebraicItem;
+    use crate::utils::FieldVariant;
+    use crate::utils::GpuAllocator;
+    use crate::Matrix;
+    use ark_poly::EvaluationDomain;
+    use ark_poly::Radix2EvaluationDomain;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+
+    /// Returns the `(constraint, row)` pairs the range check constraints don't
+    /// hold at
+    fn unsatisfied(range_checks: &RangeCheckBuilder, trace: &Matrix<Fp>) -> Vec<(usize, usize)> {
+        use AlgebraicItem::*;
+        let trace_domain = Radix2EvaluationDomain::new(trace.num_rows()).unwrap();
+        let constraints = range_checks.constraints::<Fp, Fp>(&trace_domain);
+        let mut unsatisfied = Vec::new();
+        for (i, constraint) in constraints.iter().enumerate() {
+            for (row, x) in trace_domain.elements().enumerate() {
+                let evaluation = constraint.check(&mut |leaf| match leaf {
+                    X => FieldVariant::Fp(x),
+                    &Constant(c) => c,
+                    &Trace(col, 0) => FieldVariant::Fp(trace.0[col][row]),
+                    _ => unreachable!(),
+                });
+                if evaluation.is_none() {
+                    unsatisfied.push((i, row));
+                }
+            }
+        }
+        unsatisfied
+    }
+
+    #[test]
+    fn appends_the_bits_of_each_checked_column() {
+        let column = [5u64, 0, 7, 2].map(Fp::from).to_vec_in(GpuAllocator);
+        let mut matrix = Matrix::new(vec![column]);
+        let range_checks = RangeCheckBuilder::new(1).check(0, 3);
+
+        range_checks.append_columns(&mut matrix);
+
+        assert_eq!(4, matrix.num_cols());
+        assert_eq!(Some([5, 1, 0, 1].map(Fp::from).to_vec()), matrix.get_row(0));
+        assert_eq!(Some([7, 1, 1, 1].map(Fp::from).to_vec()), matrix.get_row(2));
+    }
+
+    #[test]
+    fn constraints_hold_for_values_in_range() {
+        let column = [5u64, 0, 7, 2].map(Fp::from).to_vec_in(GpuAllocator);
+        let mut trace = Matrix::new(vec![column]);
+        let range_checks = RangeCheckBuilder::new(1).check(0, 3);
+        range_checks.append_columns(&mut trace);
+
+        assert!(unsatisfied(&range_checks, &trace).is_empty());
+    }
+
+    #[test]
+    fn constraints_fail_for_values_out_of_range() {
+        // 9 doesn't fit in 3 bits
+        let column = [5u64, 9, 7, 2].map(Fp::from).to_vec_in(GpuAllocator);
+        let mut trace = Matrix::new(vec![column]);
+        let range_checks = RangeCheckBuilder::new(1).check(0, 3);
+        range_checks.append_columns(&mut trace);
+
+        // only the recomposition of row 1 fails
+        assert_eq!(vec![(3, 1)], unsatisfied(&range_checks, &trace));
+    }
+
+    #[test]
+    #[should_panic(expected = "bit width is too large for the field")]
+    fn constraints_reject_bit_widths_that_wrap_the_modulus() {
+        let trace_domain = Radix2EvaluationDomain::<Fp>::new(4).unwrap();
+        let range_checks = RangeCheckBuilder::new(1).check(0, 64);
+        let _ = range_checks.constraints::<Fp, Fp>(&trace_domain);
+    }
+}
@@ -0,0 +1,476 @@
+use crate::constraints::AlgebraicItem;
+use crate::constraints::CompositionConstraint;
+use crate::constraints::CompositionItem;
+use crate::constraints::Constraint;
+use crate::constraints::PeriodicColumn;
+use crate::expression::Expr;
+use crate::expression::P;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use ark_ff::Zero;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use ark_serialize::Compress;
+use ark_serialize::Read;
+use ark_serialize::SerializationError;
+use ark_serialize::Valid;
+use ark_serialize::Validate;
+use ark_serialize::Write;
+use core::hash::Hash;
+use snafu::Snafu;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+/// Node of a flattened constraint expression. Children are referenced by
+/// their index in [`ConstraintSchema::nodes`] and always come before their
+/// parent. Divisors (e.g. `X^n - 1` of transition constraints) are regular
+/// [`SchemaNode::Div`] nodes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaNode<T> {
+    X,
+    Constant(T),
+    Challenge(usize),
+    Periodic {
+        coeffs: Vec<T>,
+        interval_size: usize,
+    },
+    Fixed(usize),
+    Hint(usize),
+    Trace {
+        column: usize,
+        offset: isize,
+    },
+    Neg(usize),
+    Add(usize, usize),
+    Mul(usize, usize),
+    Div(usize, usize),
+    Pow(usize, usize),
+    /// Composition coefficient of a composition constraint. Schemas of AIR
+    /// constraints never contain it.
+    CompositionCoeff(usize),
+}
+
+/// Serializable representation of an AIR's constraints for external tooling
+/// e.g. a constraint visualizer or a verifier generator for another language.
+/// Subexpressions shared between constraints are stored once.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstraintSchema<T> {
+    pub nodes: Vec<SchemaNode<T>>,
+    /// Index of each constraint's root node
+    pub constraints: Vec<usize>,
+}
+
+/// Errors that are returned when rebuilding expressions from a schema
+#[derive(Debug, Snafu)]
+pub enum SchemaError {
+    #[snafu(display("node {node} references node {child} which doesn't come before it"))]
+    ChildOutOfOrder { node: usize, child: usize },
+    #[snafu(display("root {root} of expression {expr} is out of range of {num_nodes} nodes"))]
+    RootOutOfRange {
+        expr: usize,
+        root: usize,
+        num_nodes: usize,
+    },
+    #[snafu(display("node {node} can't be a leaf of this kind of expression"))]
+    UnexpectedLeaf { node: usize },
+    #[snafu(display("periodic column of node {node} isn't one of the given columns"))]
+    UnknownPeriodicColumn { node: usize },
+    #[snafu(display("expected a single root but the schema has {num_roots}"))]
+    NotSingleRoot { num_roots: usize },
+}
+
+/// Leaf of an expression that can be stored in a [`ConstraintSchema`]
+trait SchemaLeaf<T>: Clone {
+    fn to_node(&self) -> SchemaNode<T>;
+
+    /// Returns `None` if the node isn't a leaf of this type. Periodic nodes
+    /// are rebuilt with [`Self::from_periodic`] instead.
+    fn from_node(node: &SchemaNode<T>) -> Option<Self>;
+
+    fn from_periodic(column: PeriodicColumn<'static, T>) -> Self;
+}
+
+impl<T: Clone + Ord + Send + Sync + 'static> SchemaLeaf<T> for AlgebraicItem<T> {
+    fn to_node(&self) -> SchemaNode<T> {
+        match self {
+            Self::X => SchemaNode::X,
+            Self::Constant(v) => SchemaNode::Constant(v.clone()),
+            Self::Challenge(i) => SchemaNode::Challenge(*i),
+            Self::Periodic(column) => SchemaNode::Periodic {
+                coeffs: column.coeffs().to_vec(),
+                interval_size: column.interval_size(),
+            },
+            Self::Fixed(i) => SchemaNode::Fixed(*i),
+            Self::Hint(i) => SchemaNode::Hint(*i),
+            &Self::Trace(column, offset) => SchemaNode::Trace { column, offset },
+        }
+    }
+
+    fn from_node(node: &SchemaNode<T>) -> Option<Self> {
+        Some(match node {
+            SchemaNode::X => Self::X,
+            SchemaNode::Constant(v) => Self::Constant(v.clone()),
+            SchemaNode::Challenge(i) => Self::Challenge(*i),
+            SchemaNode::Fixed(i) => Self::Fixed(*i),
+            SchemaNode::Hint(i) => Self::Hint(*i),
+            &SchemaNode::Trace { column, offset } => Self::Trace(column, offset),
+            _ => return None,
+        })
+    }
+
+    fn from_periodic(column: PeriodicColumn<'static, T>) -> Self {
+        Self::Periodic(column)
+    }
+}
+
+impl<T: Clone + Ord + Send + Sync + 'static> SchemaLeaf<T> for CompositionItem<T> {
+    fn to_node(&self) -> SchemaNode<T> {
+        match self {
+            Self::Item(item) => item.to_node(),
+            Self::CompositionCoeff(i) => SchemaNode::CompositionCoeff(*i),
+        }
+    }
+
+    fn from_node(node: &SchemaNode<T>) -> Option<Self> {
+        match node {
+            SchemaNode::CompositionCoeff(i) => Some(Self::CompositionCoeff(*i)),
+            node => AlgebraicItem::from_node(node).map(Self::Item),
+        }
+    }
+
+    fn from_periodic(column: PeriodicColumn<'static, T>) -> Self {
+        Self::Item(AlgebraicItem::Periodic(column))
+    }
+}
+
+impl<T> ConstraintSchema<T> {
+    /// Checks every node only references nodes that come before it and every
+    /// root is a node. Schemas built from expressions always pass.
+    pub fn check_indices(&self) -> Result<(), SchemaError> {
+        for (node, schema_node) in self.nodes.iter().enumerate() {
+            if let Some(child) = schema_node.children().find(|&child| child >= node) {
+                return Err(SchemaError::ChildOutOfOrder { node, child });
+            }
+        }
+        let num_nodes = self.nodes.len();
+        for (expr, &root) in self.constraints.iter().enumerate() {
+            if root >= num_nodes {
+                return Err(SchemaError::RootOutOfRange {
+                    expr,
+                    root,
+                    num_nodes,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: Clone + Ord + Send + Sync + 'static> ConstraintSchema<T> {
+    pub fn new(constraints: &[Constraint<T>]) -> Self {
+        Self::from_exprs(constraints.iter().map(|constraint| &**constraint))
+    }
+
+    /// Flattens a composition constraint into a schema with a single root
+    pub fn from_composition(composition_constraint: &CompositionConstraint<T>) -> Self {
+        Self::from_exprs([&**composition_constraint])
+    }
+
+    /// Rebuilds the constraints. Nodes referenced more than once are shared.
+    /// Fails if the schema contains composition coefficients or isn't well
+    /// formed (see [`Self::check_indices`]).
+    ///
+    /// Constraints borrow periodic column coefficients for `'static` so a
+    /// periodic node is rebuilt from the column of `periodic_columns` with the
+    /// same coefficients and interval e.g. a column returned by
+    /// [`Constraint::periodic_columns`] for the AIR's own constraints. Fails
+    /// if there's no such column.
+    pub fn to_constraints(
+        &self,
+        periodic_columns: &[PeriodicColumn<'static, T>],
+    ) -> Result<Vec<Constraint<T>>, SchemaError> {
+        let exprs = self.to_exprs(periodic_columns)?;
+        Ok(exprs.into_iter().map(Constraint::new).collect())
+    }
+
+    /// Rebuilds a composition constraint flattened by
+    /// [`Self::from_composition`]. Fails if the schema doesn't have exactly
+    /// one root or isn't well formed. Periodic nodes are rebuilt from
+    /// `periodic_columns` like in [`Self::to_constraints`].
+    pub fn to_composition(
+        &self,
+        periodic_columns: &[PeriodicColumn<'static, T>],
+    ) -> Result<CompositionConstraint<T>, SchemaError>
+    where
+        T: Copy + Zero + Hash,
+    {
+        let exprs = self.to_exprs(periodic_columns)?;
+        let num_roots = exprs.len();
+        let [expr] =
+            <[_; 1]>::try_from(exprs).map_err(|_| SchemaError::NotSingleRoot { num_roots })?;
+        Ok(CompositionConstraint::new(expr))
+    }
+
+    fn from_exprs<'a, L: SchemaLeaf<T> + 'a>(exprs: impl IntoIterator<Item = &'a Expr<L>>) -> Self {
+        let mut schema = Self {
+            nodes: Vec::new(),
+            constraints: Vec::new(),
+        };
+        let mut seen = BTreeMap::new();
+        for expr in exprs {
+            let root = schema.push_expr(&mut seen, expr);
+            schema.constraints.push(root);
+        }
+        schema
+    }
+
+    /// Fails if the schema isn't well formed, a leaf node isn't a leaf of
+    /// type `L` or a periodic node isn't one of `periodic_columns`
+    fn to_exprs<L: SchemaLeaf<T>>(
+        &self,
+        periodic_columns: &[PeriodicColumn<'static, T>],
+    ) -> Result<Vec<Expr<L>>, SchemaError> {
+        self.check_indices()?;
+        let mut exprs: Vec<P<Expr<L>>> = Vec::with_capacity(self.nodes.len());
+        for (index, node) in self.nodes.iter().enumerate() {
+            let child = |i: &usize| Arc::clone(&exprs[*i]);
+            let expr = match node {
+                SchemaNode::Neg(a) => Expr::Neg(child(a)),
+                SchemaNode::Add(a, b) => Expr::Add(child(a), child(b)),
+                SchemaNode::Mul(a, b) => Expr::Mul(child(a), child(b)),
+                SchemaNode::Div(a, b) => Expr::Div(child(a), child(b)),
+                SchemaNode::Pow(a, e) => Expr::Pow(child(a), *e),
+                SchemaNode::Periodic {
+                    coeffs,
+                    interval_size,
+                } => {
+                    let column = periodic_columns
+                        .iter()
+                        .find(|column| {
+                            column.coeffs() == coeffs && column.interval_size() == *interval_size
+                        })
+                        .ok_or(SchemaError::UnknownPeriodicColumn { node: index })?;
+                    Expr::Leaf(L::from_periodic(column.clone()))
+                }
+                leaf => {
+                    let leaf =
+                        L::from_node(leaf).ok_or(SchemaError::UnexpectedLeaf { node: index })?;
+                    Expr::Leaf(leaf)
+                }
+            };
+            exprs.push(Arc::new(RwLock::new(expr)));
+        }
+        let roots = self.constraints.iter();
+        let roots = roots.map(|&root| exprs[root].read().unwrap().clone());
+        Ok(roots.collect())
+    }
+
+    fn push(&mut self, node: SchemaNode<T>) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    /// Flattens the children of `expr` then `expr` itself. Children are keyed
+    /// by their allocation so shared subexpressions are only pushed once.
+    fn push_expr<L: SchemaLeaf<T>>(
+        &mut self,
+        seen: &mut BTreeMap<*const RwLock<Expr<L>>, usize>,
+        expr: &Expr<L>,
+    ) -> usize {
+        let mut child = |schema: &mut Self, a: &P<Expr<L>>| {
+            if let Some(&i) = seen.get(&Arc::as_ptr(a)) {
+                return i;
+            }
+            let i = match &*a.read().unwrap() {
+                Expr::Leaf(item) => schema.push(item.to_node()),
+                expr => schema.push_expr(seen, expr),
+            };
+            seen.insert(Arc::as_ptr(a), i);
+            i
+        };
+        let node = match expr {
+            Expr::Leaf(item) => return self.push(item.to_node()),
+            Expr::Neg(a) => SchemaNode::Neg(child(self, a)),
+            Expr::Add(a, b) => SchemaNode::Add(child(self, a), child(self, b)),
+            Expr::Mul(a, b) => SchemaNode::Mul(child(self, a), child(self, b)),
+            Expr::Div(a, b) => SchemaNode::Div(child(self, a), child(self, b)),
+            Expr::Pow(a, e) => SchemaNode::Pow(child(self, a), *e),
+        };
+        self.push(node)
+    }
+}
+
+impl<T: CanonicalSerialize> CanonicalSerialize for ConstraintSchema<T> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.nodes.serialize_with_mode(&mut writer, compress)?;
+        self.constraints.serialize_with_mode(writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.nodes.serialized_size(compress) + self.constraints.serialized_size(compress)
+    }
+}
+
+impl<T: Valid> Valid for ConstraintSchema<T> {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.nodes.check()?;
+        self.check_indices()
+            .map_err(|_| SerializationError::InvalidData)
+    }
+}
+
+impl<T: CanonicalDeserialize> CanonicalDeserialize for ConstraintSchema<T> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let schema = Self {
+            nodes: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+            constraints: <_>::deserialize_with_mode(reader, compress, validate)?,
+        };
+        if validate == Validate::Yes {
+            schema.check()?;
+        }
+        Ok(schema)
+    }
+}
+
+impl<T: CanonicalSerialize> CanonicalSerialize for SchemaNode<T> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.tag().serialize_with_mode(&mut writer, compress)?;
+        match self {
+            Self::X => Ok(()),
+            Self::Constant(v) => v.serialize_with_mode(writer, compress),
+            Self::Challenge(i)
+            | Self::Fixed(i)
+            | Self::Hint(i)
+            | Self::Neg(i)
+            | Self::CompositionCoeff(i) => i.serialize_with_mode(writer, compress),
+            Self::Periodic {
+                coeffs,
+                interval_size,
+            } => {
+                coeffs.serialize_with_mode(&mut writer, compress)?;
+                interval_size.serialize_with_mode(writer, compress)
+            }
+            Self::Trace { column, offset } => {
+                column.serialize_with_mode(&mut writer, compress)?;
+                // ark_serialize has no signed integers
+                (*offset as i64)
+                    .to_le_bytes()
+                    .serialize_with_mode(writer, compress)
+            }
+            Self::Add(a, b) | Self::Mul(a, b) | Self::Div(a, b) | Self::Pow(a, b) => {
+                a.serialize_with_mode(&mut writer, compress)?;
+                b.serialize_with_mode(writer, compress)
+            }
+        }
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.tag().serialized_size(compress)
+            + match self {
+                Self::X => 0,
+                Self::Constant(v) => v.serialized_size(compress),
+                Self::Challenge(i)
+                | Self::Fixed(i)
+                | Self::Hint(i)
+                | Self::Neg(i)
+                | Self::CompositionCoeff(i) => i.serialized_size(compress),
+                Self::Periodic {
+                    coeffs,
+                    interval_size,
+                } => coeffs.serialized_size(compress) + interval_size.serialized_size(compress),
+                Self::Trace { column, .. } => column.serialized_size(compress) + 8,
+                Self::Add(a, b) | Self::Mul(a, b) | Self::Div(a, b) | Self::Pow(a, b) => {
+                    a.serialized_size(compress) + b.serialized_size(compress)
+                }
+            }
+    }
+}
+
+impl<T> SchemaNode<T> {
+    /// Returns the indices of the nodes this node is built from. The exponent
+    /// of [`SchemaNode::Pow`] isn't a node.
+    fn children(&self) -> impl Iterator<Item = usize> {
+        let (a, b) = match *self {
+            Self::Neg(a) | Self::Pow(a, _) => (Some(a), None),
+            Self::Add(a, b) | Self::Mul(a, b) | Self::Div(a, b) => (Some(a), Some(b)),
+            _ => (None, None),
+        };
+        a.into_iter().chain(b)
+    }
+
+    const fn tag(&self) -> u8 {
+        match self {
+            Self::X => 0,
+            Self::Constant(_) => 1,
+            Self::Challenge(_) => 2,
+            Self::Periodic { .. } => 3,
+            Self::Hint(_) => 4,
+            Self::Trace { .. } => 5,
+            Self::Neg(_) => 6,
+            Self::Add(_, _) => 7,
+            Self::Mul(_, _) => 8,
+            Self::Div(_, _) => 9,
+            Self::Pow(_, _) => 10,
+            Self::Fixed(_) => 11,
+            Self::CompositionCoeff(_) => 12,
+        }
+    }
+}
+
+impl<T: Valid> Valid for SchemaNode<T> {
+    fn check(&self) -> Result<(), SerializationError> {
+        match self {
+            Self::Constant(v) => v.check(),
+            Self::Periodic { coeffs, .. } => coeffs.check(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<T: CanonicalDeserialize> CanonicalDeserialize for SchemaNode<T> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let tag = u8::deserialize_with_mode(&mut reader, compress, validate)?;
+        let index = |reader: &mut R| usize::deserialize_with_mode(reader, compress, validate);
+        Ok(match tag {
+            0 => Self::X,
+            1 => Self::Constant(T::deserialize_with_mode(reader, compress, validate)?),
+            2 => Self::Challenge(index(&mut reader)?),
+            3 => Self::Periodic {
+                coeffs: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+                interval_size: <_>::deserialize_with_mode(reader, compress, validate)?,
+            },
+            4 => Self::Hint(index(&mut reader)?),
+            5 => {
+                let column = index(&mut reader)?;
+                let offset = <[u8; 8]>::deserialize_with_mode(reader, compress, validate)?;
+                let offset = isize::try_from(i64::from_le_bytes(offset))
+                    .map_err(|_| SerializationError::InvalidData)?;
+                Self::Trace { column, offset }
+            }
+            6 => Self::Neg(index(&mut reader)?),
+            7 => Self::Add(index(&mut reader)?, index(&mut reader)?),
+            8 => Self::Mul(index(&mut reader)?, index(&mut reader)?),
+            9 => Self::Div(index(&mut reader)?, index(&mut reader)?),
+            10 => Self::Pow(index(&mut reader)?, index(&mut reader)?),
+            11 => Self::Fixed(index(&mut reader)?),
+            12 => Self::CompositionCoeff(index(&mut reader)?),
+            _ => return Err(SerializationError::InvalidData),
+        })
+    }
+}
@@ -0,0 +1,119 @@
+use crate::constraints::row_divisor;
+use crate::constraints::AlgebraicItem;
+use crate::constraints::Constraint;
+use crate::utils::FieldVariant;
+use crate::Matrix;
+use alloc::vec::Vec;
+use ark_ff::FftField;
+use ark_ff::Field;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+
+/// Public states at either end of a segment of a longer computation
+///
+/// A long running computation can be proven as a sequence of segment proofs
+/// where each segment starts from the state the previous one ended in. Each
+/// segment's AIR includes [`state_boundary_constraints`] so its first row
+/// matches `input_state` and its last row matches `output_state`. A chain of
+/// segment proofs composes if every segment's output state is the next
+/// segment's input state (see [`segments_compose`]).
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct SegmentBoundary<F: Field> {
+    pub input_state: Vec<F>,
+    pub output_state: Vec<F>,
+}
+
+impl<F: Field> SegmentBoundary<F> {
+    pub fn new(input_state: Vec<F>, output_state: Vec<F>) -> Self {
+        assert_eq!(
+            input_state.len(),
+            output_state.len(),
+            "state widths don't match"
+        );
+        Self {
+            input_state,
+            output_state,
+        }
+    }
+
+    /// Returns the hints referenced by [`state_boundary_constraints`]. Input
+    /// state values start at hint `hint_offset` and are followed by the output
+    /// state values.
+    pub fn hints(&self, hint_offset: usize) -> Vec<(usize, F)> {
+        let values = self.input_state.iter().chain(&self.output_state);
+        values
+            .enumerate()
+            .map(|(i, &value)| (hint_offset + i, value))
+            .collect()
+    }
+
+    /// Returns true if `next` starts in the state this segment ends in
+    pub fn composes_with(&self, next: &Self) -> bool {
+        self.output_state == next.input_state
+    }
+}
+
+/// Returns true if each segment starts in the state the previous one ends in
+pub fn segments_compose<F: Field>(segments: &[SegmentBoundary<F>]) -> bool {
+    segments
+        .array_windows()
+        .all(|[prev, next]| prev.composes_with(next))
+}
+
+/// Returns boundary constraints asserting the first row of `state_columns`
+/// equals the input state and the last row equals the output state of a
+/// [`SegmentBoundary`] whose hints start at `hint_offset`.
+pub fn state_boundary_constraints<Fp: FftField, Fq: Field>(
+    trace_domain: &Radix2EvaluationDomain<Fp>,
+    state_columns: &[usize],
+    hint_offset: usize,
+) -> Vec<Constraint<FieldVariant<Fp, Fq>>> {
+    use AlgebraicItem::*;
+    let width = state_columns.len();
+    let last_row = trace_domain.size() - 1;
+    let input_constraints = state_columns.iter().enumerate().map(|(i, &column)| {
+        (Trace(column, 0) - Hint(hint_offset + i)) / row_divisor(trace_domain, 0)
+    });
+    let output_constraints = state_columns.iter().enumerate().map(|(i, &column)| {
+        (Trace(column, 0) - Hint(hint_offset + width + i)) / row_divisor(trace_domain, last_row)
+    });
+    input_constraints
+        .chain(output_constraints)
+        .map(Constraint::new)
+        .collect()
+}
+
+/// Returns the states in the first and last row of `state_columns`
+pub fn trace_boundary<F: Field>(
+    base_columns: &Matrix<F>,
+    state_columns: &[usize],
+) -> SegmentBoundary<F> {
+    let last_row = base_columns.num_rows() - 1;
+    let state = |row: usize| {
+        state_columns
+            .iter()
+            .map(|&col| base_columns.0[col][row])
+            .collect()
+    };
+    SegmentBoundary::new(state(0), state(last_row))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::segments_compose;
+    use super::SegmentBoundary;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+
+    #[test]
+    fn mismatched_states_dont_compose() {
+        let state = |values: [u64; 2]| values.map(Fp::from).to_vec();
+        let first = SegmentBoundary::new(state([1, 1]), state([5, 8]));
+        let second = SegmentBoundary::new(state([5, 8]), state([89, 144]));
+        let third = SegmentBoundary::new(state([89, 145]), state([1, 2]));
+
+        assert!(segments_compose(&[first.clone(), second.clone()]));
+        assert!(!segments_compose(&[first, second, third]));
+    }
+}
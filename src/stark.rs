@@ -7,20 +7,37 @@ use crate::hash::Digest;
 use crate::hints::Hints;
 use crate::merkle::MatrixMerkleTree;
 use crate::merkle::MerkleTree;
+use crate::proof::ProofPolicy;
+use crate::proof::Receipt;
 use crate::prover::default_prove;
+use crate::prover::default_prove_with_extension;
+use crate::prover::default_prove_with_stats;
+use crate::prover::ProveStats;
 use crate::prover::ProvingError;
 use crate::random::draw_multiple;
 use crate::random::PublicCoin;
 use crate::verifier::default_verify;
+use crate::verifier::default_verify_against_trace_root;
+use crate::verifier::default_verify_with_policy;
+use crate::verifier::default_verify_with_stats;
+use crate::verifier::default_verify_with_trace;
 use crate::verifier::VerificationError;
+use crate::verifier::VerifierTrace;
+use crate::verifier::VerifyStats;
 use crate::Air;
 use crate::Matrix;
 use crate::Proof;
 use crate::ProofOptions;
 use crate::StarkExtensionOf;
 use crate::Trace;
+use alloc::vec::Vec;
 use ark_ff::FftField;
+use ark_serialize::CanonicalSerialize;
 use ministark_gpu::GpuFftField;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use sha2::Digest as _;
+use sha2::Sha256;
 
 pub trait Stark: Sized + Send + Sync {
     type Fp: GpuFftField + FftField;
@@ -38,13 +55,21 @@ pub trait Stark: Sized + Send + Sync {
 
     fn gen_public_coin(&self, air: &Air<Self::AirConfig>) -> Self::PublicCoin;
 
+    /// Auxiliary data (e.g. program output or a nonce) that is bound into the
+    /// proof but isn't part of the trace. It's absorbed into the transcript
+    /// before any commitments and stored in the proof. Verification fails if
+    /// the proof's auxiliary data differs from the verifier's.
+    fn aux_data(&self) -> Vec<Self::Fp> {
+        Vec::new()
+    }
+
     fn gen_deep_coeffs(
         &self,
         public_coin: &mut Self::PublicCoin,
         air: &Air<Self::AirConfig>,
     ) -> DeepCompositionCoeffs<Self::Fq> {
         let num_execution_trace = air.trace_arguments().len();
-        let num_composition_trace = air.ce_blowup_factor();
+        let num_composition_trace = air.num_composition_columns();
         DeepCompositionCoeffs {
             execution_trace: draw_multiple(public_coin, num_execution_trace),
             composition_trace: draw_multiple(public_coin, num_composition_trace),
@@ -52,26 +77,149 @@ pub trait Stark: Sized + Send + Sync {
         }
     }
 
+    /// Selects the out-of-domain point. Must draw from `public_coin` so the
+    /// prover can't choose it and must never return a point on the trace or
+    /// LDE domain. Defaults to [`Air::draw_ood_point`].
+    fn gen_ood_point(
+        &self,
+        public_coin: &mut Self::PublicCoin,
+        air: &Air<Self::AirConfig>,
+    ) -> Self::Fq {
+        air.draw_ood_point(public_coin)
+    }
+
     fn generate_trace(&self, witness: Self::Witness) -> Self::Trace;
 
+    /// Identifies the AIR in [`Stark::statement_hash`]. Defaults to the type
+    /// name of the AIR config which isn't guaranteed to be stable between
+    /// compiler versions so override this if statement hashes are persisted.
+    fn air_id(&self) -> &str {
+        core::any::type_name::<Self::AirConfig>()
+    }
+
+    /// Returns a hash of what proofs of this claim attest to: the AIR
+    /// identifier, public inputs and auxiliary data. Unlike the proof bytes it
+    /// doesn't depend on the prover's randomness so it can be used to index
+    /// and deduplicate proofs of the same claim.
+    fn statement_hash(&self) -> [u8; 32] {
+        let mut bytes = Vec::new();
+        let air_id = self.air_id().as_bytes();
+        air_id.len().serialize_compressed(&mut bytes).unwrap();
+        bytes.extend_from_slice(air_id);
+        self.get_public_inputs()
+            .serialize_compressed(&mut bytes)
+            .unwrap();
+        self.aux_data().serialize_compressed(&mut bytes).unwrap();
+        Sha256::digest(bytes).into()
+    }
+
+    /// Maximum number of nonces the prover tries when grinding for proof of
+    /// work. Proving fails with [`ProvingError::GrindingExceededBudget`] if no
+    /// nonce is found. There's no limit by default.
+    fn grinding_budget(&self) -> Option<u64> {
+        None
+    }
+
+    /// Called periodically while grinding with the number of nonces tried
+    fn on_grinding_progress(&self, _attempts: u64) {}
+
+    /// Returns the RNG the prover fills randomizer rows from (see
+    /// [`ProofOptions::with_num_randomizers`]). Defaults to one seeded from OS
+    /// entropy. Override with a fixed seed for reproducible proofs in tests or
+    /// seed it from a hardware source. The seed must stay secret otherwise the
+    /// randomizers don't hide the trace.
+    fn gen_randomizer_rng(&self) -> ChaCha20Rng {
+        ChaCha20Rng::from_entropy()
+    }
+
+    /// Public randomness (e.g. a VDF output or drand round) absorbed into the
+    /// transcript straight after the base trace commitment. Challenges then
+    /// depend on a value the prover couldn't know when choosing its trace
+    /// which prevents grinding the transcript through the trace. The
+    /// verifier reads the beacon from the same hook so proofs only verify
+    /// with the beacon they were made with. No beacon by default.
+    fn randomness_beacon(&self) -> Option<Self::Fq> {
+        None
+    }
+
+    /// Checked by the prover between stages and between FRI layers. Proving
+    /// stops with [`ProvingError::Cancelled`] once this returns true, which
+    /// drops the stage's buffers. Proving doesn't yield to the executor so
+    /// dropping the future doesn't interrupt it: a server should instead set a
+    /// flag (e.g. an `AtomicBool`) when the client disconnects and read it
+    /// here. Never cancelled by default.
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+
     async fn prove(
         &self,
         options: ProofOptions,
         witness: Self::Witness,
     ) -> Result<Proof<Self>, ProvingError> {
-        default_prove(self, options, witness)
+        default_prove(self, options, witness, None)
+    }
+
+    /// Generates a proof and a [`Receipt`] identifying it
+    async fn prove_with_receipt(
+        &self,
+        options: ProofOptions,
+        witness: Self::Witness,
+    ) -> Result<(Proof<Self>, Receipt), ProvingError> {
+        let proof = default_prove(self, options, witness, None)?;
+        let receipt = Receipt::new(self, &proof);
+        Ok((proof, receipt))
+    }
+
+    /// Generates a proof bound to a domain separator (e.g. a chain id or
+    /// protocol version). The proof only verifies with the same separator.
+    async fn prove_with_domain_separator(
+        &self,
+        options: ProofOptions,
+        witness: Self::Witness,
+        domain_separator: &[u8],
+    ) -> Result<Proof<Self>, ProvingError> {
+        default_prove(self, options, witness, Some(domain_separator))
     }
 
-    /// Check the AIR constraints are valid
+    /// Generates a proof and returns the time spent in each proving stage.
+    /// Useful for benchmarking individual stages.
+    async fn prove_with_stats(
+        &self,
+        options: ProofOptions,
+        witness: Self::Witness,
+    ) -> Result<(Proof<Self>, ProveStats), ProvingError> {
+        default_prove_with_stats(self, options, witness, None)
+    }
+
+    /// Generates a proof with the extension trace built by `build_extension`
+    /// instead of [`Trace::build_extension_columns`]. See
+    /// [`default_prove_with_extension`] for when the builder is called.
+    async fn prove_with_extension(
+        &self,
+        options: ProofOptions,
+        witness: Self::Witness,
+        build_extension: impl FnOnce(
+            &Self::Trace,
+            &Challenges<Self::Fq>,
+            &Hints<Self::Fq>,
+        ) -> Option<Matrix<Self::Fq>>,
+    ) -> Result<Proof<Self>, ProvingError> {
+        default_prove_with_extension(self, options, witness, None, build_extension)
+    }
+
+    /// Checks the AIR constraints hold at every point of the trace domain i.e.
+    /// at every row of the trace. The prover calls this in debug builds with
+    /// the trace it was given, before any randomizer rows are filled in.
     fn validate_constraints(
         &self,
+        air: &Air<Self::AirConfig>,
         challenges: &Challenges<Self::Fq>,
         hints: &Hints<Self::Fq>,
         base_trace: &Matrix<Self::Fp>,
         extension_trace: Option<&Matrix<Self::Fq>>,
-    ) {
-        // TODO: this is unfinished
-        default_validate_constraints(self, challenges, hints, base_trace, extension_trace);
+    ) -> Result<(), ProvingError> {
+        default_validate_constraints(self, air, challenges, hints, base_trace, extension_trace)
     }
 
     #[allow(clippy::too_many_lines)]
@@ -80,6 +228,61 @@ pub trait Stark: Sized + Send + Sync {
         proof: Proof<Self>,
         required_security_bits: u32,
     ) -> Result<VerifierChannelArtifacts<Self::Fq>, VerificationError> {
-        default_verify(self, proof, required_security_bits)
+        default_verify(self, proof, required_security_bits, None)
+    }
+
+    /// Verifies a proof and returns the time spent in each verification stage.
+    /// Useful for profiling verifier latency.
+    fn verify_with_stats(
+        &self,
+        proof: Proof<Self>,
+        required_security_bits: u32,
+    ) -> Result<(VerifierChannelArtifacts<Self::Fq>, VerifyStats), VerificationError> {
+        default_verify_with_stats(self, proof, required_security_bits, None)
+    }
+
+    /// Verifies a proof and records the operations the verifier performed
+    /// e.g. as the data source of a recursion witness. See
+    /// [`default_verify_with_trace`].
+    fn verify_with_trace(
+        &self,
+        proof: Proof<Self>,
+        required_security_bits: u32,
+    ) -> (
+        Result<VerifierChannelArtifacts<Self::Fq>, VerificationError>,
+        VerifierTrace<Self::Fq, Self::Digest>,
+    ) {
+        default_verify_with_trace(self, proof, required_security_bits, None)
+    }
+
+    /// Verifies a proof if it meets `policy` and the security level. See
+    /// [`default_verify_with_policy`]
+    fn verify_with_policy(
+        &self,
+        proof: Proof<Self>,
+        policy: &ProofPolicy,
+        required_security_bits: u32,
+    ) -> Result<VerifierChannelArtifacts<Self::Fq>, VerificationError> {
+        default_verify_with_policy(self, proof, policy, required_security_bits, None)
+    }
+
+    /// Verifies a proof whose base trace was committed to with `trace_root`
+    fn verify_against_trace_root(
+        &self,
+        proof: Proof<Self>,
+        required_security_bits: u32,
+        trace_root: &Self::Digest,
+    ) -> Result<VerifierChannelArtifacts<Self::Fq>, VerificationError> {
+        default_verify_against_trace_root(self, proof, required_security_bits, trace_root)
+    }
+
+    /// Verifies a proof generated with [`Stark::prove_with_domain_separator`]
+    fn verify_with_domain_separator(
+        &self,
+        proof: Proof<Self>,
+        required_security_bits: u32,
+        domain_separator: &[u8],
+    ) -> Result<VerifierChannelArtifacts<Self::Fq>, VerificationError> {
+        default_verify(self, proof, required_security_bits, Some(domain_separator))
     }
 }
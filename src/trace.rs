@@ -1,7 +1,9 @@
 use crate::challenges::Challenges;
+use crate::merkle;
 use crate::merkle::MatrixMerkleTree;
 use crate::merkle::MerkleTree;
 use crate::stark::Stark;
+use crate::utils::GpuAllocator;
 use crate::Matrix;
 use alloc::vec::Vec;
 use ark_ff::FftField;
@@ -9,6 +11,11 @@ use ark_ff::Field;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
 use ark_serialize::Valid;
+use core::iter::zip;
+use snafu::Snafu;
+
+/// Minimum number of rows an execution trace can have
+pub const MIN_TRACE_LEN: usize = 8;
 
 /// STARK execution trace
 #[allow(clippy::len_without_is_empty)]
@@ -22,6 +29,12 @@ pub trait Trace: Send + Sync {
     }
 
     /// Returns a reference to the base trace columns.
+    ///
+    /// Every column is materialized, including columns that are cheap
+    /// functions of others (e.g. the square of another column). Computing
+    /// them lazily wouldn't lower peak memory: the prover keeps the LDE of
+    /// every column for the commitment and query openings and the LDE is
+    /// `lde_blowup_factor` times larger than the column itself.
     fn base_columns(&self) -> &Matrix<Self::Fp>;
 
     /// Builds and returns the extension trace columns
@@ -33,15 +46,135 @@ pub trait Trace: Send + Sync {
     ) -> Option<Matrix<Self::Fq>> {
         None
     }
+
+    /// Checks the shape of the trace before proving. The trace must have
+    /// `num_base_columns` base columns of equal, power-of-two length that is at
+    /// least [`MIN_TRACE_LEN`].
+    fn validate(&self, num_base_columns: usize) -> Result<(), TraceError> {
+        let columns = &self.base_columns().0;
+        if columns.len() != num_base_columns {
+            return Err(TraceError::NumColumnsMismatch {
+                expected: num_base_columns,
+                actual: columns.len(),
+            });
+        }
+        let len = columns.first().map_or(0, |column| column.len());
+        if let Some(col) = columns.iter().position(|column| column.len() != len) {
+            return Err(TraceError::ColumnLengthMismatch {
+                col,
+                expected: len,
+                actual: columns[col].len(),
+            });
+        }
+        if !len.is_power_of_two() {
+            return Err(TraceError::LengthNotPowerOfTwo { len });
+        }
+        if len < MIN_TRACE_LEN {
+            return Err(TraceError::TooShort { len });
+        }
+        Ok(())
+    }
 }
 
+/// Builds an execution trace by repeatedly applying a transition to a row of
+/// `W` registers
+///
+/// ```ignore
+/// let fib = TraceBuilder::new([Fp::one(), Fp::one()]).build(16, |&[a, b]| [a + b, a + b + b]);
+/// ```
+pub struct TraceBuilder<F, const W: usize> {
+    initial_state: [F; W],
+}
+
+impl<F: Field, const W: usize> TraceBuilder<F, W> {
+    pub const fn new(initial_state: [F; W]) -> Self {
+        assert!(W > 0, "a trace needs at least one column");
+        Self { initial_state }
+    }
+
+    /// Returns a column-major matrix whose first row is the initial state and
+    /// each following row is `transition` applied to the row before it.
+    /// `num_rows` is rounded up to a power of two that is at least
+    /// [`MIN_TRACE_LEN`] and padding rows continue to apply `transition` so
+    /// they still satisfy the transition constraints.
+    pub fn build(self, num_rows: usize, transition: impl Fn(&[F; W]) -> [F; W]) -> Matrix<F> {
+        let num_rows = num_rows.max(MIN_TRACE_LEN).next_power_of_two();
+        let mut columns = (0..W)
+            .map(|_| Vec::with_capacity_in(num_rows, GpuAllocator))
+            .collect::<Vec<_>>();
+        let mut state = self.initial_state;
+        for row in 0..num_rows {
+            for (column, value) in zip(&mut columns, state) {
+                column.push(value);
+            }
+            if row + 1 != num_rows {
+                state = transition(&state);
+            }
+        }
+        Matrix::new(columns)
+    }
+}
+
+/// Errors that are returned when validating an execution trace
+#[derive(Debug, Snafu)]
+pub enum TraceError {
+    #[snafu(display("expected {expected} base columns but the trace has {actual}"))]
+    NumColumnsMismatch { expected: usize, actual: usize },
+    #[snafu(display("column {col} has {actual} rows but expected {expected}"))]
+    ColumnLengthMismatch {
+        col: usize,
+        expected: usize,
+        actual: usize,
+    },
+    #[snafu(display("trace length {len} is not a power of two"))]
+    LengthNotPowerOfTwo { len: usize },
+    #[snafu(display("trace length {len} is less than the minimum of {MIN_TRACE_LEN}"))]
+    TooShort { len: usize },
+}
+
+/// Shape of the execution trace a proof claims to have been generated for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceInfo {
+    pub trace_len: usize,
+    pub num_base_columns: usize,
+    pub num_extension_columns: usize,
+}
+
+/// Errors that are returned when a proof's [`TraceInfo`] doesn't match the AIR
+#[derive(Debug, Snafu)]
+pub enum TraceInfoMismatch {
+    #[snafu(display("expected {expected} base columns but the proof opens {actual}"))]
+    NumBaseColumns { expected: usize, actual: usize },
+    #[snafu(display("expected {expected} extension columns but the proof opens {actual}"))]
+    NumExtensionColumns { expected: usize, actual: usize },
+    #[snafu(display("trace length {len} is not a power of two"))]
+    LengthNotPowerOfTwo { len: usize },
+    #[snafu(display("trace length {len} is less than the minimum of {MIN_TRACE_LEN}"))]
+    TooShort { len: usize },
+}
+
+/// Trace openings at the FRI query positions.
+///
+/// The base, extension and composition traces are opened against their own
+/// commitments. These can't be merged into a single Merkle tree since each
+/// commitment is absorbed into the transcript before the randomness used to
+/// build the next trace is drawn i.e. extension columns depend on challenges
+/// drawn after the base trace commitment. Composition trace values and their
+/// proof are omitted if the proof carries the composition coefficients directly.
+///
+/// The three openings share query positions but not hashes. Each tree commits
+/// to different rows so its authentication path nodes are distinct digests and
+/// the positions themselves are redrawn by the verifier rather than serialized.
+/// Deduplicating hashes across the openings would leave the proof the same
+/// size. Shrinking the openings means packing more rows into each leaf (see
+/// [`PackedMatrixMerkleTree`](crate::merkle::PackedMatrixMerkleTree)).
 pub struct Queries<C: Stark> {
     pub base_trace_values: Vec<C::Fp>,
     pub extension_trace_values: Vec<C::Fq>,
     pub composition_trace_values: Vec<C::Fq>,
     pub base_trace_proof: <C::MerkleTree as MerkleTree>::Proof,
     pub extension_trace_proof: Option<<C::MerkleTree as MerkleTree>::Proof>,
-    pub composition_trace_proof: <C::MerkleTree as MerkleTree>::Proof,
+    pub composition_trace_proof: Option<<C::MerkleTree as MerkleTree>::Proof>,
 }
 
 impl<C: Stark> CanonicalSerialize for Queries<C> {
@@ -115,44 +248,53 @@ impl<C: Stark> Queries<C> {
     pub fn new(
         base_trace_lde: &Matrix<C::Fp>,
         extension_trace_lde: Option<&Matrix<C::Fq>>,
-        composition_trace_lde: &Matrix<C::Fq>,
+        composition_trace_lde: Option<&Matrix<C::Fq>>,
         base_tree: &C::MerkleTree,
         extension_tree: Option<&C::MerkleTree>,
-        composition_tree: &C::MerkleTree,
+        composition_tree: Option<&C::MerkleTree>,
         positions: &[usize],
-    ) -> Self {
-        let base_trace_proof = MatrixMerkleTree::<C::Fp>::prove_rows(base_tree, positions).unwrap();
-        let extension_trace_proof = extension_tree.map(|extension_tree| {
-            MatrixMerkleTree::<C::Fq>::prove_rows(extension_tree, positions).unwrap()
-        });
-        let composition_trace_proof =
-            MatrixMerkleTree::<C::Fq>::prove_rows(composition_tree, positions).unwrap();
+    ) -> Result<Self, merkle::Error> {
+        let base_trace_proof = MatrixMerkleTree::<C::Fp>::prove_rows(base_tree, positions)?;
+        let extension_trace_proof = extension_tree
+            .map(|extension_tree| MatrixMerkleTree::<C::Fq>::prove_rows(extension_tree, positions))
+            .transpose()?;
+        let composition_trace_proof = composition_tree
+            .map(|composition_tree| {
+                MatrixMerkleTree::<C::Fq>::prove_rows(composition_tree, positions)
+            })
+            .transpose()?;
 
         let mut base_trace_values = Vec::new();
         let mut extension_trace_values = Vec::new();
         let mut composition_trace_values = Vec::new();
         for &position in positions {
             // execution trace
-            let base_trace_row = base_trace_lde.get_row(position).unwrap();
+            let out_of_bounds = || merkle::Error::LeafIndexOutOfBounds {
+                i: position,
+                n: base_trace_lde.num_rows(),
+            };
+            let base_trace_row = base_trace_lde.get_row(position).ok_or_else(out_of_bounds)?;
             base_trace_values.extend(base_trace_row);
 
             if let Some(extension_trace_lde) = extension_trace_lde {
                 // TODO: suport ark DomainCoeff on evaluate_at
-                let extension_trace_row = extension_trace_lde.get_row(position).unwrap();
-                extension_trace_values.extend(extension_trace_row);
+                let extension_trace_row = extension_trace_lde.get_row(position);
+                extension_trace_values.extend(extension_trace_row.ok_or_else(out_of_bounds)?);
             }
 
             // composition trace
-            let composition_trace_row = composition_trace_lde.get_row(position).unwrap();
-            composition_trace_values.extend(composition_trace_row);
+            if let Some(composition_trace_lde) = composition_trace_lde {
+                let composition_trace_row = composition_trace_lde.get_row(position);
+                composition_trace_values.extend(composition_trace_row.ok_or_else(out_of_bounds)?);
+            }
         }
-        Self {
+        Ok(Self {
             base_trace_values,
             extension_trace_values,
             composition_trace_values,
             base_trace_proof,
             extension_trace_proof,
             composition_trace_proof,
-        }
+        })
     }
 }
@@ -1,4 +1,5 @@
 use crate::hash::Digest;
+use crate::prover::Instant;
 use alloc::vec::Vec;
 use ark_ff::BigInteger;
 use ark_ff::FftField;
@@ -28,7 +29,6 @@ use std::fmt::Debug;
 use std::iter::zip;
 use std::ops::Deref;
 use std::ops::DerefMut;
-use crate::prover::Instant;
 
 pub struct Timer<'a> {
     name: &'a str,
@@ -299,6 +299,13 @@ impl<Fp: Display, Fq: Display> Display for FieldVariant<Fp, Fq> {
     }
 }
 
+/// Integers convert to base field constants
+impl<Fp: From<u64>, Fq> From<u64> for FieldVariant<Fp, Fq> {
+    fn from(v: u64) -> Self {
+        Self::Fp(Fp::from(v))
+    }
+}
+
 impl<Fp: Zero, Fq: Zero> Zero for FieldVariant<Fp, Fq>
 where
     Self: Add<Self, Output = Self>,
@@ -457,6 +464,20 @@ unsafe impl Allocator for GpuAllocator {
     }
 }
 
+/// Returns true if the vec's memory can be shared with the GPU. A [`GpuVec`]
+/// built with [`vec_to_gpu_vec`] carries the [`GpuAllocator`] type but its
+/// memory came from the global allocator so it may not be page aligned.
+#[cfg_attr(
+    not(all(target_arch = "aarch64", target_os = "macos")),
+    allow(unused_variables)
+)]
+pub fn is_gpu_accessible<T>(v: &GpuVec<T>) -> bool {
+    #[cfg(all(target_arch = "aarch64", target_os = "macos"))]
+    return v.capacity() == 0 || v.as_ptr() as usize % page_aligned_allocator::PAGE_SIZE == 0;
+    #[cfg(not(all(target_arch = "aarch64", target_os = "macos")))]
+    return true;
+}
+
 pub fn gpu_vec_to_vec<T>(v: GpuVec<T>) -> Vec<T> {
     let (ptr, length, capacity) = v.into_raw_parts();
     unsafe { Vec::from_raw_parts(ptr, length, capacity) }
@@ -475,7 +496,7 @@ mod page_aligned_allocator {
     use core::alloc::Layout;
     use core::ptr::NonNull;
 
-    const PAGE_SIZE: usize = 16384;
+    pub const PAGE_SIZE: usize = 16384;
 
     pub struct PageAlignedAllocator;
 
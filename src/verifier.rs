@@ -1,121 +1,470 @@
+use crate::air::lde_domain_size;
 use crate::air::AirConfig;
 use crate::challenges::Challenges;
+use crate::channel::TranscriptEvent;
+use crate::channel::TranscriptStep;
 use crate::channel::VerifierChannelArtifacts;
 use crate::composer::DeepCompositionCoeffs;
-use crate::constraints::AlgebraicItem;
-use crate::constraints::CompositionItem;
 use crate::fri;
 use crate::fri::FriVerifier;
+use crate::hash::Digest;
 use crate::hints::Hints;
+use crate::merkle;
 use crate::merkle::MatrixMerkleTree;
-use crate::random::draw_multiple;
-use crate::random::PublicCoin;
+use crate::merkle::MerkleTree;
+use crate::proof::CompositionCommitment;
+use crate::proof::MalformedProof;
+use crate::proof::PolicyViolation;
+use crate::proof::ProofPolicy;
+use crate::proof::ReplayedTranscript;
+use crate::prover::Instant;
 use crate::stark::Stark;
+use crate::trace::TraceInfo;
+use crate::trace::TraceInfoMismatch;
 use crate::utils::horner_evaluate;
-use crate::utils::FieldVariant;
 use crate::Air;
 use crate::Proof;
+use crate::ProofOptions;
 use alloc::collections::BTreeMap;
+use alloc::collections::BTreeSet;
 use alloc::vec::Vec;
+use ark_ff::batch_inversion;
+use ark_ff::FftField;
 use ark_ff::Field;
 use ark_ff::Zero;
+use ark_poly::domain::DomainCoeff;
 use ark_poly::EvaluationDomain;
+use core::iter::zip;
 use ministark_gpu::utils::bit_reverse_index;
+use ministark_gpu::GpuField;
 use snafu::Snafu;
 
-#[allow(clippy::too_many_lines)]
+/// Time spent in each stage of proof verification in nanoseconds
+///
+/// The clock is only read with the `std` feature. Without it the timings are
+/// `0` but Merkle paths are still counted.
+#[derive(Debug, Clone, Default)]
+pub struct VerifyStats {
+    /// Checking trace queries against the trace commitments
+    pub commitment_check_ns: u64,
+    /// Checking the constraint evaluations at the out-of-domain point
+    pub ood_check_ns: u64,
+    pub fri_ns: u64,
+    /// Number of Merkle paths checked for the trace and FRI layer commitments
+    pub merkle_paths_checked: usize,
+}
+
+/// Operation performed by the verifier. Transcript operations carry the
+/// values absorbed or drawn and checks carry the values they compare.
+///
+/// Checks are recorded once they pass and as a whole: the hashes of a Merkle
+/// path and the folds of a FRI query aren't recorded individually so a
+/// recursion witness has to recompute them from the recorded values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifierOp<F, D> {
+    AbsorbDigest {
+        step: TranscriptStep,
+        digest: D,
+    },
+    AbsorbElements {
+        step: TranscriptStep,
+        values: Vec<F>,
+    },
+    Draw {
+        step: TranscriptStep,
+        values: Vec<F>,
+    },
+    /// Absorbs the proof of work nonce (if there's grinding) then draws the
+    /// query positions
+    DrawQueries {
+        nonce: u64,
+        positions: Vec<usize>,
+    },
+    /// Composition polynomial evaluated at the out-of-domain point `z` from
+    /// the constraints. See [`verify_composition_consistency`]
+    OodConstraintEvaluation {
+        z: F,
+        value: F,
+    },
+    /// Rows opened against a trace commitment at the query positions. Base
+    /// trace values are lifted into the extension field.
+    TraceOpening {
+        step: TranscriptStep,
+        commitment: D,
+        rows: Vec<Vec<F>>,
+    },
+    /// DEEP composition polynomial evaluated at the query positions
+    DeepEvaluations {
+        values: Vec<F>,
+    },
+    /// Passing FRI verification of the layers and remainder absorbed at
+    /// [`TranscriptStep::Fri`] starting from the DEEP evaluations
+    FriQueries {
+        positions: Vec<usize>,
+        evaluations: Vec<F>,
+    },
+}
+
+impl<F, D> From<TranscriptEvent<F, D>> for VerifierOp<F, D> {
+    fn from(event: TranscriptEvent<F, D>) -> Self {
+        match event {
+            TranscriptEvent::AbsorbDigest { step, digest } => Self::AbsorbDigest { step, digest },
+            TranscriptEvent::AbsorbElements { step, values } => {
+                Self::AbsorbElements { step, values }
+            }
+            TranscriptEvent::Draw { step, values } => Self::Draw { step, values },
+            TranscriptEvent::DrawQueries { nonce, positions } => {
+                Self::DrawQueries { nonce, positions }
+            }
+        }
+    }
+}
+
+/// Ordered record of the operations the verifier performed e.g. to build a
+/// recursion witness for a proof or to audit its verification. Once the shape
+/// of the proof is checked the whole transcript is replayed and recorded. The
+/// checks that follow are recorded until one fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifierTrace<F, D> {
+    pub ops: Vec<VerifierOp<F, D>>,
+}
+
+impl<F, D> Default for VerifierTrace<F, D> {
+    fn default() -> Self {
+        Self { ops: Vec::new() }
+    }
+}
+
+impl<F, D> VerifierTrace<F, D> {
+    /// Appends the operation built by `op` if there's a trace to record to.
+    /// Operations are built lazily so verifying without a trace doesn't clone.
+    fn record(trace: &mut Option<&mut Self>, op: impl FnOnce() -> VerifierOp<F, D>) {
+        if let Some(trace) = trace {
+            trace.ops.push(op());
+        }
+    }
+}
+
+/// Verifies a deserialized proof
+///
+/// Verification needs the whole [`Proof`] in memory. The serialized proof isn't
+/// in transcript order (the FRI proof comes before the out-of-domain
+/// evaluations it depends on) and the Merkle openings are batched across all
+/// queries, so queries can't be checked one at a time as they're read.
 pub fn default_verify<S: Stark>(
     this: &S,
     proof: Proof<S>,
     required_security_bits: u32,
+    domain_separator: Option<&[u8]>,
 ) -> Result<VerifierChannelArtifacts<S::Fq>, VerificationError> {
-    use VerificationError::*;
+    verify_with_optional_stats(
+        this,
+        proof,
+        required_security_bits,
+        domain_separator,
+        None,
+        None,
+    )
+}
+
+/// Same as [`default_verify`] but also returns a record of the operations the
+/// verifier performed. The record is returned even if verification fails and
+/// ends at the last check that passed.
+pub fn default_verify_with_trace<S: Stark>(
+    this: &S,
+    proof: Proof<S>,
+    required_security_bits: u32,
+    domain_separator: Option<&[u8]>,
+) -> (
+    Result<VerifierChannelArtifacts<S::Fq>, VerificationError>,
+    VerifierTrace<S::Fq, S::Digest>,
+) {
+    let mut trace = VerifierTrace::default();
+    let result = verify_with_optional_stats(
+        this,
+        proof,
+        required_security_bits,
+        domain_separator,
+        None,
+        Some(&mut trace),
+    );
+    (result, trace)
+}
+
+/// Same as [`default_verify`] but also returns the time spent in each stage
+pub fn default_verify_with_stats<S: Stark>(
+    this: &S,
+    proof: Proof<S>,
+    required_security_bits: u32,
+    domain_separator: Option<&[u8]>,
+) -> Result<(VerifierChannelArtifacts<S::Fq>, VerifyStats), VerificationError> {
+    let mut stats = VerifyStats::default();
+    let artifacts = verify_with_optional_stats(
+        this,
+        proof,
+        required_security_bits,
+        domain_separator,
+        Some(&mut stats),
+        None,
+    )?;
+    Ok((artifacts, stats))
+}
 
+/// Same as [`default_verify`] but first checks the proof meets `policy` e.g.
+/// a protocol's minimum number of queries and grinding factor. See
+/// [`ProofPolicy::check`]
+pub fn default_verify_with_policy<S: Stark>(
+    this: &S,
+    proof: Proof<S>,
+    policy: &ProofPolicy,
+    required_security_bits: u32,
+    domain_separator: Option<&[u8]>,
+) -> Result<VerifierChannelArtifacts<S::Fq>, VerificationError> {
+    policy
+        .check(&proof)
+        .map_err(|source| VerificationError::PolicyViolation { source })?;
+    default_verify(this, proof, required_security_bits, domain_separator)
+}
+
+/// Same as [`default_verify`] but first checks the proof's base trace
+/// commitment is `trace_root` e.g. a root posted to a data availability layer.
+/// The root is over the trace LDE so it only matches if the trace was committed
+/// to with the same options and randomizers.
+pub fn default_verify_against_trace_root<S: Stark>(
+    this: &S,
+    proof: Proof<S>,
+    required_security_bits: u32,
+    trace_root: &S::Digest,
+) -> Result<VerifierChannelArtifacts<S::Fq>, VerificationError> {
+    if proof.base_trace_commitment != *trace_root {
+        return Err(VerificationError::TraceRootMismatch);
+    }
+    default_verify(this, proof, required_security_bits, None)
+}
+
+fn verify_with_optional_stats<S: Stark>(
+    this: &S,
+    proof: Proof<S>,
+    required_security_bits: u32,
+    domain_separator: Option<&[u8]>,
+    stats: Option<&mut VerifyStats>,
+    trace: Option<&mut VerifierTrace<S::Fq, S::Digest>>,
+) -> Result<VerifierChannelArtifacts<S::Fq>, VerificationError> {
+    use VerificationError::*;
     if proof.security_level_bits() < required_security_bits {
         return Err(InvalidProofSecurity);
     }
+    if lde_domain_size::<S::AirConfig>(proof.trace_len, proof.options).is_none() {
+        return Err(DomainTooLarge);
+    }
+    let air = Air::new(proof.trace_len, this.get_public_inputs(), proof.options);
+    verify_with_air(this, &air, proof, domain_separator, stats, trace)
+}
+
+/// Reusable verifier state for checking many proofs that share the same trace
+/// length and proof options. The AIR constraints and composition constraint
+/// are built once and shared between proofs.
+pub struct VerifierContext<S: Stark> {
+    air: Air<S::AirConfig>,
+}
+
+impl<S: Stark> VerifierContext<S> {
+    /// # Panics
+    ///
+    /// Panics if the LDE domain exceeds the two-adicity of the field.
+    pub fn new(
+        trace_len: usize,
+        public_inputs: <S::AirConfig as AirConfig>::PublicInputs,
+        options: ProofOptions,
+    ) -> Self {
+        Self {
+            air: Air::new(trace_len, public_inputs, options),
+        }
+    }
+
+    pub const fn air(&self) -> &Air<S::AirConfig> {
+        &self.air
+    }
+
+    /// Verifies a proof re-using the state of this context. Proofs are
+    /// accepted exactly when [`default_verify`] accepts them.
+    pub fn verify(
+        &mut self,
+        this: &S,
+        proof: Proof<S>,
+        required_security_bits: u32,
+        domain_separator: Option<&[u8]>,
+    ) -> Result<VerifierChannelArtifacts<S::Fq>, VerificationError> {
+        use VerificationError::*;
+        if proof.security_level_bits() < required_security_bits {
+            return Err(InvalidProofSecurity);
+        }
+        if proof.trace_len != self.air.trace_len() || proof.options != self.air.options() {
+            return Err(ContextMismatch);
+        }
+        self.air.set_public_inputs(this.get_public_inputs());
+        verify_with_air(this, &self.air, proof, domain_separator, None, None)
+    }
+}
+
+/// Stages are only timed if `stats` is provided so [`default_verify`] doesn't
+/// read the clock
+#[allow(clippy::too_many_lines)]
+fn verify_with_air<S: Stark>(
+    this: &S,
+    air: &Air<S::AirConfig>,
+    proof: Proof<S>,
+    domain_separator: Option<&[u8]>,
+    stats: Option<&mut VerifyStats>,
+    mut trace: Option<&mut VerifierTrace<S::Fq, S::Digest>>,
+) -> Result<VerifierChannelArtifacts<S::Fq>, VerificationError> {
+    use VerificationError::*;
+    let start_timer = || stats.is_some().then(Instant::now);
+    let elapsed_ns = |timer: Option<Instant>| timer.map_or(0, |timer| timer.elapsed_ns());
+
+    let (options, trace_len) = (proof.options, proof.trace_len);
+    if let Some((constraint, (column, offset))) = air.find_out_of_range_offset() {
+        return Err(OffsetOutOfRange {
+            constraint,
+            column,
+            offset,
+        });
+    }
+    let merkle_digest_bytes = S::MerkleTree::merkle_digest_bytes();
+    if options.merkle_digest_bytes != merkle_digest_bytes {
+        return Err(MerkleDigestBytesMismatch {
+            declared: options.merkle_digest_bytes,
+            actual: merkle_digest_bytes,
+        });
+    }
+
+    // reject malformed proofs (e.g. FRI layers for a different trace length)
+    // before any transcript or FRI work
+    proof
+        .check_well_formed(options)
+        .map_err(|source| MalformedProof { source })?;
+    let lde_domain_size = air.trace_len() * air.lde_blowup_factor();
+
+    let num_composition_columns = air.num_composition_columns();
+    let sends_composition_directly = match &proof.composition_trace_commitment {
+        CompositionCommitment::Root(_) => false,
+        CompositionCommitment::Coeffs(coeffs) => {
+            if coeffs.len() != air.composition_degree() + 1 {
+                return Err(InvalidCompositionCommitment);
+            }
+            true
+        }
+    };
+    if sends_composition_directly != options.sends_composition_directly(trace_len) {
+        return Err(InvalidCompositionCommitment);
+    }
+
+    // column counts aren't stored in the proof so derive them from the openings
+    // before replaying the transcript. Composition columns are fixed by the AIR
+    // so their openings give the number of rows. Without them the number of
+    // base columns is taken from the AIR and the Merkle proofs reject openings
+    // with a different number of rows.
+    let trace_queries = &proof.trace_queries;
+    let num_rows = if sends_composition_directly {
+        let num_base_values = trace_queries.base_trace_values.len();
+        num_base_values
+            .checked_div(air.num_base_columns())
+            .unwrap_or(0)
+    } else {
+        trace_queries.composition_trace_values.len() / num_composition_columns
+    };
+    let num_columns = |num_values: usize| num_values.checked_div(num_rows).unwrap_or(0);
+    let trace_info = TraceInfo {
+        trace_len,
+        num_base_columns: num_columns(trace_queries.base_trace_values.len()),
+        num_extension_columns: num_columns(trace_queries.extension_trace_values.len()),
+    };
+    S::AirConfig::check_trace_info(&trace_info, air.public_inputs())
+        .map_err(|source| InvalidTraceInfo { source })?;
+    if proof.aux_data != this.aux_data() {
+        return Err(AuxDataMismatch);
+    }
 
+    // the transcript only depends on the proof so it's replayed in full before
+    // checking any of the values drawn from it
+    let mut transcript_log = trace.is_some().then(Vec::new);
+    let ReplayedTranscript {
+        challenges,
+        composition_coeffs,
+        z,
+        deep_coeffs,
+        fri_alphas,
+        is_proof_of_work_valid,
+        query_positions,
+    } = proof.replay_transcript(this, air, domain_separator, transcript_log.as_mut());
+    if let (Some(trace), Some(log)) = (trace.as_mut(), transcript_log) {
+        trace.ops.extend(log.into_iter().map(VerifierOp::from));
+    }
     let Proof {
-        options,
         base_trace_commitment,
         extension_trace_commitment,
         composition_trace_commitment,
         execution_trace_ood_evals,
         composition_trace_ood_evals,
         trace_queries,
-        trace_len,
         fri_proof,
-        pow_nonce,
         ..
     } = proof;
 
-    let air = Air::new(trace_len, this.get_public_inputs(), options);
-    let mut public_coin = this.gen_public_coin(&air);
-
-    public_coin.reseed_with_digest(&base_trace_commitment);
-    let num_challenges = air.num_challenges();
-    let air_challenges = Challenges::new(draw_multiple(&mut public_coin, num_challenges));
+    let air_challenges = Challenges::new(challenges);
     let air_hints = air.gen_hints(&air_challenges);
+    if let Some(index) = air.find_private_hint(&air_hints) {
+        return Err(PrivateHintInConstraint { index });
+    }
 
-    let extension_trace_commitment = extension_trace_commitment.map(|commitment| {
-        public_coin.reseed_with_digest(&commitment);
-        commitment
-    });
-
-    let num_composition_coeffs = air.num_composition_constraint_coeffs();
-    let composition_coeffs = draw_multiple(&mut public_coin, num_composition_coeffs);
-    public_coin.reseed_with_digest(&composition_trace_commitment);
-
-    let z = public_coin.draw();
-    let ood_evals = [
-        execution_trace_ood_evals.clone(),
-        composition_trace_ood_evals.clone(),
-    ]
-    .concat();
-    public_coin.reseed_with_field_elements(&ood_evals);
+    let ood_timer = start_timer();
     // execution trace ood evaluation map
     let trace_ood_eval_map = air
         .trace_arguments()
         .into_iter()
         .zip(execution_trace_ood_evals)
         .collect::<BTreeMap<(usize, isize), S::Fq>>();
-    let calculated_ood_constraint_evaluation = ood_constraint_evaluation::<S::AirConfig>(
+    let composition_poly_coeffs = match &composition_trace_commitment {
+        CompositionCommitment::Root(_) => None,
+        CompositionCommitment::Coeffs(coeffs) => Some(&**coeffs),
+    };
+    verify_composition_consistency(
+        air,
         &composition_coeffs,
         &air_challenges,
         &air_hints,
         &trace_ood_eval_map,
-        &air,
+        &composition_trace_ood_evals,
+        composition_poly_coeffs,
         z,
-    );
-
-    let provided_ood_constraint_evaluation = horner_evaluate(&composition_trace_ood_evals, &z);
-
-    if calculated_ood_constraint_evaluation != provided_ood_constraint_evaluation {
-        return Err(InconsistentOodConstraintEvaluations);
-    }
+    )?;
+    VerifierTrace::record(&mut trace, || VerifierOp::OodConstraintEvaluation {
+        z,
+        value: horner_evaluate(&composition_trace_ood_evals, &z),
+    });
+    let ood_check_ns = elapsed_ns(ood_timer);
 
-    let deep_coeffs = this.gen_deep_coeffs(&mut public_coin, &air);
-    let fri_verifier = FriVerifier::<S::Fq, S::Digest, S::MerkleTree>::new(
-        &mut public_coin,
+    let num_fri_paths = fri_proof
+        .layers
+        .iter()
+        .map(|layer| layer.flattenend_rows.len() / usize::from(options.fri_folding_factor))
+        .sum::<usize>();
+    let fri_timer = start_timer();
+    let fri_verifier = FriVerifier::<S::Fq, S::Digest, S::MerkleTree>::with_folding_challenges(
         options.into_fri_options(),
         fri_proof,
         trace_len - 1,
+        fri_alphas,
     )?;
+    let mut fri_ns = elapsed_ns(fri_timer);
 
-    if options.grinding_factor != 0 {
-        if !public_coin.verify_proof_of_work(options.grinding_factor, pow_nonce) {
-            return Err(FriProofOfWork);
-        }
-        public_coin.reseed_with_int(pow_nonce);
+    if !is_proof_of_work_valid {
+        return Err(FriProofOfWork);
     }
 
-    let lde_domain_size = air.trace_len() * air.lde_blowup_factor();
-    let query_positions =
-        Vec::from_iter(public_coin.draw_queries(options.num_queries.into(), lde_domain_size));
-
     let base_trace_rows = trace_queries
         .base_trace_values
-        .chunks(S::AirConfig::NUM_BASE_COLUMNS)
+        .chunks(air.num_base_columns())
         .collect::<Vec<_>>();
     let extension_trace_rows = if S::AirConfig::NUM_EXTENSION_COLUMNS == 0 {
         Vec::new()
@@ -126,11 +475,25 @@ pub fn default_verify<S: Stark>(
             .collect::<Vec<_>>()
     };
 
-    let composition_trace_rows = trace_queries
-        .composition_trace_values
-        .chunks(air.ce_blowup_factor())
+    let composition_trace_values = match &composition_trace_commitment {
+        CompositionCommitment::Root(_) => trace_queries.composition_trace_values,
+        CompositionCommitment::Coeffs(coeffs) => {
+            let lde_domain = air.lde_domain();
+            query_positions
+                .iter()
+                .flat_map(|&position| {
+                    let x = lde_domain.element(bit_reverse_index(lde_domain_size, position));
+                    composition_column_evaluations(coeffs, num_composition_columns, x.into())
+                })
+                .collect()
+        }
+    };
+    let composition_trace_rows = composition_trace_values
+        .chunks(num_composition_columns)
         .collect::<Vec<&[S::Fq]>>();
 
+    let commitment_timer = start_timer();
+    let mut num_trace_trees = 1;
     // base trace positions
     S::MerkleTree::verify_rows(
         &base_trace_commitment,
@@ -138,29 +501,66 @@ pub fn default_verify<S: Stark>(
         &base_trace_rows,
         trace_queries.base_trace_proof,
     )
-    .map_err(|_| BaseTraceQueryDoesNotMatchCommitment)?;
+    .map_err(|source| BaseTraceQueryDoesNotMatchCommitment { source })?;
+    VerifierTrace::record(&mut trace, || VerifierOp::TraceOpening {
+        step: TranscriptStep::BaseTrace,
+        commitment: base_trace_commitment.clone(),
+        rows: base_trace_rows
+            .iter()
+            .map(|row| row.iter().copied().map(S::Fq::from).collect())
+            .collect(),
+    });
 
     if let Some(extension_trace_commitment) = extension_trace_commitment {
+        num_trace_trees += 1;
+        let extension_trace_proof = trace_queries
+            .extension_trace_proof
+            .ok_or(merkle::Error::InvalidProof)
+            .map_err(|source| ExtensionTraceQueryDoesNotMatchCommitment { source })?;
         S::MerkleTree::verify_rows(
             &extension_trace_commitment,
             &query_positions,
             &extension_trace_rows,
-            trace_queries.extension_trace_proof.unwrap(),
+            extension_trace_proof,
         )
-        .map_err(|_| ExtensionTraceQueryDoesNotMatchCommitment)?;
+        .map_err(|source| ExtensionTraceQueryDoesNotMatchCommitment { source })?;
+        VerifierTrace::record(&mut trace, || VerifierOp::TraceOpening {
+            step: TranscriptStep::ExtensionTrace,
+            commitment: extension_trace_commitment,
+            rows: extension_trace_rows
+                .iter()
+                .map(|row| row.to_vec())
+                .collect(),
+        });
     }
 
     // composition trace positions
-    S::MerkleTree::verify_rows(
-        &composition_trace_commitment,
-        &query_positions,
-        &composition_trace_rows,
-        trace_queries.composition_trace_proof,
-    )
-    .map_err(|_| CompositionTraceQueryDoesNotMatchCommitment)?;
+    if let CompositionCommitment::Root(composition_trace_root) = &composition_trace_commitment {
+        num_trace_trees += 1;
+        let composition_trace_proof = trace_queries
+            .composition_trace_proof
+            .ok_or(merkle::Error::InvalidProof)
+            .map_err(|source| CompositionTraceQueryDoesNotMatchCommitment { source })?;
+        S::MerkleTree::verify_rows(
+            composition_trace_root,
+            &query_positions,
+            &composition_trace_rows,
+            composition_trace_proof,
+        )
+        .map_err(|source| CompositionTraceQueryDoesNotMatchCommitment { source })?;
+        VerifierTrace::record(&mut trace, || VerifierOp::TraceOpening {
+            step: TranscriptStep::CompositionTrace,
+            commitment: composition_trace_root.clone(),
+            rows: composition_trace_rows
+                .iter()
+                .map(|row| row.to_vec())
+                .collect(),
+        });
+    }
+    let commitment_check_ns = elapsed_ns(commitment_timer);
 
     let deep_evaluations = deep_composition_evaluations(
-        &air,
+        air,
         &query_positions,
         &deep_coeffs,
         &base_trace_rows,
@@ -171,8 +571,29 @@ pub fn default_verify<S: Stark>(
         z,
     );
 
+    VerifierTrace::record(&mut trace, || VerifierOp::DeepEvaluations {
+        values: deep_evaluations.clone(),
+    });
+
+    let fri_timer = start_timer();
+    verify_deep_fri_consistency(&fri_verifier, &query_positions, &deep_evaluations)?;
+
     let fri_alphas = fri_verifier.layer_alphas.clone();
     fri_verifier.verify(&query_positions, &deep_evaluations)?;
+    VerifierTrace::record(&mut trace, || VerifierOp::FriQueries {
+        positions: query_positions.clone(),
+        evaluations: deep_evaluations.clone(),
+    });
+    fri_ns += elapsed_ns(fri_timer);
+
+    if let Some(stats) = stats {
+        *stats = VerifyStats {
+            commitment_check_ns,
+            ood_check_ns,
+            fri_ns,
+            merkle_paths_checked: num_trace_trees * query_positions.len() + num_fri_paths,
+        };
+    }
 
     Ok(VerifierChannelArtifacts {
         air_challenges,
@@ -187,19 +608,136 @@ pub fn default_verify<S: Stark>(
 pub enum VerificationError {
     #[snafu(display("proof params do not satisfy security requirements"))]
     InvalidProofSecurity,
-    #[snafu(display("constraint evaluations at the out-of-domain point are inconsistent"))]
-    InconsistentOodConstraintEvaluations,
+    #[snafu(display("proof doesn't meet the policy: {source}"))]
+    PolicyViolation { source: PolicyViolation },
+    #[snafu(display("proof's LDE domain exceeds the field's two-adicity"))]
+    DomainTooLarge,
+    #[snafu(display("proof's trace does not match the AIR: {source}"))]
+    InvalidTraceInfo { source: TraceInfoMismatch },
+    #[snafu(display("auxiliary data in the proof does not match the claim"))]
+    AuxDataMismatch,
+    #[snafu(display("constraint references private hint {index}"))]
+    PrivateHintInConstraint { index: usize },
+    #[snafu(display(
+        "constraint {constraint} reads column {column} at offset {offset} which wraps around \
+         the trace"
+    ))]
+    OffsetOutOfRange {
+        constraint: usize,
+        column: usize,
+        offset: isize,
+    },
+    #[snafu(display("proof is malformed: {source}"))]
+    MalformedProof { source: MalformedProof },
+    #[snafu(display(
+        "proof declares Merkle digests of {declared:?} bytes but the Merkle tree truncates to \
+         {actual:?}"
+    ))]
+    MerkleDigestBytesMismatch {
+        declared: Option<u8>,
+        actual: Option<u8>,
+    },
+    #[snafu(display("composition trace is inconsistent with the constraints"))]
+    CompositionInconsistent,
     #[snafu(context(false))]
     #[snafu(display("fri verification failed: {source}"))]
     FriVerification { source: fri::VerificationError },
-    #[snafu(display("query does not resolve to the base trace commitment"))]
-    BaseTraceQueryDoesNotMatchCommitment,
-    #[snafu(display("query does not resolve to the extension trace commitment"))]
-    ExtensionTraceQueryDoesNotMatchCommitment,
-    #[snafu(display("query does not resolve to the composition trace commitment"))]
-    CompositionTraceQueryDoesNotMatchCommitment,
+    #[snafu(display("query does not resolve to the base trace commitment: {source}"))]
+    BaseTraceQueryDoesNotMatchCommitment { source: merkle::Error },
+    #[snafu(display("query does not resolve to the extension trace commitment: {source}"))]
+    ExtensionTraceQueryDoesNotMatchCommitment { source: merkle::Error },
+    #[snafu(display("query does not resolve to the composition trace commitment: {source}"))]
+    CompositionTraceQueryDoesNotMatchCommitment { source: merkle::Error },
+    #[snafu(display("composition commitment does not match the proof options"))]
+    InvalidCompositionCommitment,
     #[snafu(display("insufficient proof of work on fri commitments"))]
     FriProofOfWork,
+    #[snafu(display("DEEP evaluation does not match FRI layer 0 at position {position}"))]
+    DeepFriMismatch { position: usize },
+    #[snafu(display("proof's trace length or options differ from the verifier context"))]
+    ContextMismatch,
+    #[snafu(display("proof's base trace commitment differs from the expected root"))]
+    TraceRootMismatch,
+}
+
+/// Evaluates each composition trace column at `x` given the coefficients of
+/// the composition polynomial. Column `j` holds every coefficient with index
+/// `j` modulo the number of columns.
+fn composition_column_evaluations<F: Field>(coeffs: &[F], num_columns: usize, x: F) -> Vec<F> {
+    (0..num_columns)
+        .map(|j| {
+            let column_coeffs = coeffs.iter().skip(j).step_by(num_columns);
+            column_coeffs.rfold(F::zero(), |result, coeff| result * x + coeff)
+        })
+        .collect()
+}
+
+/// Checks the DEEP composition evaluations computed from the trace queries
+/// match the values FRI layer 0 opens at the same positions. This binds the
+/// DEEP composition polynomial to the polynomial FRI proves is low degree.
+/// Proofs without FRI layers are checked against the remainder by FRI instead.
+pub fn verify_deep_fri_consistency<F, D, M>(
+    fri_verifier: &FriVerifier<F, D, M>,
+    positions: &[usize],
+    deep_evaluations: &[F],
+) -> Result<(), VerificationError>
+where
+    F: GpuField + Field + DomainCoeff<F::FftField>,
+    F::FftField: FftField,
+    D: Digest,
+    M: MatrixMerkleTree<F, Root = D>,
+{
+    let Some(layer_values) = fri_verifier.first_layer_query_values(positions) else {
+        return Ok(());
+    };
+    match zip(deep_evaluations, &layer_values).position(|(a, b)| a != b) {
+        Some(i) => Err(VerificationError::DeepFriMismatch {
+            position: positions[i],
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Checks the composition trace the prover committed to is the composition of
+/// the AIR's constraints. The constraints are evaluated at the out-of-domain
+/// point `z` from the execution trace's out-of-domain evaluations and must
+/// match the composition trace's out-of-domain evaluations recombined as
+/// `sum_i z^i * c_i(z^n)`. If the composition polynomial's coefficients are
+/// sent in the proof (`composition_poly_coeffs`) the column evaluations must
+/// also match the coefficients. FRI only shows the composition trace is low
+/// degree so this is what binds it to the constraints.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_composition_consistency<A: AirConfig>(
+    air: &Air<A>,
+    composition_coeffs: &[A::Fq],
+    challenges: &Challenges<A::Fq>,
+    hints: &Hints<A::Fq>,
+    trace_ood_eval_map: &BTreeMap<(usize, isize), A::Fq>,
+    composition_trace_ood_evals: &[A::Fq],
+    composition_poly_coeffs: Option<&[A::Fq]>,
+    z: A::Fq,
+) -> Result<(), VerificationError> {
+    let calculated_evaluation = ood_constraint_evaluation(
+        composition_coeffs,
+        challenges,
+        hints,
+        trace_ood_eval_map,
+        air,
+        z,
+    );
+    let provided_evaluation = horner_evaluate(composition_trace_ood_evals, &z);
+    if calculated_evaluation != provided_evaluation {
+        return Err(VerificationError::CompositionInconsistent);
+    }
+    if let Some(coeffs) = composition_poly_coeffs {
+        let num_columns = air.num_composition_columns();
+        let z_n = z.pow([num_columns as u64]);
+        let calculated_ood_evals = composition_column_evaluations(coeffs, num_columns, z_n);
+        if calculated_ood_evals != composition_trace_ood_evals {
+            return Err(VerificationError::CompositionInconsistent);
+        }
+    }
+    Ok(())
 }
 
 pub fn ood_constraint_evaluation<A: AirConfig>(
@@ -210,28 +748,16 @@ pub fn ood_constraint_evaluation<A: AirConfig>(
     air: &Air<A>,
     x: A::Fq,
 ) -> A::Fq {
-    use AlgebraicItem::*;
-    use CompositionItem::*;
-    air.composition_constraint()
-        .graph_eval(&mut |leaf| match leaf {
-            Item(X) => FieldVariant::Fq(x),
-            &Item(Constant(v)) => v,
-            &Item(Challenge(i)) => FieldVariant::Fq(challenges[i]),
-            &Item(Hint(i)) => FieldVariant::Fq(hints[i]),
-            &Item(Periodic(col)) => {
-                let trace_len = air.trace_len();
-                let point = x.pow([(trace_len / col.interval_size()) as u64]);
-                let coeffs = col
-                    .coeffs()
-                    .iter()
-                    .map(FieldVariant::as_fq)
-                    .collect::<Vec<_>>();
-                FieldVariant::Fq(horner_evaluate(&coeffs, &point))
-            }
-            &Item(Trace(i, j)) => FieldVariant::Fq(trace_ood_eval_map[&(i, j)]),
-            &CompositionCoeff(i) => FieldVariant::Fq(composition_coefficients[i]),
-        })
-        .as_fq()
+    A::eval_constraint_at_point(
+        air.composition_constraint(),
+        air.trace_len(),
+        challenges,
+        hints,
+        &air.fixed_columns(),
+        composition_coefficients,
+        trace_ood_eval_map,
+        x,
+    )
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -249,7 +775,7 @@ pub fn deep_composition_evaluations<A: AirConfig>(
     let trace_domain = air.trace_domain();
     let g = trace_domain.group_gen();
     let g_inv = trace_domain.group_gen_inv();
-    let z_n = z.pow([air.ce_blowup_factor() as u64]);
+    let z_n = z.pow([air.num_composition_columns() as u64]);
     let lde_domain = air.lde_domain();
     let lde_domain_size = lde_domain.size();
     let xs = query_positions
@@ -257,33 +783,67 @@ pub fn deep_composition_evaluations<A: AirConfig>(
         .map(|pos| lde_domain.element(bit_reverse_index(lde_domain_size, *pos)))
         .collect::<Vec<A::Fp>>();
 
+    // Each query point only has one denominator per distinct offset plus one for
+    // the composition trace so these are batch inverted up front rather than
+    // dividing once per column.
+    let offsets = execution_trace_ood_evals_map
+        .keys()
+        .map(|&(_, offset)| offset)
+        .collect::<BTreeSet<isize>>();
+    let shifted_zs = offsets
+        .iter()
+        .map(|&offset| {
+            let shift = if offset >= 0 { g } else { g_inv }.pow([offset.unsigned_abs() as u64]);
+            z * shift
+        })
+        .chain([z_n])
+        .collect::<Vec<A::Fq>>();
+    let mut denominator_invs = xs
+        .iter()
+        .flat_map(|&x| {
+            shifted_zs
+                .iter()
+                .map(move |&shifted_z| A::Fq::from(x) - shifted_z)
+        })
+        .collect::<Vec<A::Fq>>();
+    batch_inversion(&mut denominator_invs);
+    let offset_idxs = execution_trace_ood_evals_map
+        .keys()
+        .map(|(_, offset)| offsets.iter().position(|o| o == offset).unwrap())
+        .collect::<Vec<usize>>();
+
     let mut evals = vec![A::Fq::zero(); query_positions.len()];
 
-    let num_columns = A::NUM_BASE_COLUMNS + A::NUM_EXTENSION_COLUMNS;
-    let base_column_range = 0..A::NUM_BASE_COLUMNS;
-    let extension_column_range = A::NUM_BASE_COLUMNS..num_columns;
+    let num_base_columns = air.num_base_columns();
+    let num_columns = num_base_columns + A::NUM_EXTENSION_COLUMNS;
+    let base_column_range = 0..num_base_columns;
+    let extension_column_range = num_base_columns..num_columns;
 
-    for (i, (&x, eval)) in xs.iter().zip(&mut evals).enumerate() {
+    for (i, (invs, eval)) in denominator_invs
+        .chunks(shifted_zs.len())
+        .zip(&mut evals)
+        .enumerate()
+    {
         // execution trace
-        for (j, ((column, offset), ood_eval)) in execution_trace_ood_evals_map.iter().enumerate() {
+        for (j, ((column, _), ood_eval)) in execution_trace_ood_evals_map.iter().enumerate() {
             let trace_value = if base_column_range.contains(column) {
                 A::Fq::from(base_trace_rows[i][*column])
             } else if extension_column_range.contains(column) {
-                extension_trace_rows[i][column - A::NUM_BASE_COLUMNS]
+                extension_trace_rows[i][column - num_base_columns]
             } else {
                 panic!("column {column} does not exist");
             };
 
             let alpha = composition_coeffs.execution_trace[j];
-            let shift = if *offset >= 0 { g } else { g_inv }.pow([offset.unsigned_abs() as u64]);
-            *eval += alpha * (trace_value - ood_eval) / (A::Fq::from(x) - z * shift);
+            *eval += alpha * (trace_value - ood_eval) * invs[offset_idxs[j]];
         }
 
         // composition trace
+        let composition_inv = invs[offsets.len()];
         for (j, value) in composition_trace_rows[i].iter().enumerate() {
             let alpha = composition_coeffs.composition_trace[j];
             let ood_eval = composition_trace_ood_evals[j];
-            *eval += alpha * (*value - ood_eval) / (A::Fq::from(x) - z_n);
+            *eval += alpha * (*value - ood_eval) * composition_inv;
         }
     }
 
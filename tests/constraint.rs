@@ -10,6 +10,9 @@ use ark_poly::EvaluationDomain;
 use ark_poly::Polynomial;
 use ark_poly::Radix2EvaluationDomain;
 use ark_std::rand::seq::SliceRandom;
+use ministark::constraints::extension_column;
+use ministark::constraints::row_divisor;
+use ministark::constraints::row_selector;
 use ministark::constraints::AlgebraicItem;
 use ministark::constraints::Constraint;
 use ministark::constraints::ExecutionTraceColumn;
@@ -23,9 +26,12 @@ use ministark::utils::GpuAllocator;
 use ministark::Matrix;
 use ministark::StarkExtensionOf;
 use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use ministark_gpu::fields::p18446744069414584321::ark::Fq3;
 use ministark_gpu::GpuFftField;
 use ministark_gpu::GpuField;
 use num_traits::Pow;
+use std::collections::BTreeMap;
+use std::iter::zip;
 
 // TODO: handle
 // #[test]
@@ -60,6 +66,137 @@ fn constraint_degree() {
     assert_eq!(expected_degree, actual_degree);
 }
 
+#[test]
+fn trace_degree_ignores_x_and_challenges() {
+    use AlgebraicItem::*;
+    let constraint = Constraint::<()>::new(
+        (Trace(0, 0) * Trace(1, 1) - Challenge(0)) * Trace(2, 0) * X.pow(5) + Trace(0, 0),
+    );
+
+    assert_eq!(3, constraint.trace_degree());
+}
+
+#[test]
+fn referenced_columns_are_sorted_and_deduplicated() {
+    let constraint =
+        Constraint::<Fp>::new(2.next() * 0.curr() + 0.curr() - 1.offset(-1) * 2.next());
+
+    assert_eq!(
+        vec![(0, 0), (1, -1), (2, 1)],
+        constraint.referenced_columns()
+    );
+}
+
+#[test]
+fn constraint_template_is_instantiated_for_each_column() {
+    let one = AlgebraicItem::Constant(Fp::one());
+    let boolean = Constraint::new(0.curr() * (0.curr() - one));
+
+    let instances = [3, 7, 12].map(|column| boolean.remap_columns(&BTreeMap::from([(0, column)])));
+
+    for (instance, column) in zip(&instances, [3, 7, 12]) {
+        assert_eq!(vec![(column, 0)], instance.referenced_columns());
+        assert_eq!(2, instance.trace_degree());
+    }
+}
+
+#[test]
+fn composition_columns_are_not_rounded_to_power_of_two() {
+    use AlgebraicItem::*;
+    let trace_len = 2usize.pow(10);
+    let vanishing = X.pow(trace_len) - Constant(Fp::one());
+    let high_degree = Constraint::new((0.curr().pow(8) - 1.curr()) / vanishing.clone());
+    let low_degree = Constraint::new((0.curr() * 0.curr() - 0.curr()) / vanishing);
+
+    assert_eq!(8, high_degree.blowup_factor(trace_len));
+    assert_eq!(7, high_degree.num_columns(trace_len));
+    assert_eq!(1, low_degree.num_columns(trace_len));
+}
+
+#[test]
+fn interior_row_boundary_constraint() {
+    use AlgebraicItem::*;
+    let row = 5;
+    let matrix = gen_fib_matrix::<Fp>(16);
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(16).unwrap();
+    let x = trace_domain.element(row);
+    let eval_at_row = |value: Fp| {
+        let constraint = Constraint::new(
+            (1.curr() - Constant(FieldVariant::Fp(value))) / row_divisor(&trace_domain, row),
+        );
+        constraint.check(&mut |leaf| match leaf {
+            X => FieldVariant::Fp(x),
+            &Constant(v) => v,
+            &Trace(col, 0) => FieldVariant::Fp(matrix[col][row]),
+            _ => unreachable!(),
+        })
+    };
+
+    assert!(eval_at_row(matrix[1][row]).is_some());
+    assert!(eval_at_row(matrix[1][row] + Fp::one()).is_none());
+}
+
+#[test]
+fn transition_constraint_matches_hand_written_divisor() {
+    use AlgebraicItem::*;
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(16).unwrap();
+    let one = Constant(FieldVariant::<Fp, Fp>::Fp(Fp::one()));
+    let last_trace_x = Constant(FieldVariant::Fp(trace_domain.element(15)));
+    let expression = 1.next() - 0.curr() * 1.curr();
+
+    let constraint = Constraint::transition(expression.clone(), &trace_domain);
+
+    let expected = expression * ((X - last_trace_x) / (X.pow(16) - one));
+    assert!(expected == *constraint);
+}
+
+#[test]
+fn row_selector_selects_rows_in_residue_class() {
+    use AlgebraicItem::*;
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(16).unwrap();
+    let selector = row_selector::<Fp, Fp>(&trace_domain, 4, 1);
+
+    for (row, x) in trace_domain.elements().enumerate() {
+        let value = selector.eval(&mut |leaf| match leaf {
+            X => FieldVariant::Fp(x),
+            &Constant(v) => v,
+            _ => unreachable!(),
+        });
+        let expected = if row % 4 == 1 { Fp::one() } else { Fp::zero() };
+        assert_eq!(FieldVariant::Fp(expected), value);
+    }
+}
+
+#[test]
+fn extension_column_recombines_coordinates() {
+    use AlgebraicItem::*;
+    let mut rng = ark_std::test_rng();
+    let values = (0..8).map(|_| Fq3::rand(&mut rng)).collect::<Vec<_>>();
+    let mut matrix = gen_fib_matrix::<Fp>(8);
+    matrix.append_extension_column(&values).unwrap();
+    let column = extension_column::<Fp, Fq3>(2, 0);
+
+    assert_eq!(5, matrix.num_cols());
+    for (row, value) in values.iter().enumerate() {
+        let result = column.eval(&mut |leaf| match leaf {
+            &Trace(col, 0) => FieldVariant::Fp(matrix.0[col][row]),
+            &Constant(v) => v,
+            _ => unreachable!(),
+        });
+        assert_eq!(FieldVariant::Fq(*value), result);
+    }
+}
+
+#[test]
+fn integers_convert_to_base_field_constants() {
+    let item = AlgebraicItem::<FieldVariant<Fp, Fq3>>::from(8192);
+
+    assert_eq!(
+        AlgebraicItem::Constant(FieldVariant::Fp(Fp::from(8192u64))),
+        item
+    );
+}
+
 #[test]
 fn constraint_with_challenges() {
     // TODO: hints
@@ -74,6 +211,7 @@ fn constraint_with_challenges() {
             &Constant(v) => FieldVariant::Fp(v),
             &Hint(_) => unreachable!(),
             &Periodic(_) => todo!(),
+            Fixed(_) => unreachable!(),
             &Challenge(i) => FieldVariant::Fp(challenges[i]),
             &Trace(i, j) => {
                 assert_eq!(0, j);
@@ -144,6 +282,7 @@ fn constraint_multiplication() {
             Challenge(_) => unreachable!(),
             Hint(_) => unreachable!(),
             Periodic(_) => todo!(),
+            Fixed(_) => unreachable!(),
             &Constant(v) => v,
             &Trace(i, j) => {
                 assert_eq!(0, i, "for value {val}");
@@ -329,6 +468,44 @@ fn evaluate_zerofier_constraint() {
     assert_valid_over_transition_domain(trace_domain, constraint_eval_poly);
 }
 
+#[test]
+fn vanishing_polynomial_division_matches_naive_evaluation() {
+    use AlgebraicItem::*;
+    let n = 1024;
+    let blowup = 4;
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(n).unwrap();
+    let lde_domain = Radix2EvaluationDomain::<Fp>::new_coset(n * blowup, Fp::GENERATOR).unwrap();
+    let one = Constant(FieldVariant::Fp(Fp::one()));
+    let last_trace_x = Constant(FieldVariant::Fp(trace_domain.group_gen_inv()));
+    let constraint = Constraint::new(
+        (0.next() - 0.curr()) * ((X - last_trace_x) / (X.pow(n) - one))
+            + (0.curr() - one) / (X - last_trace_x)
+            + 0.curr() / (X.pow(n / 2) - one),
+    );
+    let mut rng = ark_std::test_rng();
+    let column = (0..n * blowup)
+        .map(|_| Fp::rand(&mut rng))
+        .collect::<Vec<Fp>>()
+        .to_vec_in(GpuAllocator);
+    let lde_matrix = Matrix::new(vec![column]);
+    let x_lde = lde_domain.elements().collect::<Vec<Fp>>();
+
+    let expected = evaluate_symbolic(lde_domain, blowup, &[], &[], &constraint, &lde_matrix);
+    let actual = ministark::eval_cpu::eval::<Fp, Fp>(
+        &constraint,
+        &[],
+        &[],
+        &[],
+        blowup,
+        Fp::GENERATOR,
+        &x_lde,
+        &[&lde_matrix[0]],
+        None,
+    );
+
+    assert_eq!(expected.0, actual.0);
+}
+
 fn assert_valid_over_transition_domain<F: GpuField + Field>(
     domain: Radix2EvaluationDomain<F::FftField>,
     poly_matrix: Matrix<F>,
@@ -371,6 +548,7 @@ fn evaluate_symbolic<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
             &Hint(i) => FieldVariant::Fq(hints[i]),
             &Challenge(i) => FieldVariant::Fq(challenges[i]),
             &Periodic(_col) => todo!(),
+            Fixed(_) => unreachable!(),
             &Trace(col_idx, offset) => {
                 let pos = (i as isize + blowup_factor * offset).rem_euclid(n as isize) as usize;
                 let column = &lde_matrix[col_idx];
@@ -0,0 +1,2174 @@
+#![feature(allocator_api)]
+use ark_ff::Field;
+use ark_ff::One;
+use ark_ff::UniformRand;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use ministark::aggregate::Aggregate;
+use ministark::air::AirConfig;
+use ministark::air::CompiledAir;
+use ministark::challenges::Challenges;
+use ministark::channel::TranscriptEvent;
+use ministark::channel::TranscriptStep;
+use ministark::composer::DeepCompositionCoeffs;
+use ministark::constraints::row_divisor;
+use ministark::constraints::AlgebraicItem;
+use ministark::constraints::Constraint;
+use ministark::constraints::ExecutionTraceColumn;
+use ministark::constraints::PeriodicColumn;
+use ministark::hash::HashFn;
+use ministark::hash::Keccak256HashFn;
+use ministark::hash::Sha256HashFn;
+use ministark::hints::Hints;
+use ministark::lookup::LookupArgument;
+use ministark::merkle::FlatMatrixCommitment;
+use ministark::merkle::MatrixMerkleTree;
+use ministark::merkle::MatrixMerkleTreeImpl;
+use ministark::merkle::TruncatedMatrixMerkleTree;
+use ministark::proof::CompositionCommitment;
+use ministark::proof::MalformedProof;
+use ministark::proof::PolicyViolation;
+use ministark::proof::ProofHeader;
+use ministark::proof::ProofPolicy;
+use ministark::prover::ProverContext;
+use ministark::prover::ProvingError;
+use ministark::random::KeccakPublicCoin;
+use ministark::random::PublicCoin;
+use ministark::random::PublicCoinImpl;
+use ministark::range_check::RangeCheckBuilder;
+use ministark::schema::ConstraintSchema;
+use ministark::schema::SchemaError;
+use ministark::schema::SchemaNode;
+use ministark::stark::Stark;
+use ministark::utils::tests::gen_fib_matrix;
+use ministark::utils::FieldVariant;
+use ministark::utils::GpuAllocator;
+use ministark::utils::SerdeOutput;
+use ministark::verifier::deep_composition_evaluations;
+use ministark::verifier::ood_constraint_evaluation;
+use ministark::verifier::VerificationError;
+use ministark::verifier::VerifierContext;
+use ministark::verifier::VerifierOp;
+use ministark::Air;
+use ministark::Matrix;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark::SecurityPreset;
+use ministark::Trace;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use ministark_gpu::utils::bit_reverse_index;
+use num_traits::Pow;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::convert::identity;
+use std::iter::repeat;
+use std::iter::zip;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+struct FibTrace(Matrix<Fp>);
+
+impl Trace for FibTrace {
+    type Fp = Fp;
+    type Fq = Fp;
+
+    fn base_columns(&self) -> &Matrix<Self::Fp> {
+        &self.0
+    }
+}
+
+ministark::air_columns! {
+    enum FibColumn {
+        base: [A, B],
+        extension: [],
+    }
+}
+
+struct FibAirConfig;
+
+impl AirConfig for FibAirConfig {
+    const NUM_BASE_COLUMNS: usize = FibColumn::NUM_BASE_COLUMNS;
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = Fp;
+
+    fn gen_hints(_trace_len: usize, result: &Fp, _: &Challenges<Fp>) -> Hints<Fp> {
+        Hints::new(vec![(0, *result)])
+    }
+
+    fn constraints(
+        trace_domain: &Radix2EvaluationDomain<Fp>,
+    ) -> Vec<Constraint<FieldVariant<Fp, Fp>>> {
+        use AlgebraicItem::*;
+        use FibColumn::*;
+        let trace_len = trace_domain.size();
+        let first_trace_x = Constant(FieldVariant::Fp(trace_domain.element(0)));
+        let last_trace_x = Constant(FieldVariant::Fp(trace_domain.element(trace_len - 1)));
+        let one = Constant(FieldVariant::Fp(Fp::one()));
+
+        let boundary_constraints = [A.curr() - one, B.curr() - one]
+            .into_iter()
+            .map(|constraint| constraint / (X - first_trace_x));
+
+        let transition_constraints = [
+            A.next() - (A.curr() + B.curr()),
+            B.next() - (A.next() + B.curr()),
+        ]
+        .into_iter()
+        .map(|constraint| constraint * ((X - last_trace_x) / (X.pow(trace_len) - one)));
+
+        let terminal_constraint = (B.curr() - Hint(0)) / (X - last_trace_x);
+
+        boundary_constraints
+            .chain(transition_constraints)
+            .chain([terminal_constraint])
+            .map(Constraint::new)
+            .collect()
+    }
+}
+
+/// Public coin a [`TestClaim`] can seed from the claim's statement
+trait TestCoin: PublicCoin<Field = Fp> {
+    fn from_seed(seed: &[u8]) -> Self;
+}
+
+impl TestCoin for PublicCoinImpl<Fp, Sha256HashFn> {
+    fn from_seed(seed: &[u8]) -> Self {
+        Self::new(Sha256HashFn::hash_chunks([seed]))
+    }
+}
+
+impl TestCoin for KeccakPublicCoin<Fp> {
+    fn from_seed(seed: &[u8]) -> Self {
+        Self::new(Keccak256HashFn::hash_chunks([seed]))
+    }
+}
+
+impl TestCoin for RecordingCoin {
+    fn from_seed(seed: &[u8]) -> Self {
+        Self::new(Sha256HashFn::hash_chunks([seed]))
+    }
+}
+
+/// Claim about the AIR `A` committed to with `M` over the transcript `C`.
+/// Tests override the [`Stark`] hooks they exercise with the `with_*` methods.
+struct TestClaim<
+    A: AirConfig,
+    M = MatrixMerkleTreeImpl<Sha256HashFn>,
+    C = PublicCoinImpl<Fp, Sha256HashFn>,
+    T = FibTrace,
+> {
+    public_inputs: A::PublicInputs,
+    generate_trace: fn(T) -> T,
+    randomizer_seed: Option<u64>,
+    grinding_budget: Option<u64>,
+    randomness_beacon: Option<Fp>,
+    is_cancelled: Option<Box<dyn Fn() -> bool + Send + Sync>>,
+    on_public_coin: Option<Box<dyn Fn(&mut C) + Send + Sync>>,
+    _phantom: PhantomData<fn() -> (A, M, T)>,
+}
+
+impl<A: AirConfig, M, C, T> TestClaim<A, M, C, T> {
+    fn new(public_inputs: A::PublicInputs) -> Self {
+        Self {
+            public_inputs,
+            generate_trace: identity,
+            randomizer_seed: None,
+            grinding_budget: None,
+            randomness_beacon: None,
+            is_cancelled: None,
+            on_public_coin: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Builds the trace from the witness with `generate_trace`
+    fn with_trace_generator(mut self, generate_trace: fn(T) -> T) -> Self {
+        self.generate_trace = generate_trace;
+        self
+    }
+
+    /// Draws randomizers from an RNG seeded with `seed`
+    fn with_randomizer_seed(mut self, seed: u64) -> Self {
+        self.randomizer_seed = Some(seed);
+        self
+    }
+
+    fn with_grinding_budget(mut self, budget: u64) -> Self {
+        self.grinding_budget = Some(budget);
+        self
+    }
+
+    fn with_randomness_beacon(mut self, beacon: Fp) -> Self {
+        self.randomness_beacon = Some(beacon);
+        self
+    }
+
+    fn with_cancellation(
+        mut self,
+        is_cancelled: impl Fn() -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.is_cancelled = Some(Box::new(is_cancelled));
+        self
+    }
+
+    /// Calls `f` on every public coin the claim generates
+    fn with_public_coin(mut self, f: impl Fn(&mut C) + Send + Sync + 'static) -> Self {
+        self.on_public_coin = Some(Box::new(f));
+        self
+    }
+}
+
+impl<A, M, C, T> Stark for TestClaim<A, M, C, T>
+where
+    A: AirConfig<Fp = Fp, Fq = Fp>,
+    A::PublicInputs: Send + Sync,
+    M: MatrixMerkleTree<Fp, Root = C::Digest>,
+    C: TestCoin,
+    T: Trace<Fp = Fp, Fq = Fp>,
+{
+    type Fp = Fp;
+    type Fq = Fp;
+    type AirConfig = A;
+    type Digest = C::Digest;
+    type PublicCoin = C;
+    type MerkleTree = M;
+    type Witness = T;
+    type Trace = T;
+
+    fn get_public_inputs(&self) -> A::PublicInputs {
+        self.public_inputs.clone()
+    }
+
+    fn generate_trace(&self, witness: T) -> T {
+        (self.generate_trace)(witness)
+    }
+
+    fn gen_public_coin(&self, air: &Air<A>) -> C {
+        let mut seed = Vec::new();
+        air.public_inputs().serialize_compressed(&mut seed).unwrap();
+        air.trace_len().serialize_compressed(&mut seed).unwrap();
+        air.options().serialize_compressed(&mut seed).unwrap();
+        let mut public_coin = C::from_seed(&seed);
+        if let Some(f) = &self.on_public_coin {
+            f(&mut public_coin);
+        }
+        public_coin
+    }
+
+    fn gen_randomizer_rng(&self) -> ChaCha20Rng {
+        match self.randomizer_seed {
+            Some(seed) => ChaCha20Rng::seed_from_u64(seed),
+            None => ChaCha20Rng::from_entropy(),
+        }
+    }
+
+    fn grinding_budget(&self) -> Option<u64> {
+        self.grinding_budget
+    }
+
+    fn randomness_beacon(&self) -> Option<Fp> {
+        self.randomness_beacon
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.is_cancelled
+            .as_ref()
+            .is_some_and(|is_cancelled| is_cancelled())
+    }
+}
+
+type FibClaim = TestClaim<FibAirConfig>;
+
+/// Same as [`FibAirConfig`] but the result is read three rows from the end
+/// and the last two rows are unconstrained so they can hold randomizers
+struct ZkFibAirConfig;
+
+impl AirConfig for ZkFibAirConfig {
+    const NUM_BASE_COLUMNS: usize = FibColumn::NUM_BASE_COLUMNS;
+    const NUM_RANDOMIZER_ROWS: usize = 2;
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = Fp;
+
+    fn gen_hints(_trace_len: usize, result: &Fp, _: &Challenges<Fp>) -> Hints<Fp> {
+        Hints::new(vec![(0, *result)])
+    }
+
+    fn constraints(
+        trace_domain: &Radix2EvaluationDomain<Fp>,
+    ) -> Vec<Constraint<FieldVariant<Fp, Fp>>> {
+        use AlgebraicItem::*;
+        use FibColumn::*;
+        let trace_len = trace_domain.size();
+        let result_row = trace_len - 1 - Self::NUM_RANDOMIZER_ROWS;
+        let one = Constant(FieldVariant::Fp(Fp::one()));
+
+        let boundary_constraints = [A.curr() - one, B.curr() - one]
+            .into_iter()
+            .map(|constraint| constraint / row_divisor(trace_domain, 0));
+
+        // transitions out of the result row would read randomizers
+        let exempt_rows = Vec::from_iter(result_row..trace_len);
+        let transition_constraints = [
+            A.next() - (A.curr() + B.curr()),
+            B.next() - (A.next() + B.curr()),
+        ]
+        .into_iter()
+        .map(|constraint| {
+            Constraint::transition_with_exempt_rows(constraint, trace_domain, &exempt_rows)
+        });
+
+        let terminal_constraint = (B.curr() - Hint(0)) / row_divisor(trace_domain, result_row);
+
+        boundary_constraints
+            .chain([terminal_constraint])
+            .map(Constraint::new)
+            .chain(transition_constraints)
+            .collect()
+    }
+}
+
+type ZkFibClaim = TestClaim<ZkFibAirConfig>;
+
+/// Public coin that draws a fixed sequence of field elements
+#[derive(Debug)]
+struct ScriptedCoin(Vec<Fp>);
+
+impl PublicCoin for ScriptedCoin {
+    type Digest = SerdeOutput<Sha256>;
+    type Field = Fp;
+
+    fn new(_digest: Self::Digest) -> Self {
+        Self(Vec::new())
+    }
+
+    fn reseed_with_digest(&mut self, _val: &Self::Digest) {}
+
+    fn reseed_with_field_elements(&mut self, _vals: &[Fp]) {}
+
+    fn reseed_with_int(&mut self, _val: u64) {}
+
+    fn reseed_with_bytes(&mut self, _bytes: &[u8]) {}
+
+    fn draw(&mut self) -> Fp {
+        assert!(!self.0.is_empty(), "ScriptedCoin ran out of scripted draws");
+        self.0.remove(0)
+    }
+
+    fn draw_queries(&mut self, _max_n: usize, _domain_size: usize) -> BTreeSet<usize> {
+        panic!("ScriptedCoin only scripts field element draws, not queries")
+    }
+
+    fn verify_proof_of_work(&self, _proof_of_work_bits: u8, _nonce: u64) -> bool {
+        panic!("ScriptedCoin can't check proof of work")
+    }
+
+    fn security_level_bits() -> u32 {
+        0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CoinOp {
+    AbsorbDigest,
+    AbsorbElements,
+    AbsorbInt,
+    AbsorbBytes,
+    Draw,
+    DrawQueries,
+}
+
+/// Public coin that logs every absorb and draw made on the transcript
+#[derive(Debug)]
+struct RecordingCoin {
+    coin: PublicCoinImpl<Fp, Sha256HashFn>,
+    log: Arc<Mutex<Vec<CoinOp>>>,
+}
+
+impl RecordingCoin {
+    fn record(&self, op: CoinOp) {
+        self.log.lock().unwrap().push(op);
+    }
+}
+
+impl PublicCoin for RecordingCoin {
+    type Digest = SerdeOutput<Sha256>;
+    type Field = Fp;
+
+    fn new(digest: Self::Digest) -> Self {
+        Self {
+            coin: PublicCoinImpl::new(digest),
+            log: Arc::default(),
+        }
+    }
+
+    fn reseed_with_digest(&mut self, val: &Self::Digest) {
+        self.record(CoinOp::AbsorbDigest);
+        self.coin.reseed_with_digest(val);
+    }
+
+    fn reseed_with_field_elements(&mut self, vals: &[Fp]) {
+        self.record(CoinOp::AbsorbElements);
+        self.coin.reseed_with_field_elements(vals);
+    }
+
+    fn reseed_with_int(&mut self, val: u64) {
+        self.record(CoinOp::AbsorbInt);
+        self.coin.reseed_with_int(val);
+    }
+
+    fn reseed_with_bytes(&mut self, bytes: &[u8]) {
+        self.record(CoinOp::AbsorbBytes);
+        self.coin.reseed_with_bytes(bytes);
+    }
+
+    fn draw(&mut self) -> Fp {
+        self.record(CoinOp::Draw);
+        self.coin.draw()
+    }
+
+    fn draw_queries(&mut self, max_n: usize, domain_size: usize) -> BTreeSet<usize> {
+        self.record(CoinOp::DrawQueries);
+        self.coin.draw_queries(max_n, domain_size)
+    }
+
+    fn verify_proof_of_work(&self, proof_of_work_bits: u8, nonce: u64) -> bool {
+        self.coin.verify_proof_of_work(proof_of_work_bits, nonce)
+    }
+
+    fn security_level_bits() -> u32 {
+        PublicCoinImpl::<Fp, Sha256HashFn>::security_level_bits()
+    }
+}
+
+ministark::air_columns! {
+    enum OverDeclaredColumn {
+        base: [Value],
+        extension: [Sum, Unused, Product],
+    }
+}
+
+/// AIR that declares an extension column its constraints never reference
+struct OverDeclaredAirConfig;
+
+impl AirConfig for OverDeclaredAirConfig {
+    const NUM_BASE_COLUMNS: usize = OverDeclaredColumn::NUM_BASE_COLUMNS;
+    const NUM_EXTENSION_COLUMNS: usize = OverDeclaredColumn::NUM_EXTENSION_COLUMNS;
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = ();
+
+    fn gen_hints(_trace_len: usize, _: &(), _: &Challenges<Fp>) -> Hints<Fp> {
+        Hints::default()
+    }
+
+    fn constraints(
+        trace_domain: &Radix2EvaluationDomain<Fp>,
+    ) -> Vec<Constraint<FieldVariant<Fp, Fp>>> {
+        use AlgebraicItem::*;
+        use OverDeclaredColumn::*;
+        let first_trace_x = Constant(FieldVariant::Fp(trace_domain.element(0)));
+        vec![Constraint::new(
+            (Sum.curr() - Value.curr() - Product.curr()) / (X - first_trace_x),
+        )]
+    }
+}
+
+ministark::air_columns! {
+    enum FixedColumn {
+        base: [Value],
+        extension: [],
+    }
+}
+
+/// AIR whose only column must match a fixed table of powers of two
+struct FixedAirConfig;
+
+impl AirConfig for FixedAirConfig {
+    const NUM_BASE_COLUMNS: usize = FixedColumn::NUM_BASE_COLUMNS;
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = ();
+
+    fn fixed_columns() -> Vec<Vec<Fp>> {
+        vec![powers_of_two(8)]
+    }
+
+    fn constraints(
+        trace_domain: &Radix2EvaluationDomain<Fp>,
+    ) -> Vec<Constraint<FieldVariant<Fp, Fp>>> {
+        use AlgebraicItem::*;
+        use FixedColumn::*;
+        let trace_len = trace_domain.size();
+        let last_trace_x = Constant(FieldVariant::Fp(trace_domain.element(trace_len - 1)));
+        let one = Constant(FieldVariant::Fp(Fp::one()));
+        vec![Constraint::new(
+            (Value.curr() - Fixed(0)) * ((X - last_trace_x) / (X.pow(trace_len) - one)),
+        )]
+    }
+}
+
+/// Range checks the first column to 8 bits
+fn range_checks() -> RangeCheckBuilder {
+    RangeCheckBuilder::new(1).check(0, 8)
+}
+
+/// AIR with a single 8-bit column
+struct RangeCheckedAirConfig;
+
+impl AirConfig for RangeCheckedAirConfig {
+    // the checked column followed by its 8 bit columns
+    const NUM_BASE_COLUMNS: usize = 1 + 8;
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = ();
+
+    fn constraints(
+        trace_domain: &Radix2EvaluationDomain<Fp>,
+    ) -> Vec<Constraint<FieldVariant<Fp, Fp>>> {
+        range_checks().constraints(trace_domain)
+    }
+}
+
+/// Claim about [`RangeCheckedAirConfig`] whose trace appends the bit columns
+/// to the witness
+fn range_checked_claim() -> TestClaim<RangeCheckedAirConfig> {
+    TestClaim::new(()).with_trace_generator(|FibTrace(mut matrix)| {
+        range_checks().append_columns(&mut matrix);
+        FibTrace(matrix)
+    })
+}
+
+ministark::air_columns! {
+    enum LookupColumn {
+        base: [Looking, Table, Multiplicity],
+        extension: [RunningSum],
+    }
+}
+
+const LOOKUP: LookupArgument = LookupArgument {
+    looking: LookupColumn::Looking as usize,
+    table: LookupColumn::Table as usize,
+    multiplicity: LookupColumn::Multiplicity as usize,
+    running_sum: LookupColumn::RunningSum as usize,
+    challenge: 0,
+};
+
+/// Trace whose looked up values must all be in its table column
+struct LookupTrace(Matrix<Fp>);
+
+impl Trace for LookupTrace {
+    type Fp = Fp;
+    type Fq = Fp;
+
+    fn base_columns(&self) -> &Matrix<Self::Fp> {
+        &self.0
+    }
+
+    fn build_extension_columns(&self, challenges: &Challenges<Fp>) -> Option<Matrix<Fp>> {
+        Some(Matrix::new(vec![
+            LOOKUP.build_running_sum(challenges, &self.0)
+        ]))
+    }
+}
+
+/// AIR whose only constraints are those of [`LOOKUP`]
+struct LookupAirConfig;
+
+impl AirConfig for LookupAirConfig {
+    const NUM_BASE_COLUMNS: usize = LookupColumn::NUM_BASE_COLUMNS;
+    const NUM_EXTENSION_COLUMNS: usize = LookupColumn::NUM_EXTENSION_COLUMNS;
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = ();
+
+    fn constraints(
+        trace_domain: &Radix2EvaluationDomain<Fp>,
+    ) -> Vec<Constraint<FieldVariant<Fp, Fp>>> {
+        LOOKUP.constraints(trace_domain)
+    }
+}
+
+type LookupClaim = TestClaim<
+    LookupAirConfig,
+    MatrixMerkleTreeImpl<Sha256HashFn>,
+    PublicCoinImpl<Fp, Sha256HashFn>,
+    LookupTrace,
+>;
+
+/// Returns a 16 row trace looking up `looking` in a table of `0..16`
+fn gen_lookup_trace(looking: [u64; 16]) -> LookupTrace {
+    let mut multiplicity = [0u64; 16];
+    for &value in &looking {
+        if let Some(m) = multiplicity.get_mut(value as usize) {
+            *m += 1;
+        }
+    }
+    let column = |values: [u64; 16]| values.map(Fp::from).to_vec_in(GpuAllocator);
+    LookupTrace(Matrix::new(vec![
+        column(looking),
+        column(core::array::from_fn(|i| i as u64)),
+        column(multiplicity),
+    ]))
+}
+
+/// AIR whose constraint reads 16 rows ahead which wraps around a 16 row trace
+struct WrappingAirConfig;
+
+impl AirConfig for WrappingAirConfig {
+    const NUM_BASE_COLUMNS: usize = 1;
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = ();
+
+    fn constraints(
+        trace_domain: &Radix2EvaluationDomain<Fp>,
+    ) -> Vec<Constraint<FieldVariant<Fp, Fp>>> {
+        use AlgebraicItem::*;
+        let trace_len = trace_domain.size();
+        let one = Constant(FieldVariant::Fp(Fp::one()));
+        let column = 0usize;
+        vec![Constraint::new(
+            (column.offset(16) - column.curr()) / (X.pow(trace_len) - one),
+        )]
+    }
+}
+
+/// AIR reading rows at offsets `-1` and `15` which are the same row of a 16
+/// row trace
+struct CongruentOffsetsAirConfig;
+
+impl AirConfig for CongruentOffsetsAirConfig {
+    const NUM_BASE_COLUMNS: usize = 1;
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = ();
+
+    fn constraints(
+        trace_domain: &Radix2EvaluationDomain<Fp>,
+    ) -> Vec<Constraint<FieldVariant<Fp, Fp>>> {
+        use AlgebraicItem::*;
+        let trace_len = trace_domain.size();
+        let one = Constant(FieldVariant::Fp(Fp::one()));
+        let column = 0usize;
+        vec![Constraint::new(
+            (column.offset(15) - column.offset(-1)) / (X.pow(trace_len) - one),
+        )]
+    }
+}
+
+/// AIR whose constraint is divided by `X - 3` which doesn't divide it
+struct NonDivisibleAirConfig;
+
+impl AirConfig for NonDivisibleAirConfig {
+    const NUM_BASE_COLUMNS: usize = 1;
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = ();
+
+    fn constraints(_: &Radix2EvaluationDomain<Fp>) -> Vec<Constraint<FieldVariant<Fp, Fp>>> {
+        use AlgebraicItem::*;
+        // 3¹⁶ ≠ 1 so `X - 3` doesn't vanish over a 16 row trace domain
+        let t = Constant(FieldVariant::Fp(Fp::from(3u8)));
+        let column = 0usize;
+        vec![Constraint::new(column.curr() / (X - t))]
+    }
+}
+
+fn powers_of_two(n: usize) -> Vec<Fp> {
+    std::iter::successors(Some(Fp::one()), |v| Some(v.double()))
+        .take(n)
+        .collect()
+}
+
+fn gen_fib_trace(n: usize) -> (FibClaim, FibTrace) {
+    let matrix = gen_fib_matrix::<Fp>(n);
+    let result = *matrix.0[1].last().unwrap();
+    (FibClaim::new(result), FibTrace(matrix))
+}
+
+fn gen_zk_fib_trace(n: usize) -> (ZkFibClaim, FibTrace) {
+    let matrix = gen_fib_matrix::<Fp>(n);
+    let result_row = n - 1 - ZkFibAirConfig::NUM_RANDOMIZER_ROWS;
+    let result = matrix.0[1][result_row];
+    (ZkFibClaim::new(result), FibTrace(matrix))
+}
+
+#[test]
+fn prove_and_verify_minimal_trace() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    claim.verify(proof, 0).unwrap();
+}
+
+#[test]
+fn flat_matrix_commitment_proves_and_verifies() {
+    let (claim, trace) = gen_fib_trace(16);
+    let claim =
+        TestClaim::<FibAirConfig, FlatMatrixCommitment<Sha256HashFn>>::new(claim.public_inputs);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    claim.verify(proof, 0).unwrap();
+}
+
+#[test]
+fn keccak_transcript_proves_and_verifies() {
+    let (claim, trace) = gen_fib_trace(16);
+    let claim = TestClaim::<
+        FibAirConfig,
+        MatrixMerkleTreeImpl<Keccak256HashFn>,
+        KeccakPublicCoin<Fp>,
+    >::new(claim.public_inputs);
+    let options = ProofOptions::new(16, 4, 4, 2, 4);
+
+    let mut proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    claim.verify(proof.clone(), 0).unwrap();
+
+    proof.pow_nonce += 1;
+    assert!(claim.verify(proof, 0).is_err());
+}
+
+#[test]
+fn truncated_merkle_nodes_give_smaller_proofs_that_verify() {
+    let (claim, trace) = gen_fib_trace(16);
+    let truncated_claim =
+        TestClaim::<FibAirConfig, TruncatedMatrixMerkleTree<Sha256HashFn, 20>>::new(
+            claim.public_inputs,
+        );
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let truncated_options = options.with_merkle_digest_bytes(20);
+
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    let (_, trace) = gen_fib_trace(16);
+    let truncated_proof =
+        pollster::block_on(truncated_claim.prove(truncated_options, trace)).unwrap();
+
+    assert!(truncated_proof.compressed_size() < proof.compressed_size());
+    let security_level_bits = truncated_proof.security_level_bits();
+    truncated_claim
+        .verify(truncated_proof, security_level_bits)
+        .unwrap();
+    let (_, trace) = gen_fib_trace(16);
+    assert!(matches!(
+        pollster::block_on(truncated_claim.prove(options, trace)),
+        Err(ProvingError::MerkleDigestBytesMismatch {
+            declared: None,
+            actual: Some(20)
+        })
+    ));
+}
+
+#[test]
+fn compiled_air_loaded_from_bytes_gives_identical_proofs() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let mut compiled_bytes = Vec::new();
+    FibAirConfig::compile(16)
+        .serialize_compressed(&mut compiled_bytes)
+        .unwrap();
+    let compiled = CompiledAir::deserialize_compressed(&*compiled_bytes).unwrap();
+    let mut context = ProverContext::from_compiled(compiled, claim.get_public_inputs(), options);
+
+    let proof = context.prove(&claim, trace, None).unwrap();
+
+    let (_, trace) = gen_fib_trace(16);
+    let expected = pollster::block_on(claim.prove(options, trace)).unwrap();
+    let (mut proof_bytes, mut expected_bytes) = (Vec::new(), Vec::new());
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+    expected.serialize_compressed(&mut expected_bytes).unwrap();
+    assert_eq!(expected_bytes, proof_bytes);
+}
+
+#[test]
+fn compiled_air_loaded_from_bytes_keeps_its_fixed_columns() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let mut compiled_bytes = Vec::new();
+    FixedAirConfig::compile(16)
+        .serialize_compressed(&mut compiled_bytes)
+        .unwrap();
+    let compiled = CompiledAir::deserialize_compressed(&*compiled_bytes).unwrap();
+
+    let loaded = Air::<FixedAirConfig>::from_compiled(compiled, (), options);
+
+    let expected = Air::<FixedAirConfig>::new(16, (), options);
+    assert_eq!(expected.fixed_columns(), loaded.fixed_columns());
+    assert_eq!(1, loaded.fixed_columns().len());
+    for (expected, actual) in zip(expected.constraints(), loaded.constraints()) {
+        assert!(**expected == **actual);
+    }
+}
+
+#[test]
+fn compiled_air_with_an_inconsistent_layout_fails_to_load() {
+    let mut compiled_bytes = Vec::new();
+    FibAirConfig::compile(16)
+        .serialize_compressed(&mut compiled_bytes)
+        .unwrap();
+    // the trace length comes first and the number of composition columns last
+    let mut bad_trace_len = compiled_bytes.clone();
+    bad_trace_len[..8].copy_from_slice(&17u64.to_le_bytes());
+    let mut bad_num_columns = compiled_bytes;
+    let num_columns_start = bad_num_columns.len() - 8;
+    bad_num_columns[num_columns_start] += 1;
+
+    for bytes in [bad_trace_len, bad_num_columns] {
+        let result = CompiledAir::<FibAirConfig>::deserialize_compressed(&*bytes);
+        assert!(result.is_err());
+    }
+}
+
+#[test]
+fn reused_prover_context_gives_identical_proofs() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let expected = pollster::block_on(claim.prove(options, trace)).unwrap();
+    let mut expected_bytes = Vec::new();
+    expected.serialize_compressed(&mut expected_bytes).unwrap();
+    let mut context = ProverContext::<FibClaim>::new(16, claim.get_public_inputs(), options);
+
+    for _ in 0..2 {
+        let (_, trace) = gen_fib_trace(16);
+        let proof = context.prove(&claim, trace, None).unwrap();
+        let mut proof_bytes = Vec::new();
+        proof.serialize_compressed(&mut proof_bytes).unwrap();
+        assert_eq!(expected_bytes, proof_bytes);
+    }
+}
+
+#[test]
+fn trace_of_min_length_proves_and_verifies() {
+    // 64 queries need an LDE domain of at least 64 positions
+    let options = ProofOptions::new(64, 4, 0, 2, 4);
+
+    let min_trace_len = Air::<FibAirConfig>::min_trace_len(options);
+
+    assert_eq!(16, min_trace_len);
+    let (claim, trace) = gen_fib_trace(min_trace_len);
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    claim.verify(proof, 0).unwrap();
+    let (claim, trace) = gen_fib_trace(min_trace_len / 2);
+    assert!(pollster::block_on(claim.prove(options, trace)).is_err());
+}
+
+#[test]
+fn proof_without_extension_columns_has_no_extension_commitment() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    assert!(proof.extension_trace_commitment.is_none());
+    assert!(proof.trace_queries.extension_trace_proof.is_none());
+    assert!(proof.trace_queries.extension_trace_values.is_empty());
+    claim.verify(proof, 0).unwrap();
+}
+
+#[test]
+fn randomized_proofs_are_reproducible_with_a_seeded_rng() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4).with_num_randomizers(2);
+    let prove = |seed| {
+        let (claim, trace) = gen_zk_fib_trace(16);
+        let claim = claim.with_randomizer_seed(seed);
+        let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+        let mut proof_bytes = Vec::new();
+        proof.serialize_compressed(&mut proof_bytes).unwrap();
+        claim.verify(proof, 0).unwrap();
+        proof_bytes
+    };
+
+    assert_eq!(prove(1), prove(1));
+    assert_ne!(prove(1), prove(2));
+}
+
+#[test]
+fn randomizers_fill_rows_the_air_leaves_unconstrained() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4).with_num_randomizers(2);
+    let (claim, trace) = gen_zk_fib_trace(16);
+
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    claim.verify(proof, 0).unwrap();
+}
+
+#[test]
+fn randomizers_over_constrained_rows_are_rejected() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4).with_num_randomizers(2);
+    let (claim, trace) = gen_fib_trace(16);
+
+    let result = pollster::block_on(claim.prove(options, trace));
+
+    assert!(matches!(
+        result,
+        Err(ProvingError::TooManyRandomizers {
+            num_randomizers: 2,
+            max_randomizers: 0,
+        })
+    ));
+}
+
+#[test]
+fn unreferenced_extension_columns_are_reported() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let air = Air::<OverDeclaredAirConfig>::new(16, (), options);
+
+    assert_eq!(
+        vec![OverDeclaredColumn::Unused.index()],
+        air.unused_extension_columns()
+    );
+}
+
+#[test]
+fn fri_layout_matches_proof() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    let layout = options.into_fri_options().layout(16 * 4);
+
+    assert_eq!(vec![64, 32], layout.layer_sizes);
+    assert_eq!(proof.fri_proof.layers.len(), layout.num_layers);
+    assert_eq!(
+        proof.fri_proof.remainder_coeffs.len(),
+        layout.remainder_size / 4
+    );
+}
+
+#[test]
+fn self_describing_proof_parameters_are_read_from_bytes() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    let bytes = proof.to_self_describing_bytes();
+
+    let header = ProofHeader::deserialize_compressed(&*bytes).unwrap();
+    assert_eq!(options, header.options);
+    assert_eq!(16, header.trace_len);
+    assert_eq!(vec![64, 32], header.fri_layout.layer_sizes);
+    let proof = Proof::<FibClaim>::from_self_describing_bytes(&bytes).unwrap();
+    claim.verify(proof, 0).unwrap();
+}
+
+#[test]
+fn proof_with_invalid_options_or_trace_len_fails_to_deserialize() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    let mut stalling_proof = proof.clone();
+    // a remainder of zero coefficients never finishes folding
+    stalling_proof.options.fri_max_remainder_coeffs = 0;
+    let mut overflowing_proof = proof;
+    overflowing_proof.trace_len = 1 << (usize::BITS - 1);
+
+    for proof in [stalling_proof, overflowing_proof] {
+        let mut bytes = Vec::new();
+        proof.serialize_compressed(&mut bytes).unwrap();
+
+        assert!(matches!(
+            Proof::<FibClaim>::deserialize_compressed(&*bytes),
+            Err(ark_serialize::SerializationError::InvalidData)
+        ));
+    }
+}
+
+/// Options used to only contain the number of queries, blowup and grinding
+/// factor. The fixture is a proof serialized by code from before the FRI
+/// parameters were added so it's never blessed from the current tree.
+/// Options with the legacy FRI parameters are still written in the legacy
+/// layout so the same statement proves to the same bytes.
+#[test]
+fn proof_with_legacy_options_layout_still_verifies() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 8, 64);
+    let legacy_bytes = read_test_vector("legacy_fib_proof.bin");
+    let legacy_proof = Proof::<FibClaim>::deserialize_compressed(&*legacy_bytes).unwrap();
+
+    assert_eq!([16, 4, 0], legacy_bytes[..3]);
+    assert_eq!(options, legacy_proof.options);
+    claim.verify(legacy_proof, 0).unwrap();
+
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    let mut bytes = Vec::new();
+    proof.serialize_compressed(&mut bytes).unwrap();
+    assert_eq!(legacy_bytes, bytes);
+}
+
+/// Options without truncated Merkle nodes are written in the layout from
+/// before the digest size was added so their transcripts don't change.
+#[test]
+fn options_without_merkle_digest_bytes_keep_their_layout() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let mut bytes = Vec::new();
+    options.serialize_compressed(&mut bytes).unwrap();
+    assert_eq!([0, 1], bytes[..2]);
+    assert_eq!(options.compressed_size(), bytes.len());
+    assert_eq!(
+        options,
+        ProofOptions::deserialize_compressed(&*bytes).unwrap()
+    );
+
+    let truncated = options.with_merkle_digest_bytes(20);
+    let mut truncated_bytes = Vec::new();
+    truncated
+        .serialize_compressed(&mut truncated_bytes)
+        .unwrap();
+    assert_eq!([0, 2], truncated_bytes[..2]);
+    assert_eq!(
+        truncated,
+        ProofOptions::deserialize_compressed(&*truncated_bytes).unwrap()
+    );
+}
+
+#[test]
+fn estimated_gpu_bytes_covers_the_trace_lde() {
+    let estimate = |lde_blowup_factor| {
+        let options = ProofOptions::new(16, lde_blowup_factor, 0, 2, 4);
+        Air::<FibAirConfig>::new(16, Fp::one(), options).estimated_gpu_bytes()
+    };
+
+    // two base columns, their polynomials and their LDE
+    let base_trace_bytes = 2 * (16 + 16 + 16 * 4) * std::mem::size_of::<Fp>();
+    assert!(estimate(4) > base_trace_bytes);
+    assert!(estimate(8) > estimate(4));
+}
+
+#[test]
+fn ood_point_is_redrawn_when_it_lands_on_a_domain() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let air = Air::<FibAirConfig>::new(16, Fp::one(), options);
+    let lde_point = air.lde_domain().element(5);
+    let trace_point = air.trace_domain().element(3);
+    let ce_point = air.ce_domain().element(1);
+    let fresh_point = Fp::from(7u8);
+    let mut coin = ScriptedCoin(vec![lde_point, trace_point, ce_point, fresh_point]);
+
+    assert_eq!(fresh_point, air.draw_ood_point(&mut coin));
+    assert!(coin.0.is_empty());
+}
+
+#[test]
+fn prover_and_verifier_follow_the_transcript_schedule() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let air = Air::<FibAirConfig>::new(16, claim.public_inputs, options);
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let claim = TestClaim::<FibAirConfig, MatrixMerkleTreeImpl<Sha256HashFn>, RecordingCoin>::new(
+        claim.public_inputs,
+    )
+    .with_public_coin({
+        let log = Arc::clone(&log);
+        move |public_coin: &mut RecordingCoin| public_coin.log = Arc::clone(&log)
+    });
+
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    let num_fri_layers = proof.fri_proof.layers.len();
+    let prover_log = std::mem::take(&mut *log.lock().unwrap());
+    claim.verify(proof, 0).unwrap();
+    let verifier_log = std::mem::take(&mut *log.lock().unwrap());
+
+    use CoinOp::*;
+    let num_coeffs = air.num_challenges() + air.num_composition_constraint_coeffs();
+    let num_deep_coeffs = air.trace_arguments().len() + air.num_composition_columns() + 2;
+    let mut expected = vec![AbsorbDigest];
+    expected.extend(repeat(Draw).take(num_coeffs));
+    expected.extend([AbsorbDigest, Draw, AbsorbElements]);
+    expected.extend(repeat(Draw).take(num_deep_coeffs));
+    expected.extend([AbsorbDigest, Draw].repeat(num_fri_layers));
+    expected.extend([AbsorbElements, DrawQueries]);
+    assert_eq!(expected, prover_log);
+    assert_eq!(expected, verifier_log);
+}
+
+#[test]
+fn verifier_trace_records_the_transcript_and_checks() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    let base_trace_commitment = proof.base_trace_commitment.clone();
+    let z = proof.ood_point(&claim);
+
+    let (result, verifier_trace) = claim.verify_with_trace(proof, 0);
+
+    let artifacts = result.unwrap();
+    let ops = verifier_trace.ops;
+    assert!(matches!(
+        &ops[0],
+        VerifierOp::AbsorbDigest { step: TranscriptStep::BaseTrace, digest }
+            if *digest == base_trace_commitment
+    ));
+    assert!(ops.contains(&VerifierOp::Draw {
+        step: TranscriptStep::OodPoint,
+        values: vec![z],
+    }));
+    assert!(ops.contains(&VerifierOp::DrawQueries {
+        nonce: 0,
+        positions: artifacts.query_positions,
+    }));
+    assert!(matches!(ops.last(), Some(VerifierOp::FriQueries { .. })));
+}
+
+#[test]
+fn verifier_trace_of_a_failed_fri_check_ends_at_the_deep_evaluations() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let mut proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    // the first layer still matches the DEEP evaluations
+    for value in &mut proof.fri_proof.layers[1].flattenend_rows {
+        *value += Fp::one();
+    }
+
+    let (result, verifier_trace) = claim.verify_with_trace(proof, 0);
+
+    assert!(result.is_err());
+    assert!(matches!(
+        verifier_trace.ops.last(),
+        Some(VerifierOp::DeepEvaluations { .. })
+    ));
+}
+
+#[test]
+fn transcript_log_matches_the_verifier_transcript() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let mut proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    let log = proof.transcript_log(&claim);
+
+    let (_, verifier_trace) = claim.verify_with_trace(proof.clone(), 0);
+    let verifier_log = verifier_trace.ops.into_iter().filter_map(|op| match op {
+        VerifierOp::AbsorbDigest { step, digest } => {
+            Some(TranscriptEvent::AbsorbDigest { step, digest })
+        }
+        VerifierOp::AbsorbElements { step, values } => {
+            Some(TranscriptEvent::AbsorbElements { step, values })
+        }
+        VerifierOp::Draw { step, values } => Some(TranscriptEvent::Draw { step, values }),
+        VerifierOp::DrawQueries { nonce, positions } => {
+            Some(TranscriptEvent::DrawQueries { nonce, positions })
+        }
+        _ => None,
+    });
+    assert_eq!(verifier_log.collect::<Vec<_>>(), log);
+    // the log of an invalid proof is still complete
+    proof.composition_trace_ood_evals[0] += Fp::one();
+    let log = proof.transcript_log(&claim);
+    assert!(matches!(
+        log.last(),
+        Some(TranscriptEvent::DrawQueries { .. })
+    ));
+}
+
+#[test]
+fn constraints_can_reference_uncommitted_fixed_columns() {
+    // the fixed column has 8 rows so it repeats over the 16 row trace
+    let values = powers_of_two(8).repeat(2);
+    let trace = FibTrace(Matrix::new(vec![values.to_vec_in(GpuAllocator)]));
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+
+    let claim = TestClaim::<FixedAirConfig>::new(());
+
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    // the verifier derives the number of committed columns from the openings
+    // so only the single witness column can have been committed
+    claim.verify(proof, 0).unwrap();
+}
+
+#[test]
+fn range_checked_trace_proves_and_verifies() {
+    let values = (0..16u64).map(|i| Fp::from(i * 17)).collect::<Vec<Fp>>();
+    let witness = FibTrace(Matrix::new(vec![values.to_vec_in(GpuAllocator)]));
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+
+    let claim = range_checked_claim();
+
+    let proof = pollster::block_on(claim.prove(options, witness)).unwrap();
+
+    claim.verify(proof, 0).unwrap();
+}
+
+#[test]
+fn out_of_range_value_fails_range_check() {
+    let mut values = vec![Fp::from(255u8); 16];
+    values[3] = Fp::from(256u16);
+    let witness = FibTrace(Matrix::new(vec![values.to_vec_in(GpuAllocator)]));
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+
+    let claim = range_checked_claim();
+
+    let result = pollster::block_on(claim.prove(options, witness));
+
+    // debug builds check the constraints before proving
+    match result {
+        Err(ProvingError::UnsatisfiedConstraint { row, .. }) => assert_eq!(3, row),
+        Ok(proof) => assert!(claim.verify(proof, 0).is_err()),
+        Err(err) => panic!("unexpected error: {err:?}"),
+    }
+}
+
+#[test]
+fn lookup_proves_and_verifies() {
+    let trace = gen_lookup_trace([3, 1, 3, 3, 15, 0, 7, 7, 2, 9, 9, 9, 9, 4, 12, 3]);
+    let options = ProofOptions::new(16, 8, 0, 2, 4);
+
+    let claim = LookupClaim::new(());
+
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    claim.verify(proof, 0).unwrap();
+}
+
+#[test]
+fn lookup_of_a_value_missing_from_the_table_fails() {
+    // 16 isn't in the table
+    let trace = gen_lookup_trace([3, 1, 3, 3, 15, 0, 7, 7, 2, 9, 16, 9, 9, 4, 12, 3]);
+    let options = ProofOptions::new(16, 8, 0, 2, 4);
+
+    let claim = LookupClaim::new(());
+
+    let result = pollster::block_on(claim.prove(options, trace));
+
+    // debug builds check the constraints before proving. The running sum only
+    // fails to end at zero on the last row.
+    match result {
+        Err(ProvingError::UnsatisfiedConstraint { row, .. }) => assert_eq!(15, row),
+        Ok(proof) => assert!(claim.verify(proof, 0).is_err()),
+        Err(err) => panic!("unexpected error: {err:?}"),
+    }
+}
+
+#[test]
+fn offsets_that_wrap_around_the_trace_are_rejected() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+
+    let wrapping_air = Air::<WrappingAirConfig>::new(16, (), options);
+    let air = Air::<WrappingAirConfig>::new(32, (), options);
+
+    assert_eq!(Some((0, (0, 16))), wrapping_air.find_out_of_range_offset());
+    assert_eq!(None, air.find_out_of_range_offset());
+}
+
+#[test]
+fn offsets_that_wrap_onto_the_same_row_are_rejected() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let constant_trace = |n| {
+        FibTrace(Matrix::new(
+            vec![vec![Fp::one(); n].to_vec_in(GpuAllocator)],
+        ))
+    };
+    let claim = TestClaim::<CongruentOffsetsAirConfig>::new(());
+    let air = Air::<CongruentOffsetsAirConfig>::new(16, (), options);
+    assert_eq!(Some((0, (0, 15))), air.find_out_of_range_offset());
+
+    let proving_result = pollster::block_on(claim.prove(options, constant_trace(16)));
+
+    assert!(matches!(
+        proving_result,
+        Err(ProvingError::OffsetOutOfRange { offset: 15, .. })
+    ));
+    // the offsets are different rows of a longer trace
+    let mut proof = pollster::block_on(claim.prove(options, constant_trace(32))).unwrap();
+    claim.verify(proof.clone(), 0).unwrap();
+    proof.trace_len = 16;
+    assert!(matches!(
+        claim.verify(proof, 0),
+        Err(VerificationError::OffsetOutOfRange { offset: 15, .. })
+    ));
+}
+
+#[test]
+fn exported_constraints_round_trip() {
+    let trace_domain = Radix2EvaluationDomain::new(16).unwrap();
+    let schema = FibAirConfig::export_constraints(16);
+    let mut bytes = Vec::new();
+    schema.serialize_compressed(&mut bytes).unwrap();
+
+    let deserialized = ConstraintSchema::deserialize_compressed(&*bytes).unwrap();
+
+    assert_eq!(schema, deserialized);
+    let eval = |constraint: &Constraint<FieldVariant<Fp, Fp>>| {
+        constraint.eval(&mut |leaf| match leaf {
+            AlgebraicItem::X => FieldVariant::Fp(Fp::from(7u8)),
+            AlgebraicItem::Constant(v) => *v,
+            AlgebraicItem::Hint(i) => FieldVariant::Fp(Fp::from(*i as u64 + 11)),
+            AlgebraicItem::Trace(col, offset) => {
+                FieldVariant::Fp(Fp::from(*col as u64 * 2 + *offset as u64 + 3))
+            }
+            _ => unreachable!(),
+        })
+    };
+    let constraints = FibAirConfig::constraints(&trace_domain);
+    let rebuilt = deserialized.to_constraints(&[]).unwrap();
+    assert_eq!(constraints.len(), rebuilt.len());
+    for (expected, actual) in zip(&constraints, &rebuilt) {
+        assert!(**expected == **actual);
+        assert_eq!(eval(expected), eval(actual));
+    }
+}
+
+#[test]
+fn schema_periodic_nodes_are_rebuilt_from_the_given_columns() {
+    use AlgebraicItem::*;
+    let coeffs = [1u8, 2].map(|v| FieldVariant::Fp(Fp::from(v)));
+    let column = PeriodicColumn::new(Vec::leak(coeffs.to_vec()), 4);
+    let other_column = PeriodicColumn::new(Vec::leak(coeffs[..1].to_vec()), 4);
+    let constraint = Constraint::new(0.curr() * Periodic(column));
+    let schema = ConstraintSchema::new(&[constraint.clone()]);
+
+    let rebuilt = schema.to_constraints(&[other_column, column]).unwrap();
+
+    assert!(*rebuilt[0] == *constraint);
+    assert_eq!(BTreeSet::from([column]), rebuilt[0].periodic_columns());
+    assert!(matches!(
+        schema.to_constraints(&[other_column]),
+        Err(SchemaError::UnknownPeriodicColumn { .. })
+    ));
+}
+
+#[test]
+fn schema_with_out_of_order_indices_is_rejected() {
+    let schema = FibAirConfig::export_constraints(16);
+    let num_nodes = schema.nodes.len();
+    let mut cyclic_schema = schema.clone();
+    // a node referencing itself would be read before it's built
+    cyclic_schema.nodes[num_nodes - 1] = SchemaNode::Neg(num_nodes - 1);
+    let mut dangling_schema = schema;
+    dangling_schema.constraints.push(num_nodes);
+
+    assert!(matches!(
+        cyclic_schema.to_constraints(&[]),
+        Err(SchemaError::ChildOutOfOrder { .. })
+    ));
+    assert!(matches!(
+        dangling_schema.to_constraints(&[]),
+        Err(SchemaError::RootOutOfRange { .. })
+    ));
+    for schema in [cyclic_schema, dangling_schema] {
+        let mut bytes = Vec::new();
+        schema.serialize_compressed(&mut bytes).unwrap();
+        let result = ConstraintSchema::<FieldVariant<Fp, Fp>>::deserialize_compressed(&*bytes);
+        assert!(result.is_err());
+    }
+}
+
+#[test]
+fn doctored_composition_commitment_is_inconsistent_with_the_constraints() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let mut proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    proof.composition_trace_commitment = CompositionCommitment::Root(Default::default());
+    let result = claim.verify(proof, 0);
+
+    assert!(matches!(
+        result,
+        Err(VerificationError::CompositionInconsistent)
+    ));
+}
+
+#[test]
+fn proof_only_verifies_with_its_randomness_beacon() {
+    let (claim, trace) = gen_fib_trace(16);
+    let result = claim.public_inputs;
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let claim = claim.with_randomness_beacon(Fp::from(7u8));
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    let other_beacon_result = FibClaim::new(result)
+        .with_randomness_beacon(Fp::from(8u8))
+        .verify(proof.clone(), 0);
+
+    assert!(matches!(
+        other_beacon_result,
+        Err(VerificationError::CompositionInconsistent)
+    ));
+    claim.verify(proof, 0).unwrap();
+}
+
+#[test]
+fn cancelled_proving_stops_at_the_next_checkpoint() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    // checkpoints after each of the 4 stages then before each of the 2 FRI layers
+    for n in 1..=6 {
+        let (claim, trace) = gen_fib_trace(16);
+        let checks = Arc::new(AtomicUsize::new(0));
+        let claim = claim.with_cancellation({
+            let checks = Arc::clone(&checks);
+            move || checks.fetch_add(1, Ordering::Relaxed) + 1 >= n
+        });
+
+        let result = pollster::block_on(claim.prove(options, trace));
+
+        assert!(matches!(result, Err(ProvingError::Cancelled)));
+        assert_eq!(n, checks.load(Ordering::Relaxed));
+    }
+    let (claim, trace) = gen_fib_trace(16);
+    let checks = AtomicUsize::new(0);
+    let claim = claim.with_cancellation(move || checks.fetch_add(1, Ordering::Relaxed) + 1 >= 7);
+    pollster::block_on(claim.prove(options, trace)).unwrap();
+}
+
+#[test]
+fn openings_from_another_transcript_are_rejected() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let (claim, trace) = gen_fib_trace(16);
+    let mut proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    // the domain separator changes the transcript so the same trace commitment
+    // is opened at different positions
+    let (_, trace) = gen_fib_trace(16);
+    let other_proof =
+        pollster::block_on(claim.prove_with_domain_separator(options, trace, b"other")).unwrap();
+    assert_eq!(
+        proof.base_trace_commitment,
+        other_proof.base_trace_commitment
+    );
+    proof.trace_queries = other_proof.trace_queries;
+
+    let result = claim.verify(proof, 0);
+
+    assert!(matches!(
+        result,
+        Err(VerificationError::BaseTraceQueryDoesNotMatchCommitment { .. })
+    ));
+}
+
+#[test]
+fn proof_over_another_trace_root_is_rejected() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let prove = |n| {
+        let (claim, trace) = gen_fib_trace(n);
+        let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+        (claim, proof)
+    };
+    let (claim, proof) = prove(16);
+    // a longer fib trace is a different witness with a different commitment
+    let (_, other_proof) = prove(32);
+    let trace_root = proof.base_trace_commitment.clone();
+    let other_trace_root = other_proof.base_trace_commitment;
+
+    let result = claim.verify_against_trace_root(proof.clone(), 0, &other_trace_root);
+
+    assert!(matches!(result, Err(VerificationError::TraceRootMismatch)));
+    claim
+        .verify_against_trace_root(proof, 0, &trace_root)
+        .unwrap();
+}
+
+#[test]
+fn low_degree_composition_is_a_single_column() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let air = Air::<FibAirConfig>::new(16, claim.get_public_inputs(), options);
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    assert_eq!(1, air.num_composition_columns());
+    assert_eq!(1, proof.composition_trace_ood_evals.len());
+    claim.verify(proof, 0).unwrap();
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn prove_with_stats_times_each_stage() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+
+    let (proof, stats) = pollster::block_on(claim.prove_with_stats(options, trace)).unwrap();
+
+    let stages = [
+        stats.base_trace_ns,
+        stats.extension_ns,
+        stats.constraint_eval_ns,
+        stats.composition_ns,
+        stats.deep_ns,
+        stats.fri_ns,
+    ];
+    assert!(stages.iter().all(|&ns| ns > 0), "{stats:?}");
+    assert!(stats.total_ns >= stages.iter().sum::<u64>(), "{stats:?}");
+    claim.verify(proof, 0).unwrap();
+}
+
+#[test]
+fn verify_with_stats_counts_merkle_paths() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    let (_, stats) = claim.verify_with_stats(proof, 0).unwrap();
+
+    // base and composition trace paths at every query plus FRI layer paths
+    assert!(stats.merkle_paths_checked > 0);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn verify_with_stats_times_each_stage() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    let (_, stats) = claim.verify_with_stats(proof, 0).unwrap();
+
+    let stages = [stats.commitment_check_ns, stats.ood_check_ns, stats.fri_ns];
+    assert!(stages.iter().all(|&ns| ns > 0), "{stats:?}");
+}
+
+#[test]
+fn verifier_context_is_reused_across_proofs() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let (claim, trace) = gen_fib_trace(16);
+    let mut context = VerifierContext::<FibClaim>::new(16, claim.get_public_inputs(), options);
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    context.verify(&claim, proof, 0, None).unwrap();
+
+    let (claim, trace) = gen_fib_trace(16);
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    context.verify(&claim, proof, 0, None).unwrap();
+
+    let (claim, trace) = gen_fib_trace(32);
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    let result = context.verify(&claim, proof, 0, None);
+    assert!(matches!(result, Err(VerificationError::ContextMismatch)));
+}
+
+#[test]
+fn verify_with_policy_enforces_minimum_grinding() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 8, 2, 4);
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    let policy = ProofPolicy {
+        min_queries: 16,
+        min_grinding_factor: 16,
+        ..ProofPolicy::default()
+    };
+    let relaxed_policy = ProofPolicy {
+        min_grinding_factor: 4,
+        ..policy
+    };
+
+    assert!(matches!(
+        claim.verify_with_policy(proof.clone(), &policy, 0),
+        Err(VerificationError::PolicyViolation {
+            source: PolicyViolation::InsufficientGrinding { .. }
+        })
+    ));
+    // meeting the policy doesn't lift the security floor
+    assert!(matches!(
+        claim.verify_with_policy(proof.clone(), &relaxed_policy, 128),
+        Err(VerificationError::InvalidProofSecurity)
+    ));
+    claim.verify_with_policy(proof, &relaxed_policy, 0).unwrap();
+}
+
+#[test]
+fn prove_and_verify_with_capped_fri_layers() {
+    let (claim, trace) = gen_fib_trace(16);
+    // without the cap the 64 element domain is folded twice
+    let options = ProofOptions::new(16, 4, 0, 2, 4).with_fri_max_num_layers(1);
+
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    assert_eq!(1, proof.fri_proof.layers.len());
+    claim.verify(proof, 0).unwrap();
+}
+
+#[test]
+fn prove_and_verify_with_coset_offset() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4).with_coset_offset_exponent(3);
+
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    claim.verify(proof, 0).unwrap();
+}
+
+#[test]
+fn direct_composition_proof_is_smaller() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let (claim, trace) = gen_fib_trace(16);
+    let committed_proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    let (claim, trace) = gen_fib_trace(16);
+    let direct_options = options.with_direct_composition(4);
+    let direct_proof = pollster::block_on(claim.prove(direct_options, trace)).unwrap();
+
+    assert!(direct_proof.compressed_size() < committed_proof.compressed_size());
+    claim.verify(committed_proof, 0).unwrap();
+    claim.verify(direct_proof, 0).unwrap();
+}
+
+#[test]
+fn ood_evals_recompute_the_composition_value() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    let air = Air::<FibAirConfig>::new(proof.trace_len, claim.public_inputs, options);
+
+    let ood_evals = proof.ood_evals(&claim);
+    let hints = air.gen_hints(&ood_evals.challenges);
+    let composition_value = ood_constraint_evaluation(
+        &ood_evals.composition_coeffs,
+        &ood_evals.challenges,
+        &hints,
+        &ood_evals.trace,
+        &air,
+        ood_evals.z,
+    );
+
+    assert_eq!(ood_evals.z, proof.ood_point(&claim));
+    assert_eq!(ood_evals.composition_value(), composition_value);
+}
+
+#[test]
+fn ood_evals_absorb_the_randomness_beacon() {
+    let (claim, trace) = gen_fib_trace(16);
+    let claim = claim.with_randomness_beacon(Fp::from(7u8));
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    let air = Air::<FibAirConfig>::new(proof.trace_len, claim.public_inputs, options);
+
+    let ood_evals = proof.ood_evals(&claim);
+    let hints = air.gen_hints(&ood_evals.challenges);
+    let composition_value = ood_constraint_evaluation(
+        &ood_evals.composition_coeffs,
+        &ood_evals.challenges,
+        &hints,
+        &ood_evals.trace,
+        &air,
+        ood_evals.z,
+    );
+
+    assert_eq!(ood_evals.composition_value(), composition_value);
+}
+
+#[test]
+fn receipts_identify_the_statement_and_the_proof() {
+    let (claim, trace) = gen_fib_trace(16);
+    let (_, same_trace) = gen_fib_trace(16);
+    let (_, other_trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let other_options = ProofOptions::new(20, 4, 0, 2, 4);
+
+    let (proof, receipt) = pollster::block_on(claim.prove_with_receipt(options, trace)).unwrap();
+    let (_, same_receipt) =
+        pollster::block_on(claim.prove_with_receipt(options, same_trace)).unwrap();
+    let (_, other_receipt) =
+        pollster::block_on(claim.prove_with_receipt(other_options, other_trace)).unwrap();
+
+    assert_eq!(claim.statement_hash(), receipt.statement_hash);
+    assert_eq!(receipt.statement_hash, other_receipt.statement_hash);
+    // the prover is deterministic so the same options give the same proof
+    assert_eq!(receipt.proof_hash, same_receipt.proof_hash);
+    assert_ne!(receipt.proof_hash, other_receipt.proof_hash);
+    assert_eq!(options, receipt.options);
+    #[cfg(feature = "std")]
+    assert_ne!(0, receipt.timestamp);
+    claim.verify(proof, 0).unwrap();
+}
+
+#[test]
+fn statement_hash_only_depends_on_the_claim() {
+    let (claim, _) = gen_fib_trace(16);
+    let (same_claim, _) = gen_fib_trace(16);
+    let (other_claim, _) = gen_fib_trace(32);
+
+    assert_eq!(claim.statement_hash(), same_claim.statement_hash());
+    assert_ne!(claim.statement_hash(), other_claim.statement_hash());
+}
+
+#[test]
+fn security_presets_meet_their_target() {
+    for preset in [
+        SecurityPreset::Fast80Bit,
+        SecurityPreset::Standard100Bit,
+        SecurityPreset::Conservative128Bit,
+    ] {
+        let options = ProofOptions::preset(preset);
+
+        assert!(options.fri_query_security_bits() >= preset.security_bits());
+    }
+}
+
+#[test]
+fn prove_picks_options_from_a_security_preset() {
+    let (claim, trace) = gen_fib_trace(16);
+    let preset = SecurityPreset::Fast80Bit;
+
+    let proof = ministark::prove(&claim, trace, preset).unwrap();
+
+    assert_eq!(ProofOptions::recommended(preset), proof.options);
+    claim.verify(proof, 0).unwrap();
+}
+
+#[test]
+fn fri_layer_inconsistent_with_deep_evaluations_is_rejected() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let mut proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    for value in &mut proof.fri_proof.layers[0].flattenend_rows {
+        *value += Fp::one();
+    }
+
+    let result = claim.verify(proof, 0);
+    assert!(matches!(
+        result,
+        Err(VerificationError::DeepFriMismatch { .. })
+    ));
+}
+
+#[test]
+fn proof_with_missing_fri_layer_is_malformed() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let mut proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    proof.check_well_formed(options).unwrap();
+
+    proof.fri_proof.layers.pop();
+
+    assert!(matches!(
+        proof.check_well_formed(options),
+        Err(MalformedProof::FriLayerCountMismatch {
+            expected: 2,
+            actual: 1
+        })
+    ));
+}
+
+#[test]
+fn proof_with_invalid_options_is_malformed() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    for invalid_options in [
+        ProofOptions {
+            fri_folding_factor: 1,
+            ..options
+        },
+        ProofOptions {
+            fri_max_remainder_coeffs: 0,
+            ..options
+        },
+        ProofOptions {
+            lde_blowup_factor: 3,
+            ..options
+        },
+    ] {
+        let mut proof = proof.clone();
+        proof.options = invalid_options;
+
+        assert!(matches!(
+            proof.check_well_formed(invalid_options),
+            Err(MalformedProof::InvalidOptions)
+        ));
+    }
+}
+
+#[test]
+fn proof_with_too_few_queries_violates_policy() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(8, 4, 0, 2, 4);
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    let policy = ProofPolicy {
+        min_queries: 32,
+        ..ProofPolicy::default()
+    };
+    let relaxed_policy = ProofPolicy {
+        min_queries: 8,
+        max_proof_size: Some(proof.compressed_size()),
+        ..policy
+    };
+
+    assert!(matches!(
+        policy.check(&proof),
+        Err(PolicyViolation::InsufficientQueries {
+            num_queries: 8,
+            min_queries: 32
+        })
+    ));
+    relaxed_policy.check(&proof).unwrap();
+}
+
+#[test]
+fn proof_with_invalid_options_violates_policy() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let mut proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    proof.options.fri_folding_factor = 0;
+    let policy = ProofPolicy {
+        max_proof_size: Some(usize::MAX),
+        ..ProofPolicy::default()
+    };
+
+    assert!(matches!(
+        policy.check(&proof),
+        Err(PolicyViolation::Malformed {
+            source: MalformedProof::InvalidOptions
+        })
+    ));
+}
+
+#[test]
+fn fri_layers_for_a_different_trace_length_are_rejected() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let mut proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+    // the LDE domain of a 32 row trace folds once more to the same remainder
+    proof.trace_len = 32;
+
+    assert!(matches!(
+        claim.verify(proof, 0),
+        Err(VerificationError::MalformedProof {
+            source: MalformedProof::FriLayerCountMismatch {
+                expected: 3,
+                actual: 2
+            }
+        })
+    ));
+}
+
+#[test]
+fn infeasible_fri_parameters_are_rejected() {
+    let (claim, trace) = gen_fib_trace(16);
+    // folding a 64 element domain by 8 overshoots the remainder
+    let options = ProofOptions::new(16, 4, 0, 8, 1);
+
+    let result = pollster::block_on(claim.prove(options, trace));
+
+    assert!(matches!(
+        result,
+        Err(ProvingError::InfeasibleFriParameters { .. })
+    ));
+}
+
+#[test]
+fn more_queries_than_lde_positions_are_rejected() {
+    let (claim, trace) = gen_fib_trace(16);
+    // the LDE domain only has 64 positions
+    let options = ProofOptions::new(128, 4, 0, 2, 4);
+
+    let result = pollster::block_on(claim.prove(options, trace));
+
+    assert!(matches!(
+        result,
+        Err(ProvingError::TooManyQueries {
+            requested: 128,
+            available: 64
+        })
+    ));
+}
+
+#[test]
+fn grinding_stops_when_budget_is_exhausted() {
+    let (claim, trace) = gen_fib_trace(16);
+    let claim = claim.with_grinding_budget(1000);
+    let options = ProofOptions::new(16, 4, 32, 2, 4);
+
+    let result = pollster::block_on(claim.prove(options, trace));
+
+    assert!(matches!(
+        result,
+        Err(ProvingError::GrindingExceededBudget {
+            max_attempts: 1000,
+            ..
+        })
+    ));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn unsatisfied_constraint_is_reported() {
+    let (claim, FibTrace(mut matrix)) = gen_fib_trace(16);
+    matrix.0[0][5] += Fp::one();
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+
+    let result = pollster::block_on(claim.prove(options, FibTrace(matrix)));
+
+    // the first transition constraint fails going from row 4 to row 5
+    assert!(matches!(
+        result,
+        Err(ProvingError::UnsatisfiedConstraint {
+            constraint: 2,
+            row: 4
+        })
+    ));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn constraint_not_divisible_by_its_denominator_is_reported() {
+    let column = vec![Fp::one(); 16].to_vec_in(GpuAllocator);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let claim = TestClaim::<NonDivisibleAirConfig>::new(());
+
+    let result = pollster::block_on(claim.prove(options, FibTrace(Matrix::new(vec![column]))));
+
+    // the constraint holds at every row since `X - 3` doesn't vanish there
+    assert!(matches!(
+        result,
+        Err(ProvingError::ConstraintNotDivisible { constraint: 0 })
+    ));
+}
+
+#[test]
+fn non_power_of_two_trace_is_an_error() {
+    let (claim, _) = gen_fib_trace(16);
+    let matrix = gen_fib_matrix::<Fp>(16);
+    let columns = matrix.0.into_iter().map(|mut column| {
+        column.truncate(12);
+        column
+    });
+    let trace = FibTrace(Matrix::new(columns.collect()));
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+
+    let result = pollster::block_on(claim.prove(options, trace));
+
+    assert!(matches!(result, Err(ProvingError::InvalidTrace { .. })));
+}
+
+#[test]
+fn constraints_referencing_private_hints_are_rejected() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let air = Air::<FibAirConfig>::new(16, Fp::one(), options);
+    let public_hints = Hints::new(vec![(0, Fp::one())]);
+    let private_hints = Hints::new_with_private(vec![], vec![(0, Fp::one())]);
+
+    assert_eq!(None, air.find_private_hint(&public_hints));
+    assert_eq!(Some(0), air.find_private_hint(&private_hints));
+}
+
+#[test]
+fn vanishing_evals_match_direct_evaluation() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4).with_coset_offset_exponent(3);
+    let air = Air::<FibAirConfig>::new(16, Fp::one(), options);
+    let c = Fp::from(7u64);
+
+    let evals = air.vanishing_evals(16, c);
+
+    let expected = air.lde_domain().elements().map(|x| x.pow([16]) - c);
+    assert!(evals.iter().copied().eq(expected));
+}
+
+/// The verifier batch inverts the DEEP denominators of all query points
+#[test]
+fn deep_composition_evaluations_match_naive_division() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let air = Air::<FibAirConfig>::new(16, Fp::one(), options);
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let mut rand = || Fp::rand(&mut rng);
+    let num_composition_columns = air.num_composition_columns();
+    let z = rand();
+    let ood_evals = air
+        .trace_arguments()
+        .into_iter()
+        .map(|argument| (argument, rand()))
+        .collect::<BTreeMap<_, _>>();
+    let composition_ood_evals = (0..num_composition_columns)
+        .map(|_| rand())
+        .collect::<Vec<_>>();
+    let coeffs = DeepCompositionCoeffs {
+        execution_trace: ood_evals.keys().map(|_| rand()).collect(),
+        composition_trace: (0..num_composition_columns).map(|_| rand()).collect(),
+        degree: (rand(), rand()),
+    };
+    let query_positions = [0, 5, 17, 63];
+    let base_rows = query_positions.map(|_| vec![rand(), rand()]);
+    let composition_rows = query_positions.map(|_| {
+        (0..num_composition_columns)
+            .map(|_| rand())
+            .collect::<Vec<_>>()
+    });
+
+    let evals = deep_composition_evaluations(
+        &air,
+        &query_positions,
+        &coeffs,
+        &base_rows.iter().map(Vec::as_slice).collect::<Vec<_>>(),
+        &[],
+        &composition_rows
+            .iter()
+            .map(Vec::as_slice)
+            .collect::<Vec<_>>(),
+        &ood_evals,
+        &composition_ood_evals,
+        z,
+    );
+
+    let lde_domain = air.lde_domain();
+    let g = air.trace_domain().group_gen();
+    let z_n = z.pow([num_composition_columns as u64]);
+    for (i, &position) in query_positions.iter().enumerate() {
+        let x = lde_domain.element(bit_reverse_index(lde_domain.size(), position));
+        let mut expected = Fp::ZERO;
+        for (j, (&(column, offset), &ood_eval)) in ood_evals.iter().enumerate() {
+            let shift = g.pow([offset.unsigned_abs() as u64]);
+            let shifted_z = if offset >= 0 { z * shift } else { z / shift };
+            expected +=
+                coeffs.execution_trace[j] * (base_rows[i][column] - ood_eval) / (x - shifted_z);
+        }
+        for (j, &value) in composition_rows[i].iter().enumerate() {
+            let ood_eval = composition_ood_evals[j];
+            expected += coeffs.composition_trace[j] * (value - ood_eval) / (x - z_n);
+        }
+        expected *= coeffs.degree.0 + coeffs.degree.1 * x;
+        assert_eq!(expected, evals[i], "query {position}");
+    }
+}
+
+#[test]
+fn composition_coeffs_from_seed_are_deterministic() {
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let air = Air::<FibAirConfig>::new(16, Fp::one(), options);
+    let seed = Sha256HashFn::hash_chunks([b"seed".as_slice()]);
+
+    type Coin = PublicCoinImpl<Fp, Sha256HashFn>;
+
+    let coeffs = air.composition_coeffs_from_seed::<Coin>(seed.clone());
+    let same_coeffs = air.composition_coeffs_from_seed::<Coin>(seed);
+
+    assert_eq!(air.num_composition_constraint_coeffs(), coeffs.len());
+    assert_eq!(coeffs, same_coeffs);
+}
+
+#[test]
+fn aggregate_proof_verifies_all_claims() {
+    let (claim_a, trace_a) = gen_fib_trace(16);
+    let (claim_b, trace_b) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let aggregate = Aggregate([claim_a, claim_b]);
+
+    let proof = pollster::block_on(aggregate.prove(options, [trace_a, trace_b])).unwrap();
+
+    aggregate.verify(proof, 0).unwrap();
+}
+
+#[test]
+fn aggregate_proof_keeps_the_randomizer_rows_of_its_claims() {
+    let (claim_a, trace_a) = gen_zk_fib_trace(16);
+    let (claim_b, trace_b) = gen_zk_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4).with_num_randomizers(2);
+    let aggregate = Aggregate([claim_a, claim_b]);
+
+    let proof = pollster::block_on(aggregate.prove(options, [trace_a, trace_b])).unwrap();
+
+    aggregate.verify(proof, 0).unwrap();
+}
+
+#[test]
+fn aggregate_proof_of_airs_with_fixed_columns_verifies() {
+    let trace = || {
+        let values = powers_of_two(8).repeat(2);
+        FibTrace(Matrix::new(vec![values.to_vec_in(GpuAllocator)]))
+    };
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let aggregate = Aggregate([
+        TestClaim::<FixedAirConfig>::new(()),
+        TestClaim::<FixedAirConfig>::new(()),
+    ]);
+
+    let proof = pollster::block_on(aggregate.prove(options, [trace(), trace()])).unwrap();
+
+    aggregate.verify(proof, 0).unwrap();
+}
+
+#[test]
+fn aggregate_proof_fails_if_any_claim_is_tampered() {
+    let (claim_a, trace_a) = gen_fib_trace(16);
+    let (claim_b, trace_b) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let proof =
+        pollster::block_on(Aggregate([claim_a, claim_b]).prove(options, [trace_a, trace_b]))
+            .unwrap();
+
+    let (claim_a, _) = gen_fib_trace(16);
+    let tampered_claim_b = FibClaim::new(Fp::one());
+    let tampered = Aggregate([claim_a, tampered_claim_b]);
+
+    assert!(tampered.verify(proof, 0).is_err());
+}
+
+/// Proof encoding and Fiat-Shamir derivation must not change by accident.
+/// Run with `MINISTARK_BLESS=1` to regenerate the blob after an intentional
+/// change to the proof format.
+#[test]
+fn proof_matches_test_vector() {
+    let (claim, trace) = gen_fib_trace(16);
+    // no randomizers so proving is deterministic
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    let mut proof_bytes = Vec::new();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+    claim.verify(proof, 0).unwrap();
+
+    assert_matches_test_vector("fib_proof.bin", &proof_bytes);
+}
+
+/// Each FRI folding challenge is drawn after absorbing its layer's commitment.
+/// The order is soundness critical so the challenges are pinned like the proof.
+#[test]
+fn fri_folding_challenges_match_test_vector() {
+    let (claim, trace) = gen_fib_trace(16);
+    let options = ProofOptions::new(16, 4, 0, 2, 4);
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    let seed = Sha256HashFn::hash_chunks([b"fri".as_slice()]);
+    let new_coin = || PublicCoinImpl::<Fp, Sha256HashFn>::new(seed.clone());
+
+    let challenges = proof.fri_proof.folding_challenges(&mut new_coin());
+    let mut swapped_proof = proof.fri_proof.clone();
+    swapped_proof.layers.swap(0, 1);
+    let swapped_challenges = swapped_proof.folding_challenges(&mut new_coin());
+
+    assert_eq!(2, challenges.len());
+    assert_ne!(challenges, swapped_challenges);
+    let mut challenge_bytes = Vec::new();
+    challenges
+        .serialize_compressed(&mut challenge_bytes)
+        .unwrap();
+    assert_matches_test_vector("fri_folding_challenges.bin", &challenge_bytes);
+}
+
+fn assert_matches_test_vector(name: &str, bytes: &[u8]) {
+    let path = test_vector_path(name);
+    if std::env::var_os("MINISTARK_BLESS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, bytes).unwrap();
+    }
+    let expected = read_test_vector(name);
+    assert!(
+        expected == bytes,
+        "{name} differs from {}. Regenerate with MINISTARK_BLESS=1 if the change is intended",
+        path.display()
+    );
+}
+
+fn read_test_vector(name: &str) -> Vec<u8> {
+    let path = test_vector_path(name);
+    std::fs::read(&path).unwrap_or_else(|err| {
+        panic!(
+            "can't read {name} from {}: {err}. Generate it with MINISTARK_BLESS=1",
+            path.display()
+        )
+    })
+}
+
+fn test_vector_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/vectors")
+        .join(name)
+}